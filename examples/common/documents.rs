@@ -0,0 +1,235 @@
+//! Documents built in memory, for the form field examples and tests.
+
+use lopdf::{dictionary, Document, Object, ObjectId, StringFormat};
+
+/// Two empty pages, like the output of a generator without form support.
+pub fn without_form() -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let page_ids: Vec<Object> = (0..2)
+        .map(|_| {
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            })
+            .into()
+        })
+        .collect();
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => 2,
+            "Kids" => page_ids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut pdf_data = Vec::new();
+    doc.save_to(&mut pdf_data).unwrap();
+    pdf_data
+}
+
+/// A page with a nested text field and two signature fields under the non-terminal field
+/// `approval`, which sets `FT` and `Ff` for them.
+pub fn nested_form() -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let page_id = doc.new_object_id();
+    let mut annots = vec![];
+
+    // customer.address.street, `DA`, `Q` and `DV` are inherited from `customer`.
+    let customer_id = doc.new_object_id();
+    let address_id = doc.new_object_id();
+    let street_id = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "Parent" => address_id,
+        "T" => text("street"),
+        "V" => text("Main Street"),
+        "Rect" => rect(50, 700),
+        "P" => page_id,
+    });
+    annots.push(street_id.into());
+    doc.objects.insert(
+        address_id,
+        Object::Dictionary(dictionary! {
+            "Parent" => customer_id,
+            "T" => text("address"),
+            "FT" => "Tx",
+            // Cycle, `customer` is not a kid of `address`.
+            "Kids" => vec![street_id.into(), customer_id.into()],
+        }),
+    );
+    doc.objects.insert(
+        customer_id,
+        Object::Dictionary(dictionary! {
+            "T" => text("customer"),
+            "DA" => text("/Helv 10 Tf 0 g"),
+            "Q" => 1,
+            "DV" => text("-"),
+            "Kids" => vec![address_id.into()],
+        }),
+    );
+
+    // approval.<user>, `FT` and `Ff` are inherited from `approval`.
+    let approval_id = doc.new_object_id();
+    let mut approval_kids = vec![];
+    for (index, user_id) in ["272", "292"].into_iter().enumerate() {
+        let field_id = doc.new_object_id();
+        let widget_id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Parent" => field_id,
+            "Rect" => rect(50, 500 - 100 * index as i64),
+            "P" => page_id,
+        });
+        annots.push(widget_id.into());
+        doc.objects.insert(
+            field_id,
+            Object::Dictionary(dictionary! {
+                "Parent" => approval_id,
+                "T" => user_field_name(user_id),
+                "Kids" => vec![widget_id.into()],
+            }),
+        );
+        approval_kids.push(field_id.into());
+    }
+    doc.objects.insert(
+        approval_id,
+        Object::Dictionary(dictionary! {
+            "T" => text("approval"),
+            "FT" => "Sig",
+            // `NoExport`
+            "Ff" => 2,
+            "Kids" => approval_kids,
+        }),
+    );
+
+    doc.objects.insert(
+        page_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            "Annots" => annots,
+        }),
+    );
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => 1,
+            "Kids" => vec![page_id.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "AcroForm" => dictionary! {
+            "Fields" => vec![Object::from(customer_id), approval_id.into()],
+        },
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut pdf_data = Vec::new();
+    doc.save_to(&mut pdf_data).unwrap();
+    pdf_data
+}
+
+/// Two pages, a merged signature field and widget on page 1, and a signature field
+/// with a widget on both pages.
+pub fn merged_widgets() -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let page_ids: Vec<ObjectId> = (0..2).map(|_| doc.new_object_id()).collect();
+    let mut annots = vec![vec![], vec![]];
+
+    // Merged field and widget, without `Type`, like most producers write it.
+    let merged_id = doc.add_object(dictionary! {
+        "Subtype" => "Widget",
+        "FT" => "Sig",
+        "T" => user_field_name("272"),
+        "F" => 4,
+        "Rect" => vec![50.into(), 700.into(), 250.into(), 760.into()],
+        "P" => page_ids[0],
+    });
+    annots[0].push(merged_id.into());
+
+    // One field, a widget on each page. `P` is optional, the page is found using `Annots`.
+    let field_id = doc.new_object_id();
+    let widget_ids: Vec<Object> = page_ids
+        .iter()
+        .enumerate()
+        .map(|(index, page_id)| {
+            let mut widget = dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Widget",
+                "Parent" => field_id,
+                "Rect" => vec![50.into(), 100.into(), (250 + 50 * index as i64).into(), 160.into()],
+            };
+            if index == 0 {
+                widget.set("P", *page_id);
+            }
+            let widget_id = doc.add_object(widget);
+            annots[index].push(widget_id.into());
+            widget_id.into()
+        })
+        .collect();
+    doc.objects.insert(
+        field_id,
+        Object::Dictionary(dictionary! {
+            "FT" => "Sig",
+            "T" => user_field_name("292"),
+            "Kids" => widget_ids,
+        }),
+    );
+
+    for (page_id, annots) in page_ids.iter().zip(annots) {
+        doc.objects.insert(
+            *page_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+                "Annots" => annots,
+            }),
+        );
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => 2,
+            "Kids" => page_ids.iter().map(|id| Object::from(*id)).collect::<Vec<_>>(),
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "AcroForm" => dictionary! {
+            "Fields" => vec![Object::from(merged_id), field_id.into()],
+        },
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut pdf_data = Vec::new();
+    doc.save_to(&mut pdf_data).unwrap();
+    pdf_data
+}
+
+pub fn text(text: &str) -> Object {
+    Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+}
+
+/// The field name `sign_document` looks for, base64 encoded JSON with the user id.
+pub fn user_field_name(user_id: &str) -> Object {
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    text(&BASE64_STANDARD.encode(format!("{{\"userId\":\"{}\"}}", user_id)))
+}
+
+fn rect(x: i64, y: i64) -> Object {
+    Object::Array(vec![x.into(), y.into(), (x + 200).into(), (y + 60).into()])
+}
@@ -0,0 +1,152 @@
+//! Test certificates and documents shared by the examples, included with `mod common;`.
+//!
+//! The certificates are generated when the example runs. Only the RSA test key is stored in
+//! `./examples/assets`, as `x509_certificate` can not generate RSA keys.
+
+#![allow(dead_code)]
+
+pub mod documents;
+pub mod revocation;
+
+use bcder::{encode::Values, BitString, Mode, Oid};
+use cryptographic_message_syntax::Bytes;
+use pdf_signing::{PdfSigner, SigningKeys};
+use std::sync::Arc;
+use x509_certificate::{
+    rfc5280, CapturedX509Certificate, EcdsaCurve, InMemorySigningKeyPair, KeyAlgorithm, Sign,
+    Signer, X509Certificate, X509CertificateBuilder,
+};
+
+/// A certificate extension: OID, critical and the DER encoded value.
+pub type Extension = (&'static str, bool, &'static [u8]);
+
+/// `basicConstraints` with `cA` set and no path length limit.
+pub const CA: Extension = ("2.5.29.19", true, &[0x30, 0x03, 0x01, 0x01, 0xff]);
+/// `basicConstraints` with `cA` set and `pathLenConstraint` 0.
+pub const CA_NO_SUB_CA: Extension = (
+    "2.5.29.19",
+    true,
+    &[0x30, 0x06, 0x01, 0x01, 0xff, 0x02, 0x01, 0x00],
+);
+/// `basicConstraints` without `cA`.
+pub const NOT_CA: Extension = ("2.5.29.19", true, &[0x30, 0x00]);
+/// `keyUsage` `keyCertSign` and `cRLSign`.
+pub const KEY_CERT_SIGN: Extension = ("2.5.29.15", true, &[0x03, 0x02, 0x01, 0x06]);
+/// `keyUsage` `digitalSignature` and `nonRepudiation`.
+pub const DIGITAL_SIGNATURE: Extension = ("2.5.29.15", true, &[0x03, 0x02, 0x06, 0xc0]);
+/// `keyUsage` `keyEncipherment`.
+pub const KEY_ENCIPHERMENT: Extension = ("2.5.29.15", true, &[0x03, 0x02, 0x05, 0x20]);
+/// `extendedKeyUsage` `emailProtection`.
+pub const EMAIL_PROTECTION: Extension = (
+    "2.5.29.37",
+    false,
+    &[
+        0x30, 0x0a, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x04,
+    ],
+);
+/// `extendedKeyUsage` `OCSPSigning`.
+pub const OCSP_SIGNING: Extension = (
+    "2.5.29.37",
+    false,
+    &[
+        0x30, 0x0a, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x09,
+    ],
+);
+/// `extendedKeyUsage` `timeStamping`, critical as required by RFC 3161.
+pub const TIME_STAMPING: Extension = (
+    "2.5.29.37",
+    true,
+    &[
+        0x30, 0x0a, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x08,
+    ],
+);
+
+/// A generated certificate and its key pair.
+pub struct TestCertificate {
+    pub certificate: CapturedX509Certificate,
+    pub key: InMemorySigningKeyPair,
+}
+
+impl TestCertificate {
+    /// A P-256 certificate, signed by `issuer` or self-signed.
+    pub fn new(
+        common_name: &str,
+        extensions: &[Extension],
+        issuer: Option<&TestCertificate>,
+    ) -> Self {
+        Self::with_key_algorithm(
+            common_name,
+            KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1),
+            extensions,
+            issuer,
+        )
+    }
+
+    /// The self-signed RSA 2048 test certificate, `pdf_cert.crt` and `pkcs8.pem`.
+    pub fn rsa() -> Self {
+        let certificate = std::fs::read_to_string("./examples/assets/pdf_cert.crt").unwrap();
+        let key = std::fs::read_to_string("./examples/assets/pkcs8.pem").unwrap();
+        TestCertificate {
+            certificate: CapturedX509Certificate::from_pem(certificate).unwrap(),
+            key: InMemorySigningKeyPair::from_pkcs8_pem(&key).unwrap(),
+        }
+    }
+
+    pub fn with_key_algorithm(
+        common_name: &str,
+        key_algorithm: KeyAlgorithm,
+        extensions: &[Extension],
+        issuer: Option<&TestCertificate>,
+    ) -> Self {
+        let mut builder = X509CertificateBuilder::default();
+        builder
+            .subject()
+            .append_common_name_utf8_string(common_name)
+            .unwrap();
+        builder.serial_number(rand::random::<u32>().into());
+        for (extension_oid, critical, value) in extensions {
+            builder.add_extension_der_data(oid(extension_oid), *critical, *value);
+        }
+        let (certificate, key) = builder.create_with_random_keypair(key_algorithm).unwrap();
+        let Some(issuer) = issuer else {
+            return TestCertificate { certificate, key };
+        };
+
+        // The builder always signs with the subject key, sign it again with the issuer key.
+        let mut certificate = AsRef::<rfc5280::Certificate>::as_ref(&certificate).clone();
+        let tbs_certificate = &mut certificate.tbs_certificate;
+        tbs_certificate.issuer = issuer.certificate.subject_name().clone();
+        tbs_certificate.signature = issuer.key.signature_algorithm().unwrap().into();
+        tbs_certificate.raw_data = None;
+        let mut tbs_der = vec![];
+        tbs_certificate
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut tbs_der)
+            .unwrap();
+        certificate.signature_algorithm = issuer.key.signature_algorithm().unwrap().into();
+        certificate.signature = BitString::new(0, Bytes::from(issuer.sign(&tbs_der)));
+        let certificate_der = X509Certificate::from(certificate).encode_der().unwrap();
+        TestCertificate {
+            certificate: CapturedX509Certificate::from_der(certificate_der).unwrap(),
+            key,
+        }
+    }
+
+    /// Sign `data` with the key of the certificate.
+    pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+        self.key.try_sign(data).unwrap().as_ref().to_vec()
+    }
+
+    pub fn into_signer(self) -> Arc<dyn PdfSigner> {
+        Arc::new(SigningKeys::new(self.key, self.certificate))
+    }
+}
+
+/// A signer with a self-signed P-256 certificate.
+pub fn signer() -> Arc<dyn PdfSigner> {
+    TestCertificate::new("PDF signing test", &[], None).into_signer()
+}
+
+pub fn oid(oid: &str) -> Oid {
+    oid.parse().unwrap()
+}
@@ -0,0 +1,161 @@
+//! CRLs and OCSP responses created locally, for the revocation examples and tests.
+
+use super::TestCertificate;
+use bcder::{encode::Values, Mode};
+use chrono::{DateTime, Duration, Utc};
+use x509_certificate::{CapturedX509Certificate, DigestAlgorithm};
+
+/// `ecdsa-with-SHA256` algorithm identifier.
+const ECDSA_SHA256: &[u8] = &[
+    0x30, 0x0a, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02,
+];
+/// `sha1` algorithm identifier.
+const SHA1: &[u8] = &[
+    0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00,
+];
+/// `id-pkix-ocsp-basic`
+const OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+/// Create a CRL that is valid for a week from `this_update`.
+pub fn create_crl(
+    issuer: &TestCertificate,
+    revoked: &[(&CapturedX509Certificate, DateTime<Utc>)],
+    this_update: DateTime<Utc>,
+) -> Vec<u8> {
+    let revoked: Vec<Vec<u8>> = revoked
+        .iter()
+        .map(|(certificate, revocation_time)| {
+            der(
+                0x30,
+                &[
+                    &der(0x02, &[certificate.serial_number_asn1().as_slice()]),
+                    &utc_time(*revocation_time),
+                ],
+            )
+        })
+        .collect();
+    let mut tbs_parts = vec![
+        der(0x02, &[&[1]]),
+        ECDSA_SHA256.to_vec(),
+        name_der(&issuer.certificate),
+        utc_time(this_update),
+        utc_time(this_update + Duration::weeks(1)),
+    ];
+    if !revoked.is_empty() {
+        tbs_parts.push(der(
+            0x30,
+            &revoked.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        ));
+    }
+    let tbs = der(
+        0x30,
+        &tbs_parts.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+    );
+    signed(issuer, &tbs, &[])
+}
+
+/// Create a basic OCSP response about `certificate`, signed by `responder`.
+pub fn create_ocsp_response(
+    responder: &TestCertificate,
+    issuer: &TestCertificate,
+    certificate: &CapturedX509Certificate,
+    revocation_time: Option<DateTime<Utc>>,
+    this_update: DateTime<Utc>,
+) -> Vec<u8> {
+    let sha1 = |data: &[u8]| DigestAlgorithm::Sha1.digest_data(data);
+    let cert_id = der(
+        0x30,
+        &[
+            SHA1,
+            &der(0x04, &[&sha1(&name_der(&issuer.certificate))]),
+            &der(0x04, &[&sha1(&issuer.certificate.public_key_data())]),
+            &der(0x02, &[certificate.serial_number_asn1().as_slice()]),
+        ],
+    );
+    let cert_status = match revocation_time {
+        Some(revocation_time) => der(0xa1, &[&generalized_time(revocation_time)]),
+        None => vec![0x80, 0x00],
+    };
+    let single_response = der(
+        0x30,
+        &[
+            &cert_id,
+            &cert_status,
+            &generalized_time(this_update),
+            &der(0xa0, &[&generalized_time(this_update + Duration::weeks(1))]),
+        ],
+    );
+    let responder_id = der(
+        0xa2,
+        &[&der(
+            0x04,
+            &[&sha1(&responder.certificate.public_key_data())],
+        )],
+    );
+    let tbs = der(
+        0x30,
+        &[
+            &responder_id,
+            &generalized_time(this_update),
+            &der(0x30, &[&single_response]),
+        ],
+    );
+    // The responder certificate is included.
+    let certs = der(
+        0xa0,
+        &[&der(0x30, &[responder.certificate.constructed_data()])],
+    );
+    let basic_response = signed(responder, &tbs, &[&certs]);
+    der(
+        0x30,
+        &[
+            &der(0x0a, &[&[0]]),
+            &der(
+                0xa0,
+                &[&der(
+                    0x30,
+                    &[&der(0x06, &[OCSP_BASIC]), &der(0x04, &[&basic_response])],
+                )],
+            ),
+        ],
+    )
+}
+
+/// `SEQUENCE { tbs, signatureAlgorithm, signature, extra... }`
+fn signed(issuer: &TestCertificate, tbs: &[u8], extra: &[&[u8]]) -> Vec<u8> {
+    let signature = der(0x03, &[&[0], &issuer.sign(tbs)]);
+    let mut parts = vec![tbs, ECDSA_SHA256, &signature];
+    parts.extend(extra);
+    der(0x30, &parts)
+}
+
+/// DER encode a value with a short tag.
+pub fn der(tag: u8, content: &[&[u8]]) -> Vec<u8> {
+    let content = content.concat();
+    let mut der = vec![tag];
+    match content.len() {
+        length @ 0..=0x7f => der.push(length as u8),
+        length @ 0x80..=0xff => der.extend([0x81, length as u8]),
+        length => der.extend([0x82, (length >> 8) as u8, length as u8]),
+    }
+    der.extend(content);
+    der
+}
+
+fn utc_time(time: DateTime<Utc>) -> Vec<u8> {
+    der(0x17, &[time.format("%y%m%d%H%M%SZ").to_string().as_bytes()])
+}
+
+fn generalized_time(time: DateTime<Utc>) -> Vec<u8> {
+    der(0x18, &[time.format("%Y%m%d%H%M%SZ").to_string().as_bytes()])
+}
+
+fn name_der(certificate: &CapturedX509Certificate) -> Vec<u8> {
+    let mut name = vec![];
+    certificate
+        .subject_name()
+        .encode_ref()
+        .write_encoded(Mode::Der, &mut name)
+        .unwrap();
+    name
+}
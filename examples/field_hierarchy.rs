@@ -0,0 +1,43 @@
+//! Sign a field that is nested in the field hierarchy and inherits its `FT` from its parent.
+//!
+//! The document is built in memory by `common::documents::nested_form`.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let pdf_data = common::documents::nested_form();
+    let document = PDFSigningDocument::read_from(&*pdf_data, "nested.pdf".to_owned()).unwrap();
+    let fields = document.form_fields().unwrap();
+    for field in &fields {
+        println!(
+            "{:?} {:?}: value {:?}, default {:?}, `DA` {:?}, `Q` {:?}",
+            field.field_type,
+            field.name,
+            field.value,
+            field.default_value,
+            field.default_appearance,
+            field.quadding
+        );
+    }
+
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, "nested.pdf".to_owned())
+        .unwrap()
+        .sign_document(vec![UserSignatureInfo::new(
+            "272",
+            "Charlie",
+            "charlie@test.com",
+            std::fs::read("./examples/assets/sig1.png").unwrap(),
+            signer,
+        )])
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    for field in document.form_fields().unwrap() {
+        if field.signed {
+            println!("Signed nested field {:?}", field.name);
+        }
+    }
+}
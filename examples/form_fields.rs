@@ -0,0 +1,60 @@
+//! List the form fields of a document, before and after signing one of them.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, Rectangle, UserFormSignatureInfo, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let mut document = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    let fields = document.form_fields().unwrap();
+    for field in &fields {
+        println!(
+            "{:?} {:?}: page {:?}, {} widgets, flags {}, value {:?}, signed {}",
+            field.field_type,
+            field.name,
+            field.page(),
+            field.widgets.len(),
+            field.flags,
+            field.value,
+            field.signed
+        );
+    }
+
+    // Add a field, then sign it.
+    let rect = Rectangle {
+        x1: 50.0,
+        y1: 50.0,
+        x2: 250.0,
+        y2: 120.0,
+    };
+    document
+        .add_signature_field(
+            1,
+            rect,
+            "Approval",
+            UserFormSignatureInfo {
+                user_id: "500".to_owned(),
+            },
+        )
+        .unwrap();
+    let pdf_file_data = document
+        .sign_document(vec![UserSignatureInfo::new(
+            "500",
+            "Charlie",
+            "charlie@test.com",
+            std::fs::read("./examples/assets/sig1.png").unwrap(),
+            signer,
+        )])
+        .unwrap();
+    let fields = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap();
+    for field in fields.iter().filter(|field| field.signed) {
+        println!("Signed {:?}", field.alternate_name);
+    }
+}
@@ -0,0 +1,45 @@
+//! Sign a signature field whose widget is merged into the field dictionary,
+//! and a signature field with a widget on each page.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let pdf_data = common::documents::merged_widgets();
+    let fields = PDFSigningDocument::read_from(&*pdf_data, "form.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap();
+    for field in &fields {
+        let widget_pages: Vec<_> = field.widgets.iter().map(|widget| widget.page).collect();
+        println!("{:?}: widgets on pages {:?}", field.name, widget_pages);
+    }
+
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| {
+            UserSignatureInfo::new(
+                user_id,
+                "Charlie",
+                "charlie@test.com",
+                std::fs::read("./examples/assets/sig1.png").unwrap(),
+                signer.clone(),
+            )
+        })
+        .collect();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, "form.pdf".to_owned())
+        .unwrap()
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    for signature in document.signatures().unwrap() {
+        println!(
+            "Signed {:?} on page {:?} at {:?}",
+            signature.field_name, signature.page, signature.rect
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Sign a document and list the objects the signature added or changed.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )];
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned())
+        .unwrap()
+        .sign_document(users_signature_info)
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let revisions = document.revisions().unwrap();
+    let signed_revision = revisions.last().unwrap();
+    let report = document
+        .revision_diff(signed_revision.index - 1, signed_revision.index)
+        .unwrap();
+    print!("{}", report);
+
+    // The report can be stored or sent as JSON.
+    let report = document.revision_diff(0, signed_revision.index).unwrap();
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
@@ -0,0 +1,63 @@
+//! Sign a document twice, list its revisions and extract the revision each signature covers.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| {
+            UserSignatureInfo::new(
+                user_id,
+                "Charlie",
+                "charlie@test.com",
+                std::fs::read("./examples/assets/sig1.png").unwrap(),
+                signer.clone(),
+            )
+        })
+        .collect();
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned())
+        .unwrap()
+        .sign_document(users_signature_info)
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+
+    // Each signature is in its own incremental update.
+    let revisions = document.revisions().unwrap();
+    for revision in &revisions {
+        println!(
+            "Revision {}: {} bytes, signatures {:?}",
+            revision.index,
+            revision.length,
+            revision
+                .signatures
+                .iter()
+                .map(|signature| &signature.field_name)
+                .collect::<Vec<_>>()
+        );
+    }
+    // The first signature covers the document before the second signature was added.
+    let first_signature = revisions
+        .iter()
+        .find_map(|revision| revision.signatures.first())
+        .unwrap();
+    let signed_revision = document.signed_revision(first_signature).unwrap();
+    let signed_data = signed_revision
+        .get_incr_document_ref()
+        .get_prev_documents_bytes();
+    println!(
+        "Signature {:?} covers the first {} bytes of {}",
+        first_signature.field_name,
+        signed_data.len(),
+        pdf_file_data.len()
+    );
+    for verification in signed_revision.verify_signatures().unwrap() {
+        println!("  {:?}", verification.status);
+    }
+}
@@ -0,0 +1,82 @@
+//! Check the revocation status of a signer with CRLs and OCSP responses embedded in the document.
+//!
+//! A root CA and a signer certificate are generated, the CRL and OCSP response are created
+//! locally. Nothing is fetched.
+
+mod common;
+
+use chrono::Duration;
+use common::revocation::{create_crl, create_ocsp_response};
+use common::{TestCertificate, CA, EMAIL_PROTECTION};
+use pdf_signing::{
+    PDFSigningDocument, PdfSigner, SigningKeys, TrustStore, UserSignatureInfo, ValidationData,
+};
+use std::sync::Arc;
+
+fn main() {
+    let root = TestCertificate::new("Test Root CA", &[CA], None);
+    let TestCertificate {
+        certificate: signer_cert,
+        key: signer_key,
+    } = TestCertificate::new("Charlie", &[EMAIL_PROTECTION], Some(&root));
+    let mut trust_store = TrustStore::new();
+    trust_store.add_anchor(root.certificate.clone());
+
+    let signer: Arc<dyn PdfSigner> = Arc::new(SigningKeys::new(signer_key, signer_cert.clone()));
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )];
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned())
+        .unwrap()
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let verification = pdf_signing_document.verify_signatures().unwrap().remove(0);
+    let chain = verification.validate_chain(&trust_store, None).unwrap();
+    let signing_time = chain.validation_time;
+
+    // Without revocation data the status is unknown, the trust anchor is not checked.
+    let revocations = verification.check_revocation(&chain, &ValidationData::default());
+    println!("No revocation data: {:?}", revocations[0].status);
+
+    // A CRL in the `DSS`, the certificate is revoked after signing.
+    let crl = create_crl(
+        &root,
+        &[(&signer_cert, signing_time + Duration::days(1))],
+        signing_time + Duration::days(2),
+    );
+    pdf_signing_document
+        .add_document_security_store(&ValidationData {
+            crls: vec![crl],
+            ..Default::default()
+        })
+        .unwrap();
+    let document_security_store = pdf_signing_document.document_security_store().unwrap();
+    let revocations = verification.check_revocation(&chain, &document_security_store);
+    println!(
+        "CRL in the DSS: {:?} {:?}",
+        revocations[0].status, revocations[0].source
+    );
+
+    // An OCSP response signed by the issuer.
+    let ocsp = create_ocsp_response(&root, &root, &signer_cert, None, signing_time);
+    let revocations = verification.check_revocation(
+        &chain,
+        &ValidationData {
+            ocsps: vec![ocsp],
+            ..Default::default()
+        },
+    );
+    println!(
+        "OCSP response: {:?} {:?}",
+        revocations[0].status, revocations[0].source
+    );
+}
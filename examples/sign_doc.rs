@@ -1,6 +1,5 @@
-use cryptographic_message_syntax::SignerBuilder;
-use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
-use std::{fs::File, io::Write};
+use pdf_signing::{PDFSigningDocument, PdfSigner, SigningKeys, UserSignatureInfo};
+use std::{fs::File, io::Write, sync::Arc};
 use x509_certificate::{CapturedX509Certificate, InMemorySigningKeyPair};
 
 fn main() {
@@ -14,60 +13,42 @@ fn main() {
     let x509_cert = CapturedX509Certificate::from_pem(cert).unwrap();
     let private_key_data = std::fs::read_to_string("./examples/assets/pkcs8.pem").unwrap();
     let private_key = InMemorySigningKeyPair::from_pkcs8_pem(&private_key_data).unwrap();
-    let signer = SignerBuilder::new(&private_key, x509_cert);
-    // Try using a time server. If it fails we continue without it.
+    let signer: Arc<dyn PdfSigner> = Arc::new(SigningKeys::new(private_key, x509_cert));
+    // Optionally add a time-stamp token from a time server to the signatures,
+    // with `.timestamp_provider(time_server.clone())`.
     // Alternative time servers:
     // 1: https://freetsa.org/tsr
     // 2: http://timestamp.digicert.com
-    // let signer_fallback = signer.clone();
-    // let signer = signer
-    //     .time_stamp_url("http://timestamp.digicert.com")
-    //     .or::<reqwest::Error>(Ok(signer_fallback))
-    //     .expect("Can not happen because of fallback.");
+    // let time_server = Arc::new(HttpTimestampProvider::new("http://timestamp.digicert.com"));
 
-    let users_signature_info = vec![
-        UserSignatureInfo {
-            user_id: "9".to_owned(),
-            user_name: "Alice".to_owned(),
-            user_email: "alice@test.com".to_owned(),
-            user_signature: std::fs::read("./examples/assets/sig1.png").unwrap(),
-            user_signing_keys: signer.clone(),
-        },
-        UserSignatureInfo {
-            user_id: "256".to_owned(),
-            user_name: "Bob".to_owned(),
-            user_email: "bob@test.com".to_owned(),
-            user_signature: std::fs::read("./examples/assets/sig2.png").unwrap(),
-            user_signing_keys: signer.clone(),
-        },
-        UserSignatureInfo {
-            user_id: "272".to_owned(),
-            user_name: "Charlie".to_owned(),
-            user_email: "charlie@test.com".to_owned(),
-            user_signature: std::fs::read("./examples/assets/sig1.png").unwrap(),
-            user_signing_keys: signer.clone(),
-        },
-        UserSignatureInfo {
-            user_id: "292".to_owned(),
-            user_name: "Dave".to_owned(),
-            user_email: "dave@test.com".to_owned(),
-            user_signature: std::fs::read("./examples/assets/sig3.png").unwrap(),
-            user_signing_keys: signer.clone(),
-        },
-        UserSignatureInfo {
-            user_id: "274".to_owned(),
-            user_name: "Ester".to_owned(),
-            user_email: "ester@test.com".to_owned(),
-            user_signature: std::fs::read("./examples/assets/sig2.png").unwrap(),
-            user_signing_keys: signer.clone(),
-        },
-    ];
+    let users_signature_info = [
+        ("9", "Alice", "sig1.png"),
+        ("256", "Bob", "sig2.png"),
+        ("272", "Charlie", "sig1.png"),
+        ("292", "Dave", "sig3.png"),
+        ("274", "Ester", "sig2.png"),
+    ]
+    .into_iter()
+    .map(|(user_id, user_name, signature_image)| {
+        UserSignatureInfo::new(
+            user_id,
+            user_name,
+            format!("{}@test.com", user_name.to_lowercase()),
+            std::fs::read(format!("./examples/assets/{}", signature_image)).unwrap(),
+            signer.clone(),
+        )
+    })
+    .collect();
 
     let mut pdf_signing_document =
         PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
     let pdf_file_data = pdf_signing_document
         .sign_document(users_signature_info)
         .unwrap();
+    // Add a document time-stamp over the signed document (PAdES B-LTA).
+    // let pdf_file_data = pdf_signing_document
+    //     .add_document_timestamp(time_server.as_ref())
+    //     .unwrap();
 
     let mut pdf_file = File::create("./examples/result.pdf").unwrap();
     pdf_file.write_all(&pdf_file_data).unwrap();
@@ -0,0 +1,71 @@
+//! Sign with a key on a PKCS#11 token, run with `cargo run --example sign_pkcs11 --features pkcs11`.
+//!
+//! SoftHSM can be used as token, so no hardware is needed:
+//! ```sh
+//! export SOFTHSM2_CONF=/tmp/softhsm2.conf
+//! mkdir -p /tmp/softhsm-tokens
+//! echo "directories.tokendir = /tmp/softhsm-tokens" > $SOFTHSM2_CONF
+//! softhsm2-util --init-token --free --label pdf-signing --pin 1234 --so-pin 5678
+//! # Key and certificate, see `Create_Cert.md` file.
+//! softhsm2-util --import pkcs8.pem --token pdf-signing --label signer --id 01 --pin 1234
+//! pkcs11-tool --module /usr/lib/softhsm/libsofthsm2.so --token-label pdf-signing \
+//!     --login --pin 1234 --write-object pdf_cert.der --type cert --label signer --id 01
+//! ```
+//! Intermediate and root certificates imported the same way (with another id)
+//! are added to the signature as the chain.
+//!
+//! Environment variables (with default):
+//! - `PKCS11_MODULE` (`/usr/lib/softhsm/libsofthsm2.so`)
+//! - `PKCS11_TOKEN` (`pdf-signing`)
+//! - `PKCS11_PIN` (`1234`)
+//! - `PKCS11_KEY_LABEL` (`signer`)
+
+use pdf_signing::{
+    PDFSigningDocument, PdfSigner, Pkcs11KeyId, Pkcs11Signer, SignatureFormat, UserSignatureInfo,
+};
+use std::{fs::File, io::Write, sync::Arc};
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_owned())
+}
+
+fn main() {
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+
+    let pkcs11_signer = Pkcs11Signer::new(
+        env_or("PKCS11_MODULE", "/usr/lib/softhsm/libsofthsm2.so"),
+        &env_or("PKCS11_TOKEN", "pdf-signing"),
+        &env_or("PKCS11_PIN", "1234"),
+        Pkcs11KeyId::Label(env_or("PKCS11_KEY_LABEL", "signer")),
+    )
+    .unwrap();
+    println!(
+        "Signing with `{}`, chain of {} certificate(s), algorithms: {:?}",
+        pkcs11_signer
+            .signing_certificate()
+            .subject_common_name()
+            .unwrap_or_default(),
+        pkcs11_signer.certificate_chain().len(),
+        pkcs11_signer.supported_algorithms()
+    );
+    let signer: Arc<dyn PdfSigner> = Arc::new(pkcs11_signer);
+
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )
+    .signature_format(SignatureFormat::EtsiCadesDetached)];
+
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    let pdf_file_data = pdf_signing_document
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    let mut pdf_file = File::create("./examples/result_pkcs11.pdf").unwrap();
+    pdf_file.write_all(&pdf_file_data).unwrap();
+}
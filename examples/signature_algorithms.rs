@@ -0,0 +1,42 @@
+//! Sign with a chosen digest and signature algorithm and check it with
+//! `PDFSigningDocument::verify_signatures`.
+//!
+//! The RSA test key is loaded from `./examples/assets/pkcs8.pem` and `pdf_cert.crt`
+//! (see `Create_Cert.md` file).
+
+mod common;
+
+use common::TestCertificate;
+use pdf_signing::{PDFSigningDocument, SignatureAlgorithm, SignatureFormat, UserSignatureInfo};
+use x509_certificate::DigestAlgorithm;
+
+fn main() {
+    let signer = TestCertificate::rsa().into_signer();
+    let signature_algorithm = SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha384);
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )
+    .signature_format(SignatureFormat::EtsiCadesDetached)
+    .signature_algorithm(signature_algorithm)];
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    let pdf_file_data = pdf_signing_document
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    for verification in document.verify_signatures().unwrap() {
+        println!(
+            "{:?}: {:?} with {:?}",
+            verification.field_name, verification.status, verification.signature_algorithm
+        );
+    }
+    println!("Signed with {}.", signature_algorithm);
+}
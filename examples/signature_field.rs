@@ -0,0 +1,81 @@
+//! Add signature fields to documents and sign them.
+//!
+//! A field is added to an already signed document, the existing signature stays valid.
+//! A document without `AcroForm` gets one with the new field.
+
+mod common;
+
+use pdf_signing::{
+    PDFSigningDocument, PdfSigner, Rectangle, UserFormSignatureInfo, UserSignatureInfo,
+};
+use std::sync::Arc;
+
+fn main() {
+    let signer = common::signer();
+    let rect = Rectangle {
+        x1: 50.0,
+        y1: 50.0,
+        x2: 250.0,
+        y2: 120.0,
+    };
+
+    // Sign an existing field, then add a field for another user and sign that one.
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned())
+        .unwrap()
+        .sign_document(vec![user_signature_info("272", signer.clone())])
+        .unwrap();
+    let mut document =
+        PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    document
+        .add_signature_field(1, rect.clone(), "Approval", user_form_info("500"))
+        .unwrap();
+    let pdf_file_data = document
+        .sign_document(vec![user_signature_info("500", signer.clone())])
+        .unwrap();
+    print_signatures(&pdf_file_data);
+
+    // A document without `AcroForm`.
+    let pdf_data = common::documents::without_form();
+    let mut document = PDFSigningDocument::read_from(&*pdf_data, "no-form.pdf".to_owned()).unwrap();
+    document
+        .add_signature_field(2, rect, "Second page", user_form_info("600"))
+        .unwrap();
+    let pdf_file_data = document
+        .sign_document(vec![user_signature_info("600", signer)])
+        .unwrap();
+    print_signatures(&pdf_file_data);
+}
+
+fn print_signatures(pdf_file_data: &[u8]) {
+    let document = PDFSigningDocument::read_from(pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    for signature in document.signatures().unwrap() {
+        println!(
+            "Field {:?} on page {:?}",
+            signature.field_name, signature.page
+        );
+    }
+    let verifications = document.verify_signatures().unwrap();
+    let valid = verifications
+        .iter()
+        .filter(|verification| verification.is_valid())
+        .count();
+    println!("{} of {} signatures valid", valid, verifications.len());
+}
+
+fn user_form_info(user_id: &str) -> UserFormSignatureInfo {
+    UserFormSignatureInfo {
+        user_id: user_id.to_owned(),
+    }
+}
+
+fn user_signature_info(user_id: &str, signer: Arc<dyn PdfSigner>) -> UserSignatureInfo {
+    UserSignatureInfo::new(
+        user_id,
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )
+}
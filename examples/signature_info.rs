@@ -0,0 +1,50 @@
+//! Sign a document and list the signatures with `PDFSigningDocument::signatures`.
+
+mod common;
+
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = common::signer();
+
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| {
+            UserSignatureInfo::new(
+                user_id,
+                "Charlie",
+                "charlie@test.com",
+                std::fs::read("./examples/assets/sig1.png").unwrap(),
+                signer.clone(),
+            )
+        })
+        .collect();
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned())
+        .unwrap()
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    let signatures = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .signatures()
+        .unwrap();
+    for signature in &signatures {
+        println!(
+            "{:?} on page {:?} at {:?}, signed by {:?} at {:?}",
+            signature.field_name,
+            signature.page,
+            signature.rect,
+            signature.name,
+            signature.signing_time
+        );
+        println!(
+            "  {} {:?}, {} bytes of CMS",
+            signature.filter,
+            signature.sub_filter,
+            signature.contents.len()
+        );
+    }
+}
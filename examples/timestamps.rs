@@ -0,0 +1,72 @@
+//! Verify the time-stamp tokens of a signature and of a document time-stamp.
+//!
+//! A local time-stamping authority is used, its certificate is trusted with a `TrustStore`.
+
+mod common;
+
+use common::{TestCertificate, DIGITAL_SIGNATURE, TIME_STAMPING};
+use pdf_signing::{
+    LocalTimestampAuthority, PDFSigningDocument, PdfSigner, SignatureFormat, TimestampProvider,
+    TrustStore, UserSignatureInfo,
+};
+use std::sync::Arc;
+
+fn main() {
+    let signer = TestCertificate::new("Charlie", &[DIGITAL_SIGNATURE], None).into_signer();
+    let tsa = TestCertificate::new("Test TSA", &[DIGITAL_SIGNATURE, TIME_STAMPING], None);
+    let mut trust_store = TrustStore::new();
+    trust_store.add_anchor(tsa.certificate.clone());
+    let tsa = LocalTimestampAuthority::new(tsa.into_signer());
+
+    // Signature with a `signature-time-stamp`.
+    let pdf_file_data = sign(signer, Arc::new(tsa.clone()));
+    let verification = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+        .remove(0);
+    for timestamp in &verification.timestamps {
+        let validation = timestamp.validate_chain(&trust_store, None).unwrap();
+        println!(
+            "Signature time-stamp: {:?} at {:?}, TSA {:?}",
+            timestamp.status, timestamp.gen_time, validation.status
+        );
+    }
+
+    // Document time-stamp over the signed document.
+    let mut document =
+        PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let pdf_file_data = document.add_document_timestamp(&tsa).unwrap();
+    let verifications = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap();
+    for verification in &verifications {
+        for timestamp in &verification.timestamps {
+            println!(
+                "{:?} time-stamp: {:?} at {:?}",
+                timestamp.kind, timestamp.status, timestamp.gen_time
+            );
+        }
+    }
+}
+
+fn sign(signer: Arc<dyn PdfSigner>, timestamp_provider: Arc<dyn TimestampProvider>) -> Vec<u8> {
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )
+    .signature_format(SignatureFormat::EtsiCadesDetached)
+    .timestamp_provider(timestamp_provider)];
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    pdf_signing_document
+        .sign_document(users_signature_info)
+        .unwrap()
+}
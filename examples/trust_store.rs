@@ -0,0 +1,86 @@
+//! Validate the certificate chain of a signature against a `TrustStore`.
+//!
+//! A root CA, an intermediate CA and a signer certificate are generated, the document is signed
+//! and the chain is checked: trusted, untrusted (unknown root) and expired.
+
+mod common;
+
+use common::{
+    TestCertificate, CA, CA_NO_SUB_CA, DIGITAL_SIGNATURE, EMAIL_PROTECTION, KEY_CERT_SIGN, NOT_CA,
+};
+use pdf_signing::{PDFSigningDocument, PdfSigner, SigningKeys, TrustStore, UserSignatureInfo};
+use std::sync::Arc;
+
+fn main() {
+    let root = TestCertificate::new("Test Root CA", &[CA, KEY_CERT_SIGN], None);
+    let intermediate = TestCertificate::new(
+        "Test Intermediate CA",
+        &[CA_NO_SUB_CA, KEY_CERT_SIGN],
+        Some(&root),
+    );
+    let TestCertificate {
+        certificate: signer_cert,
+        key: signer_key,
+    } = TestCertificate::new(
+        "Charlie",
+        &[NOT_CA, DIGITAL_SIGNATURE, EMAIL_PROTECTION],
+        Some(&intermediate),
+    );
+
+    // The intermediate is included in the signature.
+    let signer: Arc<dyn PdfSigner> = Arc::new(
+        SigningKeys::new(signer_key, signer_cert.clone())
+            .certificate_chain([intermediate.certificate.clone()]),
+    );
+    let pdf_file_data = sign(signer);
+    let verification = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+        .remove(0);
+
+    let mut trust_store = TrustStore::new();
+    trust_store
+        .add_anchors_pem(root.certificate.encode_pem().as_bytes())
+        .unwrap();
+    let validation = verification.validate_chain(&trust_store, None).unwrap();
+    println!("Known root: {:?}", validation.status);
+    for certificate in &validation.chain {
+        println!("  {:?}", certificate.subject_common_name());
+    }
+
+    let validation = verification
+        .validate_chain(&TrustStore::new(), None)
+        .unwrap();
+    println!(
+        "Unknown root: {:?} {:?}",
+        validation.status, validation.problems
+    );
+
+    let validation_time = signer_cert.validity_not_after() + chrono::Duration::days(1);
+    let validation = verification
+        .validate_chain(&trust_store, Some(validation_time))
+        .unwrap();
+    println!(
+        "After the certificates expired: {:?} {:?}",
+        validation.status, validation.problems
+    );
+}
+
+fn sign(signer: Arc<dyn PdfSigner>) -> Vec<u8> {
+    let users_signature_info = vec![UserSignatureInfo::new(
+        "272",
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )];
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    pdf_signing_document
+        .sign_document(users_signature_info)
+        .unwrap()
+}
@@ -0,0 +1,56 @@
+//! Sign a document, verify the signatures and list the changes made after each signature.
+//!
+//! A P-256 key and certificate are generated.
+
+mod common;
+
+use common::TestCertificate;
+use pdf_signing::{PDFSigningDocument, UserSignatureInfo};
+
+fn main() {
+    let signer = TestCertificate::new("PDF signing test", &[], None).into_signer();
+
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| {
+            UserSignatureInfo::new(
+                user_id,
+                "Charlie",
+                "charlie@test.com",
+                std::fs::read("./examples/assets/sig1.png").unwrap(),
+                signer.clone(),
+            )
+        })
+        .collect();
+
+    let pdf_file_name = "test-small-3sig.pdf";
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", pdf_file_name)).unwrap();
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*pdf_data, pdf_file_name.to_owned()).unwrap();
+    let pdf_file_data = pdf_signing_document
+        .sign_document(users_signature_info)
+        .unwrap();
+
+    for verification in PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+    {
+        println!(
+            "{:?}: {:?}, signed by {:?}",
+            verification.field_name,
+            verification.status,
+            verification
+                .signer_certificate
+                .and_then(|certificate| certificate.subject_common_name())
+        );
+        // Changes made in the incremental updates after this signature.
+        for modification in verification
+            .modifications
+            .iter()
+            .flat_map(|report| &report.modifications)
+        {
+            println!("  {:?}", modification);
+        }
+    }
+}
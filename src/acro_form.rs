@@ -1,19 +1,32 @@
 //! `AcroForm` is the section of a pdf used to store info about forms.
 //!
-#![allow(unused_variables)] // TODO: remove, but requires implementing `InheritableFields`
-#![allow(dead_code)] // TODO: remove, but requires implementing `InheritableFields
 
 use crate::PdfObjectDeref;
-use crate::{lopdf_utils, Error};
-use lopdf::{Document, Object, ObjectId};
+use crate::{lopdf_utils, Error, FieldValue, Rectangle};
+use chrono::{DateTime, Utc};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
 pub(crate) struct AcroForm {
     object_id: Option<ObjectId>,
     partial_field_name: Option<String>,
+    /// The partial names of the field and its parents, separated by periods.
+    fully_qualified_name: Option<String>,
     alternate_field_name: Option<String>,
+    /// Field flags (`Ff`).
+    field_flags: u32,
+    value: Option<FieldValue>,
+    /// Default value (`DV`).
+    default_value: Option<FieldValue>,
+    /// Default appearance string (`DA`) of text.
+    default_appearance: Option<String>,
+    /// Quadding (`Q`), the justification of text.
+    quadding: Option<i64>,
 
     form_component: FormComponent,
+    /// The attributes inherited from the parents, used to reload the field.
+    inherited: InheritableFields,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -47,14 +60,14 @@ pub(crate) enum FormComponent {
         /// This value should be used only when it is not possible to extract the
         /// name from the signature.
         name: Option<String>,
-        // `M` (date of signing) not implemented.
-        // `Location` not implemented.
-        // `Reason` not implemented.
-        // `ContactInfo` not implemented.
+        /// The time of signing (`M`).
+        signing_time: Option<DateTime<Utc>>,
+        location: Option<String>,
+        reason: Option<String>,
+        contact_info: Option<String>,
         // `R` not implemented. (deprecated)
         // `V` not implemented.
-        // `` not implemented.
-        prod_build: Option<SignBuildDictionary>,
+        prop_build: Option<SignBuildProperties>,
         prod_auth_time: Option<u64>,
         prod_auth_type: Option<String>,
     },
@@ -62,21 +75,102 @@ pub(crate) enum FormComponent {
     Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct SignBuildDictionary {
-    pub name: String,
-    pub date: String,
-    pub r: u32,
+/// Build properties (`Prop_Build`) of the software that created a signature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignBuildProperties {
+    /// The signature handler (`Filter`).
+    pub filter: Option<SignBuildDictionary>,
+    /// The `PubSec` software module.
+    pub pub_sec: Option<SignBuildDictionary>,
+    /// The application that created the signature (`App`).
+    pub app: Option<SignBuildDictionary>,
+}
+
+/// Build data dictionary of one software module, all entries are optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignBuildDictionary {
+    pub name: Option<String>,
+    /// Date of the build, no format is defined.
+    pub date: Option<String>,
+    /// Revision, often encoded in hex (`0x0001_0101` is 1.1.1).
+    pub r: Option<i64>,
     pub pre_release: bool,
     pub os: Vec<String>,
     pub non_e_font_no_warn: bool,
     pub trusted_mode: bool,
     // `V` not implemented. Deprecated in PDF 1.7
-    pub r_ex: String,
+    /// Revision as text, for example the version of the application.
+    pub r_ex: Option<String>,
+}
+
+impl SignBuildProperties {
+    /// Parse `Prop_Build`, entries that are not valid are ignored.
+    fn load(raw_doc: &Document, prop_build: &Object) -> Result<Self, Error> {
+        let prop_build = prop_build.deref(raw_doc)?.as_dict()?;
+        let load = |key: &[u8]| {
+            let dict = prop_build
+                .get(key)
+                .ok()?
+                .deref(raw_doc)
+                .ok()?
+                .as_dict()
+                .ok()?;
+            Some(SignBuildDictionary::load(dict))
+        };
+        Ok(SignBuildProperties {
+            filter: load(b"Filter"),
+            pub_sec: load(b"PubSec"),
+            app: load(b"App"),
+        })
+    }
+}
+
+impl SignBuildDictionary {
+    fn load(dict: &Dictionary) -> Self {
+        SignBuildDictionary {
+            name: lopdf_utils::as_option_name(dict.get(b"Name").ok())
+                .ok()
+                .flatten(),
+            date: lopdf_utils::as_option_decoded_text_string(dict.get(b"Date").ok())
+                .ok()
+                .flatten(),
+            r: lopdf_utils::as_option_integer(dict.get(b"R").ok())
+                .ok()
+                .flatten(),
+            pre_release: lopdf_utils::as_option_bool(dict.get(b"PreRelease").ok())
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            os: dict
+                .get(b"OS")
+                .and_then(Object::as_array)
+                .map(|list| {
+                    list.iter()
+                        .filter_map(|os| os.as_name_str().ok())
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            non_e_font_no_warn: lopdf_utils::as_option_bool(dict.get(b"NonEFontNoWarn").ok())
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            trusted_mode: lopdf_utils::as_option_bool(dict.get(b"TrustedMode").ok())
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            r_ex: lopdf_utils::as_option_decoded_text_string(dict.get(b"REx").ok())
+                .ok()
+                .flatten(),
+        }
+    }
 }
 
+/// Attributes of a non-terminal field that are used by its kids, unless a kid sets them.
 #[derive(Debug, Clone, Default)]
 struct InheritableFields {
+    /// Fully qualified name of the parent.
+    parent_name: Option<String>,
     /// Technically an enum.
     /// Allowed values:
     /// - `Btn` (Button)
@@ -84,6 +178,55 @@ struct InheritableFields {
     /// - `Ch` (Choice)
     /// - `Sig` (Signature) (PDF 1.3)
     ft: Option<String>,
+    ff: Option<i64>,
+    v: Option<Object>,
+    dv: Option<Object>,
+    da: Option<String>,
+    q: Option<i64>,
+}
+
+impl InheritableFields {
+    /// The field flags (`Ff`) of a field, or the inherited flags.
+    fn ff_of(&self, field_dict: &Dictionary) -> i64 {
+        field_dict
+            .get(b"Ff")
+            .and_then(Object::as_i64)
+            .ok()
+            .or(self.ff)
+            .unwrap_or(0)
+    }
+
+    /// The attributes for the kids of a field, the entries of the field replace the
+    /// inherited ones.
+    fn for_kids(
+        &self,
+        raw_doc: &Document,
+        field_dict: &Dictionary,
+        partial_field_name: Option<&str>,
+    ) -> Result<Self, Error> {
+        let get = |key: &[u8]| -> Result<Option<&Object>, Error> {
+            match field_dict.get(key) {
+                Ok(object) => Ok(Some(object.deref(raw_doc)?)),
+                Err(_) => Ok(None),
+            }
+        };
+        Ok(InheritableFields {
+            parent_name: match (&self.parent_name, partial_field_name) {
+                (Some(parent_name), Some(partial_field_name)) => {
+                    Some(format!("{}.{}", parent_name, partial_field_name))
+                }
+                (parent_name, partial_field_name) => parent_name
+                    .clone()
+                    .or_else(|| partial_field_name.map(str::to_owned)),
+            },
+            ft: lopdf_utils::as_option_name(get(b"FT")?)?.or_else(|| self.ft.clone()),
+            ff: lopdf_utils::as_option_integer(get(b"Ff")?)?.or(self.ff),
+            v: field_dict.get(b"V").ok().or(self.v.as_ref()).cloned(),
+            dv: field_dict.get(b"DV").ok().or(self.dv.as_ref()).cloned(),
+            da: lopdf_utils::as_option_text_string(get(b"DA")?)?.or_else(|| self.da.clone()),
+            q: lopdf_utils::as_option_integer(get(b"Q")?)?.or(self.q),
+        })
+    }
 }
 
 impl AcroForm {
@@ -115,41 +258,95 @@ impl AcroForm {
 
         // Fields can be a hierarchy, so need to be parsed this way.
         let empty_inherit_root = InheritableFields::default();
-        Self::load_field_list(raw_doc, fields_list, empty_inherit_root)
+        Self::load_field_list(
+            raw_doc,
+            fields_list,
+            &empty_inherit_root,
+            &mut HashSet::new(),
+        )
     }
 
-    /// For an AcroForm find a reference to the page it is on.
-    pub(crate) fn get_page_ref(&self, raw_doc: &Document) -> Result<ObjectId, Error> {
-        let mut object_id = None;
-        let kids = self.get_kids(raw_doc)?;
-
-        if let Some(kids) = kids {
-            for child in kids {
-                let child_dict = child.deref(raw_doc)?.as_dict()?;
-                // Child should be of `Type` `Annot` for Annotation.
-                if child_dict.has(b"P") {
-                    let child_page_ir = child_dict.get(b"P")?.get_object_id();
-                    if let Some(page_ir) = child_page_ir {
-                        // Found a reference, set as return value
-                        object_id = Some(page_ir);
+    /// The widget annotations of the field, the field itself when they are merged.
+    pub(crate) fn get_widgets(&self, raw_doc: &Document) -> Result<Vec<ObjectId>, Error> {
+        let self_object_id = self
+            .object_id
+            .ok_or_else(|| Error::from("AcroForm object is not a indirect reference."))?;
+        Self::get_field_widgets(raw_doc, self_object_id)
+    }
+
+    /// The widget annotations in the `Kids` of a field. When there are none,
+    /// the field itself if the field and widget dictionaries are merged.
+    pub(crate) fn get_field_widgets(
+        raw_doc: &Document,
+        field_id: ObjectId,
+    ) -> Result<Vec<ObjectId>, Error> {
+        let field_dict = raw_doc.get_object(field_id)?.as_dict()?;
+        let mut widgets = vec![];
+        if let Ok(kids) = field_dict.get(b"Kids") {
+            for kid in kids.deref(raw_doc)?.as_array()? {
+                if let Some(kid_id) = kid.get_object_id() {
+                    if Self::is_widget(raw_doc.get_object(kid_id)?.as_dict()?) {
+                        widgets.push(kid_id);
                     }
                 }
             }
         }
+        if widgets.is_empty() && Self::is_widget(field_dict) {
+            widgets.push(field_id);
+        }
+        Ok(widgets)
+    }
 
-        object_id.ok_or_else(|| Error::from("AcroForm: Page reference not found."))
+    fn is_widget(dict: &Dictionary) -> bool {
+        dict.get(b"Subtype")
+            .and_then(Object::as_name)
+            .map_or(dict.has(b"Rect"), |subtype| subtype == b"Widget")
     }
 
-    fn get_kids<'a>(&self, raw_doc: &'a Document) -> Result<Option<&'a Vec<Object>>, Error> {
-        let self_object_id = self
-            .object_id
-            .ok_or_else(|| Error::from("AcroForm object is not a indirect reference."))?;
+    /// The page number (starting at 1) of a widget, using `P` or the `Annots` of the pages.
+    pub(crate) fn get_widget_page_number(raw_doc: &Document, widget_id: ObjectId) -> Option<u32> {
+        let pages = raw_doc.get_pages();
+        let page_id = raw_doc
+            .get_object(widget_id)
+            .and_then(Object::as_dict)
+            .and_then(|dict| dict.get(b"P"))
+            .and_then(Object::as_reference)
+            .ok();
+        if let Some(page_number) = page_id.and_then(|page_id| {
+            pages
+                .iter()
+                .find(|(_, id)| **id == page_id)
+                .map(|(page_number, _)| *page_number)
+        }) {
+            return Some(page_number);
+        }
+        // `P` is optional, search the annotations of all pages.
+        pages.into_iter().find_map(|(page_number, page_id)| {
+            let annots = raw_doc
+                .get_dictionary(page_id)
+                .ok()?
+                .get(b"Annots")
+                .ok()?
+                .deref(raw_doc)
+                .ok()?
+                .as_array()
+                .ok()?;
+            annots
+                .iter()
+                .any(|annot| annot.get_object_id() == Some(widget_id))
+                .then_some(page_number)
+        })
+    }
 
-        let form_dict = raw_doc.get_object(self_object_id)?.as_dict()?;
-        if form_dict.has(b"Kids") {
-            Ok(Some(form_dict.get(b"Kids")?.as_array()?))
-        } else {
-            Ok(None)
+    /// The `Rect` of a widget.
+    pub(crate) fn get_widget_rect(
+        raw_doc: &Document,
+        widget_id: ObjectId,
+    ) -> Result<Option<Rectangle>, Error> {
+        let widget_dict = raw_doc.get_object(widget_id)?.as_dict()?;
+        match widget_dict.get(b"Rect") {
+            Ok(rect) => Rectangle::from_object(rect.deref(raw_doc)?),
+            Err(_) => Ok(None),
         }
     }
 
@@ -157,6 +354,30 @@ impl AcroForm {
         self.partial_field_name.as_deref()
     }
 
+    pub(crate) fn get_fully_qualified_name(&self) -> Option<&str> {
+        self.fully_qualified_name.as_deref()
+    }
+
+    pub(crate) fn get_field_flags(&self) -> u32 {
+        self.field_flags
+    }
+
+    pub(crate) fn get_value(&self) -> Option<&FieldValue> {
+        self.value.as_ref()
+    }
+
+    pub(crate) fn get_default_value(&self) -> Option<&FieldValue> {
+        self.default_value.as_ref()
+    }
+
+    pub(crate) fn get_default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    pub(crate) fn get_quadding(&self) -> Option<i64> {
+        self.quadding
+    }
+
     pub(crate) fn get_alternate_field_name(&self) -> Option<&str> {
         self.alternate_field_name.as_deref()
     }
@@ -174,110 +395,203 @@ impl AcroForm {
     }
 
     /// Parse a list if referenced in the hierarchy of `Root->AcroForm->Fields`.
+    /// Only the terminal fields are returned, the `Kids` of the other fields are parsed.
     ///
     /// There are properties that can be inherited from the parents.
+    /// Fields that were already `visited` are skipped, so cycles in `Kids` are not followed.
     fn load_field_list(
         raw_doc: &Document,
         list: &[Object],
-        inherit: InheritableFields,
+        inherit: &InheritableFields,
+        visited: &mut HashSet<ObjectId>,
     ) -> Result<Vec<Self>, Error> {
         // Create list for results
         let mut form_fields = vec![];
 
         for field in list {
-            let field_object_id = field.get_object_id();
+            if let Some(field_object_id) = field.get_object_id() {
+                if !visited.insert(field_object_id) {
+                    log::warn!(
+                        "AcroForm: Field {:?} is used more than once, ignored.",
+                        field_object_id
+                    );
+                    continue;
+                }
+            }
             let field_dict = field.deref(raw_doc)?.as_dict()?;
+            let child_fields = Self::get_child_fields(raw_doc, field_dict)?;
+            if child_fields.is_empty() {
+                if let Some(form_field) = Self::load_field(raw_doc, field, inherit)? {
+                    form_fields.push(form_field);
+                }
+            } else {
+                let partial_field_name =
+                    lopdf_utils::as_option_text_string(field_dict.get(b"T").ok())?;
+                let inherit_kids =
+                    inherit.for_kids(raw_doc, field_dict, partial_field_name.as_deref())?;
+                form_fields.extend(Self::load_field_list(
+                    raw_doc,
+                    &child_fields,
+                    &inherit_kids,
+                    visited,
+                )?);
+            }
+        }
+        Ok(form_fields)
+    }
 
-            // Check if it has the `FT` field.
-            if field_dict.has(b"FT") {
-                let component = match field_dict.get(b"FT")?.as_name()? {
-                    b"Btn" => {
-                        // Not implemented, ignored
-                        FormComponent::Button
-                    }
-                    b"Tx" => {
-                        // Not implemented, ignored
-                        FormComponent::Text
-                    }
-                    b"Ch" => {
-                        // Not implemented, ignored
-                        FormComponent::ComboBox
-                    }
-                    b"Sig" => {
-                        // We do not check or store all info according to the spec.
-                        // We do not check required fields or locks.
+    /// The `Kids` of a field that are fields, the other kids are its widget annotations.
+    fn get_child_fields(raw_doc: &Document, field_dict: &Dictionary) -> Result<Vec<Object>, Error> {
+        let kids = match field_dict.get(b"Kids") {
+            Ok(kids) => kids.deref(raw_doc)?.as_array()?,
+            Err(_) => return Ok(vec![]),
+        };
+        Ok(kids
+            .iter()
+            .filter(|kid| {
+                kid.deref(raw_doc)
+                    .ok()
+                    .and_then(|kid| kid.as_dict().ok())
+                    .is_some_and(|kid_dict| kid_dict.has(b"T") || !Self::is_widget(kid_dict))
+            })
+            .cloned()
+            .collect())
+    }
 
-                        // Check if `SV` (seed value) is set
-                        if field_dict.has(b"SV") {
-                            log::warn!("`SV` is not supported for signatures.");
-                        }
+    /// Load the field again, to get the changes made in a new incremental update.
+    pub(crate) fn reload(&self, raw_doc: &Document) -> Result<Self, Error> {
+        let object_id = self
+            .object_id
+            .ok_or_else(|| Error::from("AcroForm object is not a indirect reference."))?;
+        Self::load_field(raw_doc, &Object::Reference(object_id), &self.inherited)?
+            .ok_or_else(|| Error::from("AcroForm: Field no longer has a `FT`."))
+    }
+
+    /// Parse a single field, `None` when it has no `FT`.
+    fn load_field(
+        raw_doc: &Document,
+        field: &Object,
+        inherit: &InheritableFields,
+    ) -> Result<Option<Self>, Error> {
+        let field_object_id = field.get_object_id();
+        let field_dict = field.deref(raw_doc)?.as_dict()?;
 
-                        // Check if already signed
-                        if field_dict.has(b"V") {
-                            let sign_value_dict =
-                                field_dict.get(b"V")?.deref(raw_doc)?.as_dict()?;
-                            if sign_value_dict.has(b"Filter") || sign_value_dict.has(b"Contents") {
-                                // Signature is already signed.
-                                FormComponent::SignedSignature {
-                                    r#type: lopdf_utils::as_option_name(
-                                        sign_value_dict.get(b"Type").ok(),
-                                    )?,
-                                    filter: lopdf_utils::as_name(
-                                        sign_value_dict.get(b"Filter").ok(),
-                                    )?,
-                                    sub_filter: lopdf_utils::as_option_name(
-                                        sign_value_dict.get(b"SubFilter").ok(),
-                                    )?,
-                                    contents: lopdf_utils::as_byte_string(
-                                        sign_value_dict.get(b"Contents").ok(),
-                                    )?,
-                                    cert: lopdf_utils::as_array_or_byte_string(
-                                        sign_value_dict.get(b"Cert").ok(),
-                                    )
-                                    .ok(),
-                                    byte_range: lopdf_utils::as_byte_range(
-                                        sign_value_dict.get(b"ByteRange").ok(),
-                                    )?,
-                                    name: lopdf_utils::as_option_text_string(
-                                        sign_value_dict.get(b"Name").ok(),
-                                    )?,
-                                    prod_build: None, // TODO
-                                    prod_auth_time: lopdf_utils::as_option_integer(
-                                        sign_value_dict.get(b"Prop_AuthTime").ok(),
-                                    )?
-                                    .map(u64::try_from)
-                                    .transpose()?,
-                                    prod_auth_type: lopdf_utils::as_option_name(
-                                        sign_value_dict.get(b"Prop_AuthType").ok(),
-                                    )?,
+        // Check if it has the `FT` field, or inherits it.
+        let field_type = match lopdf_utils::as_option_name(field_dict.get(b"FT").ok())? {
+            Some(field_type) => field_type,
+            None => match &inherit.ft {
+                Some(field_type) => field_type.clone(),
+                None => return Ok(None),
+            },
+        };
+        let value = field_dict.get(b"V").ok().or(inherit.v.as_ref());
+        let component = match field_type.as_bytes() {
+            // Field flags: bit 16 `Radio`, bit 17 `Pushbutton`.
+            b"Btn" => match inherit.ff_of(field_dict) {
+                flags if flags & (1 << 16) != 0 => FormComponent::Button,
+                flags if flags & (1 << 15) != 0 => FormComponent::Radio,
+                _ => FormComponent::CheckBox,
+            },
+            b"Tx" => {
+                // Not implemented, ignored
+                FormComponent::Text
+            }
+            // Field flags: bit 18 `Combo`.
+            b"Ch" => match inherit.ff_of(field_dict) {
+                flags if flags & (1 << 17) != 0 => FormComponent::ComboBox,
+                _ => FormComponent::ListBox,
+            },
+            b"Sig" => {
+                // We do not check or store all info according to the spec.
+                // We do not check required fields or locks.
+
+                // Check if `SV` (seed value) is set
+                if field_dict.has(b"SV") {
+                    log::warn!("`SV` is not supported for signatures.");
+                }
+
+                // Check if already signed
+                if let Some(value) = value {
+                    let sign_value_dict = value.deref(raw_doc)?.as_dict()?;
+                    if sign_value_dict.has(b"Filter") || sign_value_dict.has(b"Contents") {
+                        // Signature is already signed.
+                        FormComponent::SignedSignature {
+                            r#type: lopdf_utils::as_option_name(sign_value_dict.get(b"Type").ok())?,
+                            filter: lopdf_utils::as_name(sign_value_dict.get(b"Filter").ok())?,
+                            sub_filter: lopdf_utils::as_option_name(
+                                sign_value_dict.get(b"SubFilter").ok(),
+                            )?,
+                            contents: lopdf_utils::as_byte_string(
+                                sign_value_dict.get(b"Contents").ok(),
+                            )?,
+                            cert: lopdf_utils::as_array_or_byte_string(
+                                sign_value_dict.get(b"Cert").ok(),
+                            )
+                            .ok(),
+                            byte_range: lopdf_utils::as_byte_range(
+                                sign_value_dict.get(b"ByteRange").ok(),
+                            )?,
+                            name: lopdf_utils::as_option_text_string(
+                                sign_value_dict.get(b"Name").ok(),
+                            )?,
+                            signing_time: lopdf_utils::as_option_date(
+                                sign_value_dict.get(b"M").ok(),
+                            ),
+                            location: lopdf_utils::as_option_decoded_text_string(
+                                sign_value_dict.get(b"Location").ok(),
+                            )?,
+                            reason: lopdf_utils::as_option_decoded_text_string(
+                                sign_value_dict.get(b"Reason").ok(),
+                            )?,
+                            contact_info: lopdf_utils::as_option_decoded_text_string(
+                                sign_value_dict.get(b"ContactInfo").ok(),
+                            )?,
+                            prop_build: match sign_value_dict.get(b"Prop_Build") {
+                                Ok(prop_build) => {
+                                    Some(SignBuildProperties::load(raw_doc, prop_build)?)
                                 }
-                            } else {
-                                FormComponent::EmptySignature
-                            }
-                        } else {
-                            FormComponent::EmptySignature
+                                Err(_) => None,
+                            },
+                            prod_auth_time: lopdf_utils::as_option_integer(
+                                sign_value_dict.get(b"Prop_AuthTime").ok(),
+                            )?
+                            .map(u64::try_from)
+                            .transpose()?,
+                            prod_auth_type: lopdf_utils::as_option_name(
+                                sign_value_dict.get(b"Prop_AuthType").ok(),
+                            )?,
                         }
+                    } else {
+                        FormComponent::EmptySignature
                     }
-                    unknown_type => {
-                        log::warn!(
-                            "Found an unknown `FT`: {}",
-                            String::from_utf8_lossy(unknown_type)
-                        );
-                        FormComponent::Unknown
-                    }
-                };
-                form_fields.push(AcroForm {
-                    object_id: field_object_id,
-                    partial_field_name: lopdf_utils::as_option_text_string(
-                        field_dict.get(b"T").ok(),
-                    )?,
-                    alternate_field_name: lopdf_utils::as_option_text_string(
-                        field_dict.get(b"TU").ok(),
-                    )?,
-                    form_component: component,
-                });
+                } else {
+                    FormComponent::EmptySignature
+                }
             }
-        }
-        Ok(form_fields)
+            unknown_type => {
+                log::warn!(
+                    "Found an unknown `FT`: {}",
+                    String::from_utf8_lossy(unknown_type)
+                );
+                FormComponent::Unknown
+            }
+        };
+        let partial_field_name = lopdf_utils::as_option_text_string(field_dict.get(b"T").ok())?;
+        let own = inherit.for_kids(raw_doc, field_dict, partial_field_name.as_deref())?;
+        Ok(Some(AcroForm {
+            object_id: field_object_id,
+            partial_field_name,
+            fully_qualified_name: own.parent_name,
+            alternate_field_name: lopdf_utils::as_option_text_string(field_dict.get(b"TU").ok())?,
+            field_flags: own.ff.unwrap_or(0) as u32,
+            value: value.and_then(|value| FieldValue::from_object(raw_doc, value)),
+            default_value: own
+                .dv
+                .and_then(|value| FieldValue::from_object(raw_doc, &value)),
+            default_appearance: own.da,
+            quadding: own.q,
+            form_component: component,
+            inherited: inherit.clone(),
+        }))
     }
 }
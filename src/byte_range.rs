@@ -1,5 +1,4 @@
 use crate::Error;
-use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone)]
 pub struct ByteRange(pub Vec<usize>);
@@ -26,29 +25,4 @@ impl ByteRange {
             ))
         }
     }
-
-    pub fn get_range(&self, range_pair_index: usize) -> RangeInclusive<usize> {
-        let index = range_pair_index * 2;
-        if index > self.0.len() {
-            panic!("Index range out of bounds: TODO");
-        }
-        self.0[index]..=self.0[index] + self.0[index + 1] - 1
-    }
-
-    pub fn get_capacity_inclusive(&self) -> usize {
-        let mut total = 0;
-        let mut offset_value = None;
-        for value in &self.0 {
-            match offset_value {
-                Some(_offset_value) => {
-                    total += value;
-                    offset_value = None;
-                }
-                None => {
-                    offset_value = Some(value);
-                }
-            }
-        }
-        total
-    }
 }
@@ -0,0 +1,129 @@
+//! Certification signatures (`DocMDP`).
+//!
+//! A certification signature is the first signature in a document and defines which
+//! changes are allowed after it. Structure:
+//! - Root (dictionary)
+//!   - Perms (dictionary)
+//!     - DocMDP (reference to the signature dictionary)
+//! - Signature dictionary
+//!   - Reference (array)
+//!     - SigRef (dictionary, `TransformMethod` = `DocMDP`)
+//!       - TransformParams (dictionary, `P` = permission level)
+
+use crate::acro_form::FormComponent;
+use crate::error::Error;
+use crate::pdf_object::PdfObjectDeref;
+use crate::PDFSigningDocument;
+use lopdf::{Object, ObjectId};
+
+/// The changes that are allowed after a certification signature (`P` in `TransformParams`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocMdpPermission {
+    /// `P` = 1: No changes to the document are permitted.
+    NoChanges,
+    /// `P` = 2: Filling in forms, instantiating page templates and signing.
+    FormFilling,
+    /// `P` = 3: `FormFilling` plus creating, deleting and modifying annotations.
+    FormFillingAndAnnotations,
+}
+
+impl DocMdpPermission {
+    /// The value of `P`.
+    pub fn p_value(&self) -> i64 {
+        match self {
+            DocMdpPermission::NoChanges => 1,
+            DocMdpPermission::FormFilling => 2,
+            DocMdpPermission::FormFillingAndAnnotations => 3,
+        }
+    }
+}
+
+impl TryFrom<i64> for DocMdpPermission {
+    type Error = Error;
+
+    fn try_from(p_value: i64) -> Result<Self, Error> {
+        match p_value {
+            1 => Ok(DocMdpPermission::NoChanges),
+            2 => Ok(DocMdpPermission::FormFilling),
+            3 => Ok(DocMdpPermission::FormFillingAndAnnotations),
+            _ => Err(Error::Other(format!(
+                "Invalid DocMDP permission `{}`, expected 1, 2 or 3.",
+                p_value
+            ))),
+        }
+    }
+}
+
+impl PDFSigningDocument {
+    /// A certification signature has to be the first signature in the document.
+    pub(crate) fn check_can_certify(&self) -> Result<(), Error> {
+        let has_signature = self.acro_form.iter().flatten().any(|form| {
+            matches!(
+                form.get_form_component(),
+                FormComponent::SignedSignature { .. }
+            )
+        });
+        if has_signature {
+            return Err(Error::from(
+                "Document already contains a signature, it can not be certified.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The `SigRef` dictionary for the `Reference` array of the signature dictionary.
+    pub(crate) fn doc_mdp_reference(permission: DocMdpPermission) -> Object {
+        use lopdf::Object::*;
+
+        Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("Type", Name("SigRef".as_bytes().to_vec())),
+            ("TransformMethod", Name("DocMDP".as_bytes().to_vec())),
+            (
+                "TransformParams",
+                Dictionary(lopdf::Dictionary::from_iter(vec![
+                    ("Type", Name("TransformParams".as_bytes().to_vec())),
+                    ("P", Integer(permission.p_value())),
+                    ("V", Name("1.2".as_bytes().to_vec())),
+                ])),
+            ),
+        ]))
+    }
+
+    /// Set `Perms/DocMDP` in the catalog to the certification signature dictionary.
+    pub(crate) fn add_doc_mdp_permission(
+        &mut self,
+        signature_dictionary_id: ObjectId,
+    ) -> Result<(), Error> {
+        use lopdf::Object::*;
+
+        let root_obj_id = self
+            .raw_document
+            .get_prev_documents()
+            .trailer
+            .get(b"Root")?
+            .as_reference()?;
+        self.raw_document
+            .opt_clone_object_to_new_document(root_obj_id)?;
+
+        // `Perms` can be a reference, the new value is always stored inline.
+        let prev_document = self.raw_document.get_prev_documents();
+        let mut perms = match self
+            .raw_document
+            .new_document
+            .get_object(root_obj_id)?
+            .as_dict()?
+            .get(b"Perms")
+        {
+            Ok(perms) => perms.deref(prev_document)?.as_dict()?.clone(),
+            Err(_) => lopdf::Dictionary::new(),
+        };
+        perms.set("DocMDP", Reference(signature_dictionary_id));
+
+        self.raw_document
+            .new_document
+            .get_object_mut(root_obj_id)?
+            .as_dict_mut()?
+            .set("Perms", Dictionary(perms));
+        Ok(())
+    }
+}
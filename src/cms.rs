@@ -0,0 +1,412 @@
+//! Creates the CMS `SignedData` structures used in signatures and time-stamp tokens.
+//!
+//! The `SignedDataBuilder` from `cryptographic_message_syntax` always adds a `signing-time`
+//! attribute, this is not allowed for PAdES signatures. So the structure is assembled here.
+
+use crate::{Error, PdfSigner, SignatureAlgorithm, SignatureFormat, TimestampProvider};
+use bcder::{
+    decode::Constructed,
+    encode::{self, PrimitiveContent, Values},
+    Captured, ConstOid, Mode, OctetString, Oid, Tag,
+};
+use chrono::{DateTime, Utc};
+use cryptographic_message_syntax::{
+    asn1::{
+        rfc3161::OID_TIME_STAMP_TOKEN,
+        rfc5652::{
+            CertificateChoices, CertificateSet, CmsVersion, DigestAlgorithmIdentifier,
+            DigestAlgorithmIdentifiers, EncapsulatedContentInfo, IssuerAndSerialNumber,
+            SignatureValue, SignedAttributes, SignedData, SignerIdentifier, SignerInfo,
+            SignerInfos, UnsignedAttributes, OID_CONTENT_TYPE, OID_ID_DATA, OID_MESSAGE_DIGEST,
+            OID_SIGNING_TIME,
+        },
+    },
+    Bytes,
+};
+use std::ops::Deref;
+use x509_certificate::{
+    asn1time::UtcTime,
+    rfc5652::{Attribute, AttributeValue},
+    CapturedX509Certificate, DigestAlgorithm,
+};
+
+/// ESS signing-certificate-v2 attribute (RFC 5035)
+///
+/// 1.2.840.113549.1.9.16.2.47
+pub(crate) const OID_SIGNING_CERTIFICATE_V2: ConstOid =
+    Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 2, 47]);
+
+/// Creates a CMS `SignedData` with a single signer.
+pub(crate) struct CmsBuilder<'a> {
+    signing_certificate: &'a CapturedX509Certificate,
+    /// Extra certificates added to the `SignedData`.
+    certificate_chain: &'a [CapturedX509Certificate],
+    /// Signer used by `build_der()`, not needed when the signature is created elsewhere.
+    signer: Option<&'a dyn PdfSigner>,
+    /// `eContentType` and value of the `content-type` attribute.
+    content_type: Oid,
+    /// Embed the content in the `SignedData`, otherwise the signature is detached.
+    content_inline: bool,
+    /// Add a `signing-time` attribute.
+    signing_time: Option<DateTime<Utc>>,
+    /// Add the ESS `signing-certificate-v2` attribute.
+    signing_certificate_v2: bool,
+    /// Request a time-stamp token over the signature value.
+    timestamp_provider: Option<&'a dyn TimestampProvider>,
+    /// Algorithm used by `build_der()`, the preferred algorithm of the signer when `None`.
+    signature_algorithm: Option<SignatureAlgorithm>,
+}
+
+impl<'a> CmsBuilder<'a> {
+    pub(crate) fn new(signer: &'a dyn PdfSigner) -> Self {
+        let mut builder =
+            Self::from_certificates(signer.signing_certificate(), signer.certificate_chain());
+        builder.signer = Some(signer);
+        builder
+    }
+
+    pub(crate) fn from_certificates(
+        signing_certificate: &'a CapturedX509Certificate,
+        certificate_chain: &'a [CapturedX509Certificate],
+    ) -> Self {
+        CmsBuilder {
+            signing_certificate,
+            certificate_chain,
+            signer: None,
+            content_type: Oid(Bytes::copy_from_slice(OID_ID_DATA.as_ref())),
+            content_inline: false,
+            signing_time: None,
+            signing_certificate_v2: false,
+            timestamp_provider: None,
+            signature_algorithm: None,
+        }
+    }
+
+    /// Create a builder for a detached signature over the `ByteRange` content of a PDF.
+    /// `signing_time` has to be the `M` of the signature dictionary.
+    pub(crate) fn for_signature_format(
+        signer: &'a dyn PdfSigner,
+        signature_format: SignatureFormat,
+        signing_time: DateTime<Utc>,
+    ) -> Self {
+        let builder = Self::new(signer);
+        match signature_format {
+            SignatureFormat::AdbePkcs7Detached => builder.signing_time(signing_time),
+            // PAdES: `signing-time` shall not be present, `M` is used instead.
+            SignatureFormat::EtsiCadesDetached => builder.signing_certificate_v2(),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn content_type(mut self, content_type: Oid) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn content_inline(mut self) -> Self {
+        self.content_inline = true;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn signing_time(mut self, signing_time: DateTime<Utc>) -> Self {
+        self.signing_time = Some(signing_time);
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn signing_certificate_v2(mut self) -> Self {
+        self.signing_certificate_v2 = true;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn timestamp_provider(
+        mut self,
+        timestamp_provider: Option<&'a dyn TimestampProvider>,
+    ) -> Self {
+        self.timestamp_provider = timestamp_provider;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn signature_algorithm(
+        mut self,
+        signature_algorithm: Option<SignatureAlgorithm>,
+    ) -> Self {
+        self.signature_algorithm = signature_algorithm;
+        self
+    }
+
+    /// The algorithm used to sign, checked against the key of the signing certificate
+    /// and the algorithms of the signer.
+    pub(crate) fn select_signature_algorithm(&self) -> Result<SignatureAlgorithm, Error> {
+        let key_algorithm = self
+            .signing_certificate
+            .key_algorithm()
+            .ok_or_else(|| Error::from("CMS: Unknown key algorithm of the certificate."))?;
+        let signature_algorithm = match (self.signature_algorithm, self.signer) {
+            (Some(signature_algorithm), _) => signature_algorithm,
+            (None, Some(signer)) => *signer
+                .supported_algorithms()
+                .first()
+                .ok_or_else(|| Error::from("CMS: Signer does not support any algorithm."))?,
+            (None, None) => SignatureAlgorithm::default_for_key(key_algorithm),
+        };
+        if !signature_algorithm.is_allowed_for_signing() {
+            return Err(Error::Other(format!(
+                "CMS: Signature algorithm `{}` is not allowed for new signatures.",
+                signature_algorithm
+            )));
+        }
+        if let Some(signer) = self.signer {
+            if !signer.supported_algorithms().contains(&signature_algorithm) {
+                return Err(Error::Other(format!(
+                    "CMS: Signature algorithm `{}` is not supported by the signer.",
+                    signature_algorithm
+                )));
+            }
+        }
+        if !signature_algorithm.is_supported_by_key(key_algorithm) {
+            return Err(Error::Other(format!(
+                "CMS: Signature algorithm `{}` can not be used with a {:?} key.",
+                signature_algorithm, key_algorithm
+            )));
+        }
+        Ok(signature_algorithm)
+    }
+
+    /// Estimate the size in bytes of the DER encoded `SignedData`.
+    /// Used to reserve space for the signature in the PDF.
+    pub(crate) fn estimate_size(&self) -> usize {
+        let signing_certificate = self.signing_certificate;
+        let certificates: usize = std::iter::once(signing_certificate)
+            .chain(self.certificate_chain.iter())
+            .map(|cert| cert.constructed_data().len())
+            .sum();
+        // The signature is at most about the size of the public key.
+        let signature = signing_certificate.public_key_data().len() + 64;
+        let timestamp = self
+            .timestamp_provider
+            .map(|provider| provider.estimated_token_size())
+            .unwrap_or(0);
+        // Signed attributes, algorithm identifiers and the ASN.1 structure itself.
+        certificates + signature + timestamp + 2048
+    }
+
+    /// Sign the content and return the DER encoded `SignedData`.
+    pub(crate) fn build_der(&self, content: &[u8]) -> Result<Vec<u8>, Error> {
+        let digest_algorithm = self.select_signature_algorithm()?.digest_algorithm();
+        self.build_der_with_digest(content, &digest_algorithm.digest_data(content))
+    }
+
+    /// Sign the `digest` of the detached content and return the DER encoded `SignedData`.
+    ///
+    /// The digest has to use the digest algorithm of `select_signature_algorithm()`.
+    pub(crate) fn build_der_for_digest(&self, digest: &[u8]) -> Result<Vec<u8>, Error> {
+        if self.content_inline {
+            return Err(Error::from(
+                "CMS: Inline content can not be signed by digest.",
+            ));
+        }
+        self.build_der_with_digest(&[], digest)
+    }
+
+    fn build_der_with_digest(&self, content: &[u8], digest: &[u8]) -> Result<Vec<u8>, Error> {
+        let signer = self
+            .signer
+            .ok_or_else(|| Error::from("CMS: No signer available."))?;
+        let signature_algorithm = self.select_signature_algorithm()?;
+
+        let signed_attributes = self.signed_attributes(digest)?;
+        let signed_content = Self::signed_attributes_der(&signed_attributes)?;
+        let signature = signer.sign(&signed_content, signature_algorithm)?;
+
+        self.build_der_with_signature(content, signed_attributes, signature, signature_algorithm)
+    }
+
+    /// The DER encoding of the signed attributes that is signed (as `SET OF`, RFC 5652 5.4).
+    pub(crate) fn signed_attributes_der(
+        signed_attributes: &SignedAttributes,
+    ) -> Result<Vec<u8>, Error> {
+        let mut der = Vec::new();
+        encode::set(encode::slice(signed_attributes.deref(), |attribute| {
+            attribute.clone().encode()
+        }))
+        .write_encoded(Mode::Der, &mut der)?;
+        Ok(der)
+    }
+
+    /// Create the DER encoded `SignedData` with a `signature` over the `signed_attributes`.
+    ///
+    /// The `content` is only used when it is embedded in the `SignedData`.
+    pub(crate) fn build_der_with_signature(
+        &self,
+        content: &[u8],
+        signed_attributes: SignedAttributes,
+        signature: Vec<u8>,
+        signature_algorithm: SignatureAlgorithm,
+    ) -> Result<Vec<u8>, Error> {
+        let digest_algorithm = signature_algorithm.digest_algorithm();
+        let signing_certificate = self.signing_certificate;
+
+        let mut signer_info = SignerInfo {
+            version: CmsVersion::V1,
+            sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: signing_certificate.issuer_name().clone(),
+                serial_number: signing_certificate.serial_number_asn1().clone(),
+            }),
+            digest_algorithm: DigestAlgorithmIdentifier {
+                algorithm: digest_algorithm.into(),
+                parameters: None,
+            },
+            signed_attributes: Some(signed_attributes),
+            signature_algorithm: signature_algorithm.algorithm_identifier(),
+            signature: SignatureValue::new(Bytes::new()),
+            unsigned_attributes: None,
+            signed_attributes_data: None,
+        };
+
+        if let Some(timestamp_provider) = self.timestamp_provider {
+            // The message sent to the TSA (via a digest) is the signature value.
+            let signature_digest = digest_algorithm.digest_data(&signature);
+            let token =
+                timestamp_provider.get_timestamp_token(&signature_digest, digest_algorithm)?;
+            let mut unsigned_attributes = UnsignedAttributes::default();
+            unsigned_attributes.push(Attribute {
+                typ: Oid(Bytes::copy_from_slice(OID_TIME_STAMP_TOKEN.as_ref())),
+                values: vec![AttributeValue::new(
+                    Constructed::decode(token.as_ref(), Mode::Der, |cons| cons.capture_one())
+                        .map_err(|err| {
+                            Error::Other(format!("CMS: Invalid time-stamp token: {}", err))
+                        })?,
+                )],
+            });
+            signer_info.unsigned_attributes = Some(unsigned_attributes);
+        }
+        signer_info.signature = SignatureValue::new(Bytes::from(signature));
+
+        let mut signer_infos = SignerInfos::default();
+        signer_infos.push(signer_info);
+
+        let mut digest_algorithms = DigestAlgorithmIdentifiers::default();
+        digest_algorithms.push(DigestAlgorithmIdentifier {
+            algorithm: digest_algorithm.into(),
+            parameters: None,
+        });
+
+        // Many consumers prefer the issuing certificate to come before the issued certificate.
+        let mut certificates = vec![signing_certificate.clone()];
+        certificates.extend(self.certificate_chain.iter().cloned());
+        certificates.sort_by(|a, b| a.compare_issuer(b));
+        let mut certificate_set = CertificateSet::default();
+        certificate_set.extend(
+            certificates
+                .into_iter()
+                .map(|cert| CertificateChoices::Certificate(Box::new(cert.into()))),
+        );
+
+        let signed_data = SignedData {
+            // Version 3 is required when the content is not `id-data`.
+            version: if self.content_type == OID_ID_DATA {
+                CmsVersion::V1
+            } else {
+                CmsVersion::V3
+            },
+            digest_algorithms,
+            content_info: EncapsulatedContentInfo {
+                content_type: self.content_type.clone(),
+                content: if self.content_inline {
+                    Some(OctetString::new(Bytes::copy_from_slice(content)))
+                } else {
+                    None
+                },
+            },
+            certificates: Some(certificate_set),
+            crls: None,
+            signer_infos,
+        };
+
+        let mut der = Vec::new();
+        signed_data
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut der)?;
+        Ok(der)
+    }
+
+    /// The signed attributes for the `digest` of the content,
+    /// these are signed to create the signature.
+    pub(crate) fn signed_attributes(&self, digest: &[u8]) -> Result<SignedAttributes, Error> {
+        let mut signed_attributes = SignedAttributes::default();
+
+        // The content-type field is mandatory.
+        signed_attributes.push(Attribute {
+            typ: Oid(Bytes::copy_from_slice(OID_CONTENT_TYPE.as_ref())),
+            values: vec![AttributeValue::new(Captured::from_values(
+                Mode::Der,
+                self.content_type.encode_ref(),
+            ))],
+        });
+        // The message-digest field is mandatory.
+        signed_attributes.push(Attribute {
+            typ: Oid(Bytes::copy_from_slice(OID_MESSAGE_DIGEST.as_ref())),
+            values: vec![AttributeValue::new(Captured::from_values(
+                Mode::Der,
+                digest.encode(),
+            ))],
+        });
+
+        if let Some(signing_time) = self.signing_time {
+            signed_attributes.push(Attribute {
+                typ: Oid(Bytes::copy_from_slice(OID_SIGNING_TIME.as_ref())),
+                values: vec![AttributeValue::new(Captured::from_values(
+                    Mode::Der,
+                    UtcTime::from(signing_time).encode(),
+                ))],
+            });
+        }
+        if self.signing_certificate_v2 {
+            signed_attributes.push(self.signing_certificate_v2_attribute());
+        }
+
+        // Signed attributes are DER encoded, so the SET needs to be sorted.
+        Ok(signed_attributes.as_sorted()?)
+    }
+
+    /// Create the `signing-certificate-v2` attribute.
+    ///
+    /// ```ASN.1
+    /// SigningCertificateV2 ::= SEQUENCE {
+    ///   certs SEQUENCE OF ESSCertIDv2,
+    ///   policies SEQUENCE OF PolicyInformation OPTIONAL }
+    /// ESSCertIDv2 ::= SEQUENCE {
+    ///   hashAlgorithm AlgorithmIdentifier DEFAULT {algorithm id-sha256},
+    ///   certHash OCTET STRING,
+    ///   issuerSerial IssuerSerial OPTIONAL }
+    /// IssuerSerial ::= SEQUENCE {
+    ///   issuer GeneralNames,
+    ///   serialNumber CertificateSerialNumber }
+    /// ```
+    fn signing_certificate_v2_attribute(&self) -> Attribute {
+        let certificate = self.signing_certificate;
+        let cert_hash = OctetString::new(Bytes::from(
+            DigestAlgorithm::Sha256.digest_data(certificate.constructed_data()),
+        ));
+
+        // `hashAlgorithm` is left out because SHA-256 is the default.
+        let value = encode::sequence(encode::sequence(encode::sequence((
+            cert_hash.encode_ref(),
+            encode::sequence((
+                // `GeneralNames` with a single `directoryName`
+                encode::sequence(certificate.issuer_name().encode_ref().explicit(Tag::CTX_4)),
+                certificate.serial_number_asn1().encode(),
+            )),
+        ))));
+
+        Attribute {
+            typ: Oid(Bytes::copy_from_slice(OID_SIGNING_CERTIFICATE_V2.as_ref())),
+            values: vec![AttributeValue::new(Captured::from_values(Mode::Der, value))],
+        }
+    }
+}
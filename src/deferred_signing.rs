@@ -0,0 +1,221 @@
+//! Two-phase signing, for when the private key is not available in this process.
+//!
+//! 1. `prepare_signature()` adds the signature to the PDF and returns a `PreparedSignature`.
+//! 2. The signature is created elsewhere (remote signing service, HSM, ...).
+//! 3. `finish_signature()` inserts the signature into the prepared PDF.
+//!
+//! The `PreparedSignature` can be serialized, so both steps can run in different processes.
+
+use crate::cms::CmsBuilder;
+use crate::digitally_sign::SigningTarget;
+use crate::error::Error;
+use crate::{ByteRange, PDFSigningDocument, SignatureAlgorithm, UserSignatureInfo};
+use bcder::{decode::Constructed, Mode};
+use chrono::{DateTime, SubsecRound, Utc};
+use cryptographic_message_syntax::asn1::rfc5652::SignedAttributes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use x509_certificate::CapturedX509Certificate;
+
+/// A PDF with a signature that still needs to be filled in.
+/// The bytes are serialized as base64 strings, except for the `certificates`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedSignature {
+    pub file_name: String,
+    /// The PDF file, the `Contents` of the signature is still empty.
+    #[serde(with = "base64_bytes")]
+    pub pdf: Vec<u8>,
+    pub byte_range: Vec<usize>,
+    /// The algorithm the signature has to be created with.
+    pub signature_algorithm: SignatureAlgorithm,
+    /// Digest of the content covered by the `ByteRange`,
+    /// using the digest algorithm of the `signature_algorithm`.
+    /// Use this when the signing service creates the full CMS.
+    #[serde(with = "base64_bytes")]
+    pub document_digest: Vec<u8>,
+    /// DER encoded signed attributes (`SET OF Attribute`).
+    /// Use this when the signing service only creates a raw signature,
+    /// the data needs to be signed with the `signature_algorithm`.
+    #[serde(with = "base64_bytes")]
+    pub signed_attributes: Vec<u8>,
+    /// DER encoded certificates, the signing certificate first followed by the chain.
+    pub certificates: Vec<Vec<u8>>,
+}
+
+/// The signature that is created for a `PreparedSignature`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalSignature {
+    /// DER encoded CMS `SignedData` over the `document_digest`.
+    Cms(#[serde(with = "base64_bytes")] Vec<u8>),
+    /// Raw signature value over the `signed_attributes`.
+    Raw(#[serde(with = "base64_bytes")] Vec<u8>),
+}
+
+impl PDFSigningDocument {
+    /// Add the signature images and signature dictionary for the first empty signature field
+    /// of the user, without signing it. See `finish_signature()`.
+    ///
+    /// The `user_signer` is not used to sign, see `SigningKeys::from_certificate()`.
+    /// Time-stamps are not added, the signing service can add them when it creates the CMS.
+    /// The space reserved for the signature can not be increased after this step,
+    /// so set `contents_size` when the CMS might be bigger than the estimate.
+    ///
+    /// The document itself is not changed, the signature is only added to the returned PDF.
+    pub fn prepare_signature(
+        &mut self,
+        user_info: &UserSignatureInfo,
+    ) -> Result<PreparedSignature, Error> {
+        self.load_all()?;
+        let prepared = self.prepare_signature_field(user_info);
+        self.discard_incremental_update();
+        prepared
+    }
+
+    fn prepare_signature_field(
+        &mut self,
+        user_info: &UserSignatureInfo,
+    ) -> Result<PreparedSignature, Error> {
+        let users_signature_info_map =
+            HashMap::from([(user_info.user_id.clone(), user_info.clone())]);
+        let signing_time = Utc::now().trunc_subsecs(0);
+        let acro_forms = self.acro_form.clone().unwrap_or_default();
+        for form_field in acro_forms {
+            if !form_field.is_empty_signature() {
+                continue;
+            }
+            if self
+                .add_signature_images(form_field, &users_signature_info_map, signing_time)?
+                .is_some()
+            {
+                return self.prepare_signature_state(user_info, signing_time);
+            }
+        }
+
+        Err(Error::Other(format!(
+            "No empty signature field found for user `{}`.",
+            user_info.user_id
+        )))
+    }
+
+    fn prepare_signature_state(
+        &mut self,
+        user_info: &UserSignatureInfo,
+        signing_time: DateTime<Utc>,
+    ) -> Result<PreparedSignature, Error> {
+        let signer = user_info.user_signer.as_ref();
+        // The signer has no key, so only the key type of the certificate is checked.
+        let signature_algorithm =
+            CmsBuilder::from_certificates(signer.signing_certificate(), signer.certificate_chain())
+                .signature_algorithm(user_info.signature_algorithm)
+                .select_signature_algorithm()?;
+        let digest_algorithm = signature_algorithm.digest_algorithm();
+
+        let mut target = SigningTarget::new(Vec::new());
+        let (_, byte_range, document_digest) =
+            self.write_byte_range(digest_algorithm, &mut target)?;
+        let pdf = target.file;
+        let signed_attributes =
+            CmsBuilder::for_signature_format(signer, user_info.signature_format, signing_time)
+                .signed_attributes(&document_digest)?;
+
+        let certificates = std::iter::once(signer.signing_certificate())
+            .chain(signer.certificate_chain())
+            .map(|cert| cert.constructed_data().to_vec())
+            .collect();
+
+        Ok(PreparedSignature {
+            file_name: self.file_name.clone(),
+            pdf,
+            byte_range: byte_range.0,
+            signature_algorithm,
+            document_digest,
+            signed_attributes: CmsBuilder::signed_attributes_der(&signed_attributes)?,
+            certificates,
+        })
+    }
+
+    /// Insert the signature into the prepared PDF and return the signed PDF file.
+    ///
+    /// Returns `Error::ContentsTooSmall` when the signature does not fit, and an error when
+    /// the `byte_range` does not match the `pdf`.
+    pub fn finish_signature(
+        prepared: PreparedSignature,
+        signature: ExternalSignature,
+    ) -> Result<Vec<u8>, Error> {
+        Self::check_prepared_byte_range(&prepared.pdf, &prepared.byte_range)?;
+        let byte_range = ByteRange(prepared.byte_range);
+        let cms = match signature {
+            ExternalSignature::Cms(cms) => cms,
+            ExternalSignature::Raw(signature) => {
+                let mut certificates = prepared
+                    .certificates
+                    .into_iter()
+                    .map(CapturedX509Certificate::from_der)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if certificates.is_empty() {
+                    return Err(Error::from("Prepared signature has no certificates."));
+                }
+                let signing_certificate = certificates.remove(0);
+                let signed_attributes = Constructed::decode(
+                    prepared.signed_attributes.as_ref(),
+                    Mode::Der,
+                    SignedAttributes::take_from,
+                )
+                .map_err(|err| Error::Other(format!("Invalid signed attributes: {}", err)))?;
+
+                CmsBuilder::from_certificates(&signing_certificate, &certificates)
+                    .build_der_with_signature(
+                        &[],
+                        signed_attributes,
+                        signature,
+                        prepared.signature_algorithm,
+                    )?
+            }
+        };
+
+        Self::set_content(prepared.pdf, &byte_range, &cms)
+    }
+
+    /// Check that the `byte_range` covers the whole `pdf`, except for the empty `Contents`
+    /// hex string. The `PreparedSignature` can come from another process.
+    fn check_prepared_byte_range(pdf: &[u8], byte_range: &[usize]) -> Result<(), Error> {
+        let valid = match *byte_range {
+            [0, first_end, second_start, second_len] => {
+                first_end < second_start
+                    && second_start <= pdf.len()
+                    && second_len == pdf.len() - second_start
+                    && second_start - first_end >= 2
+                    && pdf[first_end] == b'<'
+                    && pdf[second_start - 1] == b'>'
+                    && pdf[(first_end + 1)..(second_start - 1)]
+                        .iter()
+                        .all(|byte| *byte == b'0')
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(Error::Other(format!(
+                "`ByteRange` {:?} does not match the empty `Contents` of the prepared PDF.",
+                byte_range
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Serialize bytes as a base64 string instead of an array of numbers.
+mod base64_bytes {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64_STANDARD.decode(encoded).map_err(D::Error::custom)
+    }
+}
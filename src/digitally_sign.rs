@@ -1,292 +1,417 @@
+use crate::cms::CmsBuilder;
 use crate::error::Error;
 use crate::{ByteRange, PDFSigningDocument, UserSignatureInfo};
-use cryptographic_message_syntax::{Bytes, Oid, SignedDataBuilder};
-use lopdf::ObjectId;
-use std::io::Write;
+use chrono::{DateTime, Utc};
+use lopdf::{Object, StringFormat};
+use ring::digest;
+use std::io::{Seek, SeekFrom, Write};
+use std::ops::Range;
+use x509_certificate::DigestAlgorithm;
+
+/// The `ByteRange` placeholder as written by `byte_range_placeholder()`, with the start of
+/// the `Contents` that follows it.
+const BYTE_RANGE_PATTERN: &[u8] = b"/ByteRange[0 1000000000 2000000000 1000000000]/Contents<";
+/// Width of the list of values in the `ByteRange` placeholder.
+const BYTE_RANGE_LIST_WIDTH: usize = b"0 1000000000 2000000000 1000000000".len();
+
+/// The file a signed document is written to.
+pub(crate) struct SigningTarget<W> {
+    pub(crate) file: W,
+    /// Number of bytes of the previous revisions that are already written to `file`.
+    prev_written: usize,
+}
+
+impl<W: Write> SigningTarget<W> {
+    pub(crate) fn new(file: W) -> Self {
+        Self {
+            file,
+            prev_written: 0,
+        }
+    }
+
+    /// Write the part of the previous revisions that is not written yet,
+    /// `digester` gets the bytes of all previous revisions.
+    pub(crate) fn write_prev_documents(
+        &mut self,
+        prev_bytes: &[u8],
+        digester: Option<&mut digest::Context>,
+    ) -> Result<(), Error> {
+        let (written, pending) = prev_bytes.split_at(self.prev_written.min(prev_bytes.len()));
+        self.file.write_all(pending)?;
+        if let Some(digester) = digester {
+            digester.update(written);
+            digester.update(pending);
+        }
+        self.prev_written = prev_bytes.len();
+        Ok(())
+    }
+}
 
 impl PDFSigningDocument {
     /// Digitally signs the document using a cryptographically secure algorithm.
     /// Note that using this function will prevent you from changing anything else about the document.
     /// Changing the document in any other way will invalidate the cryptographic check.
-    pub(crate) fn digitally_sign_document(
-        &self,
+    ///
+    /// The signed incremental update is written to `target`.
+    /// Returns the update and the `ByteRange` of the signature.
+    pub(crate) fn digitally_sign_document<W: Write + Seek>(
+        &mut self,
         user_info: &UserSignatureInfo,
-    ) -> Result<Vec<u8>, Error> {
-        // TODO: Code should be enabled in the future, do not remove.
-        // Decompose `pdf_document` into it parts.
-        // let acro_forms = self.acro_form.clone();
-        // Add data to file before signing
-        // Get first signature
-        // let first_signature_id = if let Some(Some(first_signature)) =
-        //     acro_forms.as_ref().map(|forms| forms.first().cloned())
-        // {
-        //     first_signature.get_object_id()
-        // } else {
-        //     None
-        // };
-        // // first_signature_id
-        // if let Some(first_signature_id) = first_signature_id {
-        //     pdf_signing_document.add_digital_signature_data(first_signature_id)?;
-        // } else {
-        //     return Err(InternalError::new(
-        //         "Could not find first signature in PDF, can not sign document.",
-        //         ApiErrorKind::ServerError,
-        //         InternalErrorCodes::Default,
-        //     ));
-        // }
-
-        // Convert pdf document to binary data.
-        let mut pdf_file_data: Vec<u8> = Vec::new();
-        self.write_document(&mut pdf_file_data)?;
+        signing_time: DateTime<Utc>,
+        target: &mut SigningTarget<W>,
+    ) -> Result<(Vec<u8>, ByteRange), Error> {
+        let cms_builder = CmsBuilder::for_signature_format(
+            user_info.user_signer.as_ref(),
+            user_info.signature_format,
+            signing_time,
+        )
+        .timestamp_provider(user_info.timestamp_provider.as_deref())
+        .signature_algorithm(user_info.signature_algorithm);
+        let digest_algorithm = cms_builder.select_signature_algorithm()?.digest_algorithm();
+
+        // Sign the file hash using the users key
+        self.sign_byte_range(digest_algorithm, target, |digest| {
+            cms_builder.build_der_for_digest(digest)
+        })
+    }
 
-        let (byte_range, pdf_file_data) = Self::set_next_byte_range(pdf_file_data);
+    /// Write the new incremental update to `target`, with the `ByteRange` placeholder filled in,
+    /// and set `Contents` to the value returned by `sign`. `sign` receives the digest of the
+    /// bytes covered by the `ByteRange`.
+    ///
+    /// Returns the signed incremental update and the `ByteRange`.
+    /// When the signature does not fit in the reserved `Contents`, the reservation is
+    /// increased, the update is written again and `sign` is called again.
+    pub(crate) fn sign_byte_range<W, F>(
+        &mut self,
+        digest_algorithm: DigestAlgorithm,
+        target: &mut SigningTarget<W>,
+        sign: F,
+    ) -> Result<(Vec<u8>, ByteRange), Error>
+    where
+        W: Write + Seek,
+        F: Fn(&[u8]) -> Result<Vec<u8>, Error>,
+    {
+        match self.try_sign_byte_range(digest_algorithm, target, &sign) {
+            Err(Error::ContentsTooSmall { available, needed }) => {
+                log::info!(
+                    "Signature does not fit in `Contents`, retrying. Available: {}, Needed: {}",
+                    available,
+                    needed
+                );
+                // The signature can differ in size on each call (time-stamps, ECDSA).
+                self.set_contents_placeholder_size(needed + 1024)?;
+                self.try_sign_byte_range(digest_algorithm, target, &sign)
+            }
+            result => result,
+        }
+    }
 
-        let first_part = &pdf_file_data[byte_range.get_range(0)];
-        let second_part = &pdf_file_data[byte_range.get_range(1)];
+    /// On error `target` is positioned at the start of the update,
+    /// so the update can be written again.
+    fn try_sign_byte_range<W, F>(
+        &mut self,
+        digest_algorithm: DigestAlgorithm,
+        target: &mut SigningTarget<W>,
+        sign: &F,
+    ) -> Result<(Vec<u8>, ByteRange), Error>
+    where
+        W: Write + Seek,
+        F: Fn(&[u8]) -> Result<Vec<u8>, Error>,
+    {
+        let (mut update, byte_range, digest) = self.write_byte_range(digest_algorithm, target)?;
+        let update_end = target.file.stream_position()?;
+        let update_start = update_end - update.len() as u64;
+
+        let contents = sign(&digest).and_then(|signature| {
+            #[cfg(feature = "debug")]
+            {
+                let mut file = std::fs::File::create("./signature.der").unwrap();
+                file.write_all(&signature).unwrap();
+            }
+            Self::contents_hex(&byte_range, &signature)
+        });
+        let (content_offset, new_contents) = match contents {
+            Ok(contents) => contents,
+            Err(err) => {
+                target.file.seek(SeekFrom::Start(update_start))?;
+                return Err(err);
+            }
+        };
+
+        // Write signature to the update and over the placeholder in `target`.
+        let prev_len = self.raw_document.get_prev_documents_bytes().len();
+        let update_offset = content_offset - prev_len;
+        update[update_offset..(update_offset + new_contents.len())].copy_from_slice(&new_contents);
+        target
+            .file
+            .seek(SeekFrom::Start(update_start + update_offset as u64))?;
+        target.file.write_all(&new_contents)?;
+        target.file.seek(SeekFrom::Start(update_end))?;
+        target.prev_written += update.len();
+        Ok((update, byte_range))
+    }
 
-        // Used for debugging
-        // log::trace!(
-        //     "End of first part: {}",
-        //     String::from_utf8_lossy(&first_part[(byte_range.0[1] - 15)..])
-        // );
-        // log::trace!(
-        //     "Start of second part: {}...{}",
-        //     String::from_utf8_lossy(&second_part[0..10]),
-        //     String::from_utf8_lossy(&second_part[(second_part.len() - 5)..])
-        // );
+    /// Write the previous revisions that are not in `target` yet and the new incremental
+    /// update, with the `ByteRange` placeholder filled in.
+    ///
+    /// Returns the update, the `ByteRange` (relative to the start of the document) and the
+    /// digest of the content covered by it. The content is hashed while it is written, the
+    /// update is written twice: first only to find the placeholder, then to `target`.
+    pub(crate) fn write_byte_range<W: Write>(
+        &mut self,
+        digest_algorithm: DigestAlgorithm,
+        target: &mut SigningTarget<W>,
+    ) -> Result<(Vec<u8>, ByteRange, Vec<u8>), Error> {
+        let mut digester = digest_algorithm.digester();
+        let prev_bytes = self.raw_document.get_prev_documents_bytes();
+        let prev_len = prev_bytes.len();
+        target.write_prev_documents(prev_bytes, Some(&mut digester))?;
+
+        let mut locator = PlaceholderLocator::default();
+        self.write_update(&mut locator)?;
+        let byte_range = locator.byte_range(prev_len)?;
+
+        let mut writer = SigningWrite::new(&mut target.file, &mut digester, &byte_range, prev_len)?;
+        self.write_update(&mut writer)?;
+        let update = writer.update;
+
+        Ok((update, byte_range, digester.finish().as_ref().to_vec()))
+    }
 
-        // create new vec without the content part
-        let mut vec = Vec::with_capacity(byte_range.get_capacity_inclusive());
-        vec.extend_from_slice(first_part);
-        vec.extend_from_slice(second_part);
+    /// Default value of `ByteRange`, filled in by `write_byte_range()`.
+    /// Must be directly followed by `Contents` in the dictionary.
+    ///
+    /// The values take up as much space as offsets of 10 digits, so the real values are
+    /// written over them without moving the `Contents`.
+    pub(crate) fn byte_range_placeholder() -> Object {
+        Object::Array(vec![
+            Object::Integer(0),
+            Object::Integer(1_000_000_000), // byte of `<`
+            Object::Integer(2_000_000_000), // Byte of char after `>`
+            Object::Integer(1_000_000_000), // until end of file
+        ])
+    }
 
-        // Calculate file hash and sign it using the users key
-        let signature = SignedDataBuilder::default()
-            .content_external(vec)
-            .content_type(Oid(Bytes::copy_from_slice(
-                cryptographic_message_syntax::asn1::rfc5652::OID_ID_DATA.as_ref(),
-            )))
-            .signer(user_info.user_signing_keys.clone())
-            .build_der()
-            .unwrap();
+    /// Default value of `Contents`, filled in by `set_content()`.
+    ///
+    /// `size` is the number of bytes reserved for the signature,
+    /// this takes up `2 * size` chars in the file.
+    pub(crate) fn contents_placeholder(size: usize) -> Object {
+        Object::String(vec![0u8; size], StringFormat::Hexadecimal)
+    }
 
-        #[cfg(feature = "debug")]
-        {
-            let mut file = std::fs::File::create("./signature.der").unwrap();
-            file.write_all(&signature).unwrap();
+    /// Change the size of the `Contents` placeholder in the new incremental update.
+    fn set_contents_placeholder_size(&mut self, size: usize) -> Result<(), Error> {
+        let placeholder = Self::byte_range_placeholder();
+        for object in self.raw_document.new_document.objects.values_mut() {
+            if let Ok(dict) = object.as_dict_mut() {
+                if dict.get(b"ByteRange").ok() == Some(&placeholder) {
+                    dict.set("Contents", Self::contents_placeholder(size));
+                    return Ok(());
+                }
+            }
         }
+        Err(Error::from("Signature placeholder not found in document."))
+    }
 
-        // Write signature to file
-        let pdf_file_data = Self::set_content(pdf_file_data, signature);
-
+    /// Set the `Contents` of the signature in the PDF file.
+    pub(crate) fn set_content(
+        mut pdf_file_data: Vec<u8>,
+        byte_range: &ByteRange,
+        content: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Self::write_content(&mut pdf_file_data, 0, byte_range, content)?;
         Ok(pdf_file_data)
     }
 
-    // TODO: Not used, see start of `digitally_sign_document()`
-    #[allow(dead_code)]
-    pub(crate) fn add_digital_signature_data(
-        &mut self,
-        first_signature_id: ObjectId,
+    /// Set the `Contents` of the signature in `pdf_part`, the part of the PDF file
+    /// that starts at `offset`.
+    fn write_content(
+        pdf_part: &mut [u8],
+        offset: usize,
+        byte_range: &ByteRange,
+        content: &[u8],
     ) -> Result<(), Error> {
-        use lopdf::Object::*;
-        // Get root ID
-        let root_obj_id = self
-            .raw_document
-            .get_prev_documents()
-            .trailer
-            .get(b"Root")?
-            .as_reference()?;
-        // Clone object
-        self.raw_document
-            .opt_clone_object_to_new_document(root_obj_id)?;
-        // Get Root in new document
-        let root = self
-            .raw_document
-            .new_document
-            .get_object_mut(root_obj_id)?
-            .as_dict_mut()?;
-        log::debug!("Root: {:?}", root);
-
-        if root.has(b"Perms") {
-            log::info!("Document already has `Perms` field.");
-            let perms = root.get_mut(b"Perms")?.as_dict_mut()?;
-            log::debug!("Perms: {:?}", perms);
-            // Add `DocMDP` reference to existing dict
-            perms.set("DocMDP", Reference(first_signature_id));
-        } else {
-            // Add `Perms` field with `DocMDP` reference
-            root.set(
-                "Perms",
-                lopdf::Dictionary::from_iter(vec![("DocMDP", Reference(first_signature_id))]),
-            );
-        }
-
+        let (content_offset, new_contents) = Self::contents_hex(byte_range, content)?;
+        content_offset
+            .checked_sub(offset)
+            .and_then(|start| pdf_part.get_mut(start..(start + new_contents.len())))
+            .ok_or_else(|| Error::from("Signature `Contents` is outside of the PDF file."))?
+            .copy_from_slice(&new_contents);
         Ok(())
     }
 
-    // Find and set the `Content` field in the signature
-    fn set_content(mut pdf_file_data: Vec<u8>, content: Vec<u8>) -> Vec<u8> {
-        // Determine the byte ranged
-        // Find the `Content` part of the file
-        let pattern_prefix = b"/Contents<";
-        let pattern_content = vec![48u8; 18000]; // 48 = 0x30 = `0`
-
-        if content.len() > pattern_content.len() {
-            panic!(
-                "Length of content is to long. Available: {}, Needed: {}",
-                pattern_content.len(),
-                content.len()
-            );
-        }
-        let mut pattern = pattern_prefix.to_vec();
-        pattern.extend_from_slice(&pattern_content[..=50]); // Just add the first part, rest will be okay
-
-        // Find the pattern in the PDF file binary
-        let found_at = Self::find_binary_pattern(&pdf_file_data, &pattern);
-
-        match found_at {
-            Some(found_at) => {
-                // Construct new Contents and insert it into file
-                let new_contents_vec = format!(
-                    "/Contents<{}",
-                    content
-                        .iter()
-                        .map(|num| format!("{:02x}", num))
-                        .collect::<Vec<String>>()
-                        .join("")
-                )
-                .as_bytes()
-                .to_vec();
-
-                pdf_file_data.splice(
-                    found_at..(found_at + new_contents_vec.len()),
-                    new_contents_vec,
-                );
-
-                pdf_file_data
+    /// The offset of the `Contents` hex string and the hex encoded `content`.
+    fn contents_hex(byte_range: &ByteRange, content: &[u8]) -> Result<(usize, Vec<u8>), Error> {
+        // The `Contents` hex string is the gap between the 2 ranges, including `<` and `>`.
+        let (first_end, second_start) = match byte_range.0.get(1..3) {
+            Some([first_end, second_start])
+                if first_end
+                    .checked_add(2)
+                    .is_some_and(|min| *second_start >= min) =>
+            {
+                (*first_end, *second_start)
             }
-            None => {
-                // Pattern was not found, add debug info
-                #[cfg(debug_assertions)]
-                {
-                    let crashed_file = "./pdf_missing_pattern.pdf";
-                    let mut file = std::fs::File::create(crashed_file).unwrap();
-                    file.write_all(&pdf_file_data).unwrap();
-                    log::error!(
-                        "Pattern not found `{}`. Saved file to: `{}`.",
-                        String::from_utf8_lossy(&pattern),
-                        crashed_file
-                    );
-                }
-                panic!(
-                    "Pattern not found `{}`. PDF Signing bug in the code.",
-                    String::from_utf8_lossy(&pattern),
-                );
+            _ => {
+                return Err(Error::Other(format!(
+                    "Invalid `ByteRange` {:?}, there is no gap for the `Contents`.",
+                    byte_range.0
+                )))
             }
+        };
+        let content_offset = first_end + 1;
+        let available = (second_start - content_offset - 1) / 2;
+        if content.len() > available {
+            return Err(Error::ContentsTooSmall {
+                available,
+                needed: content.len(),
+            });
         }
-    }
 
-    /// Set the next found byte `ByteRange` that still has the default values.
-    fn set_next_byte_range(mut pdf_file_data: Vec<u8>) -> (ByteRange, Vec<u8>) {
-        // Determine the byte ranged
-        // Find the `Content` part of the file
-        let pattern_prefix = b"/ByteRange[0 10000 20000 10000]/Contents<";
-        let pattern_content = vec![48u8; 18000]; // 48 = 0x30 = `0`
-        let mut pattern = pattern_prefix.to_vec();
-        pattern.extend_from_slice(&pattern_content[..=50]); // Just add the first part, rest will be okay
+        let new_contents = content
+            .iter()
+            .map(|num| format!("{:02x}", num))
+            .collect::<String>()
+            .into_bytes();
+        Ok((content_offset, new_contents))
+    }
+}
 
-        // Search for `ByteRange` tag with default values
-        let found_at = Self::find_binary_pattern(&pdf_file_data, &pattern).unwrap();
+/// Counts the bytes of an incremental update and finds the `ByteRange` placeholder in it,
+/// without keeping the update.
+#[derive(Default)]
+struct PlaceholderLocator {
+    bytes_written: usize,
+    /// Number of bytes of `BYTE_RANGE_PATTERN` matched so far.
+    matched: usize,
+    /// Offset of `BYTE_RANGE_PATTERN` in the update.
+    found_at: Option<usize>,
+    /// Number of hex digits of the `Contents` placeholder, when its `>` is found.
+    contents_len: Option<usize>,
+}
 
-        // Calculate `ByteRange`
-        let fixed_byte_range_width = 25;
-        let pattern_prefix_len = b"/ByteRange[]/Contents<".len() + fixed_byte_range_width;
-        let content_len =
-            pattern_content.len() + b"0 10000 20000 10000".len() - fixed_byte_range_width;
-        let content_offset = found_at + pattern_prefix_len - 1;
-        let byte_range = ByteRange(vec![
+impl PlaceholderLocator {
+    /// The `ByteRange` of the signature, `offset` is the position of the update in the file.
+    fn byte_range(&self, offset: usize) -> Result<ByteRange, Error> {
+        let found_at = self.found_at.ok_or_else(|| {
+            Error::Other(format!(
+                "Pattern not found `{}`. PDF Signing bug in the code.",
+                String::from_utf8_lossy(BYTE_RANGE_PATTERN),
+            ))
+        })?;
+        let contents_len = self
+            .contents_len
+            .ok_or_else(|| Error::from("Signature `Contents` is not terminated."))?;
+
+        // The gap is the `Contents` including `<` and `>`.
+        let content_offset = offset + found_at + BYTE_RANGE_PATTERN.len() - 1;
+        let content_end = content_offset + contents_len + 2;
+        Ok(ByteRange(vec![
             0,
             content_offset,
-            content_offset + content_len + 2,
-            pdf_file_data.len() - 2 - (content_offset + content_len),
-        ]);
-
-        // Code for debugging
-        // dbg!(&byte_range
-        //     .0
-        //     .iter()
-        //     .map(|x| format!("0x{:02x}", x))
-        //     .collect::<Vec<String>>());
-
-        // Change binary file
-
-        // The `Contents` field after the `ByteRange` always need to have an even number of `0`s
-        // because otherwise it will have invalid byte pattern.
-
-        // Construct new ByteRange and insert it into file
-        // Note: Notice the `0`s after `Contents<` this is to make sure that if the `ByteRange`
-        // is shorter then the pattern that any other chars are overwritten.
-        // Have at least "0 10000 20000 10000".len() + "{}".len() `0`s. (and even number)
-        let mut new_byte_range_string = format!(
-            "/ByteRange[{}]/Contents<0000000000000000000000",
-            byte_range.to_list(fixed_byte_range_width).unwrap()
-        );
+            content_end,
+            offset + self.bytes_written - content_end,
+        ]))
+    }
+}
 
-        // The `Contents<...>` always need to be an even number of chars
-        if pattern_prefix.len() % 2 != new_byte_range_string.len() % 2 {
-            log::trace!("Added space to `ByteRange`");
-            // Add space to make equal
-            new_byte_range_string = format!(
-                "/ByteRange[{} ]/Contents<0000000000000000000000",
-                byte_range.to_list(fixed_byte_range_width).unwrap()
-            );
+impl Write for PlaceholderLocator {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        for byte in buffer {
+            match (self.found_at, self.contents_len) {
+                (None, _) => {
+                    if *byte != BYTE_RANGE_PATTERN[self.matched] {
+                        // The pattern does not repeat its start, so only check it again.
+                        self.matched = usize::from(*byte == BYTE_RANGE_PATTERN[0]);
+                    } else {
+                        self.matched += 1;
+                    }
+                    if self.matched == BYTE_RANGE_PATTERN.len() {
+                        self.found_at = Some(self.bytes_written + 1 - self.matched);
+                    }
+                }
+                (Some(found_at), None) if *byte == b'>' => {
+                    self.contents_len =
+                        Some(self.bytes_written - found_at - BYTE_RANGE_PATTERN.len());
+                }
+                _ => {}
+            }
+            self.bytes_written += 1;
         }
-        let new_byte_range_string = new_byte_range_string.as_bytes().to_vec();
-
-        pdf_file_data.splice(
-            found_at..(found_at + new_byte_range_string.len()),
-            new_byte_range_string,
-        );
+        Ok(buffer.len())
+    }
 
-        (byte_range, pdf_file_data)
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
+}
 
-    /// Finds the first instance matching the pattern.
-    ///
-    /// Note: This function can not deal well with repeating patterns inside the pattern.
-    /// But this should not matter in our cases.
-    ///
-    /// Result is `byte_offset_where_pattern_starts`
-    ///
-    fn find_binary_pattern(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
-        if bytes.is_empty() || pattern.is_empty() {
-            return None;
-        }
+/// Writes the incremental update to the file, fills in the `ByteRange` and hashes everything
+/// except the `Contents`. A copy of the update is kept to append it to the document.
+struct SigningWrite<'a, W> {
+    file: &'a mut W,
+    digester: &'a mut digest::Context,
+    update: Vec<u8>,
+    /// The values of the `ByteRange` and their position in the update.
+    byte_range_list: Vec<u8>,
+    byte_range_list_at: Range<usize>,
+    /// The position of the `Contents` in the update, including `<` and `>`.
+    contents_at: Range<usize>,
+}
 
-        let first_pat_byte = pattern.first().expect("At least 1 byte expected.");
-        let mut next_pat_byte = first_pat_byte;
-        let mut pattern_index = 0;
-        let mut start_index = 0;
+impl<'a, W: Write> SigningWrite<'a, W> {
+    /// `offset` is the position of the update in the file.
+    fn new(
+        file: &'a mut W,
+        digester: &'a mut digest::Context,
+        byte_range: &ByteRange,
+        offset: usize,
+    ) -> Result<Self, Error> {
+        let contents_at = (byte_range.0[1] - offset)..(byte_range.0[2] - offset);
+        let list_start = contents_at.start + 1 - BYTE_RANGE_PATTERN.len() + b"/ByteRange[".len();
+        Ok(Self {
+            file,
+            digester,
+            update: Vec::new(),
+            byte_range_list: byte_range.to_list(BYTE_RANGE_LIST_WIDTH)?.into_bytes(),
+            byte_range_list_at: list_start..(list_start + BYTE_RANGE_LIST_WIDTH),
+            contents_at,
+        })
+    }
+}
 
-        for (index, byte) in bytes.iter().enumerate() {
-            if next_pat_byte == byte {
-                // Save `start_index` for later
-                if pattern_index == 0 {
-                    start_index = index;
-                }
-                // Go to next byte of pattern
-                pattern_index += 1;
-                next_pat_byte = match pattern.get(pattern_index) {
-                    Some(byte) => byte,
-                    None => return Some(start_index),
-                };
-            } else {
-                // If pattern breaks or does not match
-                pattern_index = 0;
-                next_pat_byte = first_pat_byte;
-            }
+impl<W: Write> Write for SigningWrite<'_, W> {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        let position = self.update.len();
+        // Only write up to the next boundary, so all bytes are in the same part.
+        let boundaries = [
+            self.byte_range_list_at.start,
+            self.byte_range_list_at.end,
+            self.contents_at.start,
+            self.contents_at.end,
+        ];
+        let len = boundaries
+            .into_iter()
+            .filter(|boundary| *boundary > position)
+            .map(|boundary| boundary - position)
+            .fold(buffer.len(), usize::min);
+
+        let bytes = if self.byte_range_list_at.contains(&position) {
+            let list_start = position - self.byte_range_list_at.start;
+            &self.byte_range_list[list_start..(list_start + len)]
+        } else {
+            &buffer[..len]
+        };
+        let written = self.file.write(bytes)?;
+        if !self.contents_at.contains(&position) {
+            self.digester.update(&bytes[..written]);
         }
+        self.update.extend_from_slice(&bytes[..written]);
+        Ok(written)
+    }
 
-        None
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
     }
 }
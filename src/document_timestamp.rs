@@ -0,0 +1,212 @@
+use crate::digitally_sign::SigningTarget;
+use crate::error::Error;
+use crate::{PDFSigningDocument, TimestampProvider};
+use lopdf::{Object, ObjectId, StringFormat};
+use std::io::Cursor;
+use x509_certificate::DigestAlgorithm;
+
+impl PDFSigningDocument {
+    /// Add a document time-stamp (`ETSI.RFC3161`) in a new incremental update.
+    ///
+    /// The time-stamp token covers the whole document including all existing signatures.
+    /// An invisible signature field is added to the first page to hold the time-stamp,
+    /// so no form field, user or image is needed.
+    ///
+    /// Returns the new PDF file, the document itself is also updated.
+    pub fn add_document_timestamp(
+        &mut self,
+        timestamp_provider: &dyn TimestampProvider,
+    ) -> Result<Vec<u8>, Error> {
+        self.load_all()?;
+        let mut target = SigningTarget::new(Cursor::new(Vec::new()));
+        let update_and_byte_range = self
+            .add_document_timestamp_field(timestamp_provider.estimated_token_size())
+            .and_then(|_| {
+                let digest_algorithm = DigestAlgorithm::Sha256;
+                self.sign_byte_range(digest_algorithm, &mut target, |digest| {
+                    timestamp_provider.get_timestamp_token(digest, digest_algorithm)
+                })
+            });
+        let (update, byte_range) = match update_and_byte_range {
+            Ok(update_and_byte_range) => update_and_byte_range,
+            Err(err) => {
+                self.discard_incremental_update();
+                return Err(err);
+            }
+        };
+
+        // Continue with the new document, the new field is loaded when needed.
+        self.append_incremental_update(&update, Some(&byte_range))?;
+        self.acro_form = None;
+        Ok(target.file.into_inner())
+    }
+
+    /// Add an invisible signature field with a `DocTimeStamp` value to the document.
+    fn add_document_timestamp_field(&mut self, contents_size: usize) -> Result<ObjectId, Error> {
+        use lopdf::Object::*;
+
+        let timestamp_dictionary = Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("Type", Name("DocTimeStamp".as_bytes().to_vec())),
+            ("Filter", Name("Adobe.PPKLite".as_bytes().to_vec())),
+            ("SubFilter", Name("ETSI.RFC3161".as_bytes().to_vec())),
+            // The order of `ByteRange` and `Contents` is important.
+            // They should not be moved or switched in ordering.
+            (
+                "ByteRange", // Set default value. This will later be filled in.
+                PDFSigningDocument::byte_range_placeholder(),
+            ),
+            (
+                "Contents", // Will be filled in later
+                PDFSigningDocument::contents_placeholder(contents_size),
+            ),
+        ]));
+        let timestamp_id = self
+            .raw_document
+            .new_document
+            .add_object(timestamp_dictionary);
+
+        let page_id = *self
+            .raw_document
+            .get_prev_documents()
+            .get_pages()
+            .values()
+            .next()
+            .ok_or_else(|| Error::from("Document does not contain any pages."))?;
+
+        // Field names have to be unique.
+        let existing_names: Vec<&str> = self
+            .acro_form
+            .iter()
+            .flatten()
+            .filter_map(|form| form.get_partial_field_name())
+            .collect();
+        let field_name = (1..)
+            .map(|index| format!("DocTimeStamp{}", index))
+            .find(|name| !existing_names.contains(&name.as_str()))
+            .expect("Infinite iterator always finds a name.");
+
+        // Merged field and widget annotation.
+        // `F` = 132: `Print` and `Locked`, the `Rect` of size 0 makes it invisible.
+        let field_dictionary = Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("Type", Name("Annot".as_bytes().to_vec())),
+            ("Subtype", Name("Widget".as_bytes().to_vec())),
+            ("FT", Name("Sig".as_bytes().to_vec())),
+            (
+                "T",
+                String(field_name.as_bytes().to_vec(), StringFormat::Literal),
+            ),
+            ("V", Reference(timestamp_id)),
+            ("F", Integer(132)),
+            (
+                "Rect",
+                Array(vec![Integer(0), Integer(0), Integer(0), Integer(0)]),
+            ),
+            ("P", Reference(page_id)),
+        ]));
+        let field_id = self.raw_document.new_document.add_object(field_dictionary);
+
+        // Add annotation to page
+        self.raw_document
+            .opt_clone_object_to_new_document(page_id)?;
+        self.push_to_array(page_id, b"Annots", Reference(field_id))?;
+
+        // Add field to `AcroForm`
+        let acro_form_id = self.get_or_create_acro_form()?;
+        self.push_to_array(acro_form_id, b"Fields", Reference(field_id))?;
+        let acro_form = self
+            .raw_document
+            .new_document
+            .get_object_mut(acro_form_id)?
+            .as_dict_mut()?;
+        // `SigFlags` 3: `SignaturesExist` and `AppendOnly`
+        let sig_flags = acro_form
+            .get(b"SigFlags")
+            .and_then(Object::as_i64)
+            .unwrap_or(0);
+        acro_form.set("SigFlags", Integer(sig_flags | 3));
+
+        Ok(field_id)
+    }
+
+    /// Get the `AcroForm` dictionary of the `Root` as an object in the new document.
+    /// It is created when it does not exist yet.
+    pub(crate) fn get_or_create_acro_form(&mut self) -> Result<ObjectId, Error> {
+        let root_id = self
+            .raw_document
+            .get_prev_documents()
+            .trailer
+            .get(b"Root")?
+            .as_reference()?;
+        self.raw_document
+            .opt_clone_object_to_new_document(root_id)?;
+
+        let acro_form = self
+            .raw_document
+            .new_document
+            .get_object(root_id)?
+            .as_dict()?
+            .get(b"AcroForm")
+            .ok()
+            .cloned();
+        match acro_form {
+            Some(Object::Reference(acro_form_id)) => {
+                self.raw_document
+                    .opt_clone_object_to_new_document(acro_form_id)?;
+                Ok(acro_form_id)
+            }
+            acro_form => {
+                // Inline (or missing) dictionary is moved to its own object.
+                let acro_form = match acro_form {
+                    Some(acro_form) => acro_form.as_dict()?.clone(),
+                    None => lopdf::Dictionary::new(),
+                };
+                let acro_form_id = self.raw_document.new_document.add_object(acro_form);
+                self.raw_document
+                    .new_document
+                    .get_object_mut(root_id)?
+                    .as_dict_mut()?
+                    .set("AcroForm", Object::Reference(acro_form_id));
+                Ok(acro_form_id)
+            }
+        }
+    }
+
+    /// Push `value` to the array `key` of a dictionary in the new document.
+    /// The array can be inline, a reference or missing.
+    pub(crate) fn push_to_array(
+        &mut self,
+        dictionary_id: ObjectId,
+        key: &[u8],
+        value: Object,
+    ) -> Result<(), Error> {
+        let array = self
+            .raw_document
+            .new_document
+            .get_object(dictionary_id)?
+            .as_dict()?
+            .get(key)
+            .ok()
+            .cloned();
+        match array {
+            Some(Object::Reference(array_id)) => {
+                self.raw_document
+                    .opt_clone_object_to_new_document(array_id)?;
+                let array = self.raw_document.new_document.get_object_mut(array_id)?;
+                array.as_array_mut()?.push(value);
+            }
+            array => {
+                let mut list = match array {
+                    Some(array) => array.as_array()?.clone(),
+                    None => vec![],
+                };
+                list.push(value);
+                self.raw_document
+                    .new_document
+                    .get_object_mut(dictionary_id)?
+                    .as_dict_mut()?
+                    .set(key, Object::Array(list));
+            }
+        }
+        Ok(())
+    }
+}
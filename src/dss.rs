@@ -0,0 +1,272 @@
+//! Document Security Store (`DSS`) used for long-term validation (PAdES B-LT).
+//!
+//! Structure in the catalog:
+//! - Root (dictionary)
+//!   - DSS (dictionary)
+//!     - Certs (array of streams with DER encoded certificates)
+//!     - OCSPs (array of streams with DER encoded OCSP responses)
+//!     - CRLs (array of streams with DER encoded CRLs)
+//!     - VRI (dictionary, key is the SHA-1 of the signature `Contents`)
+
+use crate::acro_form::FormComponent;
+use crate::error::Error;
+use crate::pdf_object::PdfObjectDeref;
+use crate::PDFSigningDocument;
+use chrono::Utc;
+use lopdf::{Dictionary, Object, ObjectId, Stream, StringFormat};
+use x509_certificate::DigestAlgorithm;
+
+/// Validation material that is embedded in the `DSS`.
+/// All values are DER encoded, they are not checked or fetched.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationData {
+    /// Certificates of the signers, issuers and OCSP responders.
+    pub certs: Vec<Vec<u8>>,
+    /// OCSP responses (`OCSPResponse`).
+    pub ocsps: Vec<Vec<u8>>,
+    /// Certificate revocation lists.
+    pub crls: Vec<Vec<u8>>,
+}
+
+impl PDFSigningDocument {
+    /// Add a `DSS` with the `validation_data` in a new incremental update.
+    ///
+    /// Data that is already in an existing `DSS` is reused. A `VRI` entry is added for every
+    /// signed signature in the document, referencing all the validation data.
+    ///
+    /// Returns the new PDF file, the document itself is also updated.
+    pub fn add_document_security_store(
+        &mut self,
+        validation_data: &ValidationData,
+    ) -> Result<Vec<u8>, Error> {
+        self.load_all()?;
+        let mut update = Vec::new();
+        let result = self
+            .add_dss_dictionary(validation_data)
+            .and_then(|_| self.write_update(&mut update));
+        if let Err(err) = result {
+            self.discard_incremental_update();
+            return Err(err);
+        }
+
+        // Continue with the new document, the form fields did not change.
+        self.append_incremental_update(&update, None)?;
+        Ok(self.raw_document.get_prev_documents_bytes().to_vec())
+    }
+
+    fn add_dss_dictionary(&mut self, validation_data: &ValidationData) -> Result<(), Error> {
+        use lopdf::Object::*;
+
+        let root_id = self
+            .raw_document
+            .get_prev_documents()
+            .trailer
+            .get(b"Root")?
+            .as_reference()?;
+        self.raw_document
+            .opt_clone_object_to_new_document(root_id)?;
+
+        let prev_document = self.raw_document.get_prev_documents();
+        let mut dss = match prev_document.get_object(root_id)?.as_dict()?.get(b"DSS") {
+            Ok(dss) => dss.deref(prev_document)?.as_dict()?.clone(),
+            Err(_) => lopdf::Dictionary::new(),
+        };
+        dss.set("Type", Name("DSS".as_bytes().to_vec()));
+
+        let cert_refs = self.add_dss_streams(&mut dss, "Certs", &validation_data.certs)?;
+        let ocsp_refs = self.add_dss_streams(&mut dss, "OCSPs", &validation_data.ocsps)?;
+        let crl_refs = self.add_dss_streams(&mut dss, "CRLs", &validation_data.crls)?;
+
+        // Validation related information per signature
+        let prev_document = self.raw_document.get_prev_documents();
+        let mut vri = match dss.get(b"VRI") {
+            Ok(vri) => vri.deref(prev_document)?.as_dict()?.clone(),
+            Err(_) => lopdf::Dictionary::new(),
+        };
+        let now = Utc::now();
+        for form in self.acro_form.iter().flatten() {
+            if let FormComponent::SignedSignature { contents, .. } = form.get_form_component() {
+                let vri_key = Self::vri_key(contents);
+                // Keep the references of an existing entry.
+                let mut vri_entry = match vri.get(vri_key.as_bytes()) {
+                    Ok(vri_entry) => vri_entry.deref(prev_document)?.as_dict()?.clone(),
+                    Err(_) => lopdf::Dictionary::new(),
+                };
+                vri_entry.set("Type", Name("VRI".as_bytes().to_vec()));
+                for (key, refs) in [
+                    ("Cert", &cert_refs),
+                    ("OCSP", &ocsp_refs),
+                    ("CRL", &crl_refs),
+                ] {
+                    let mut list = match vri_entry.get(key.as_bytes()) {
+                        Ok(list) => list.deref(prev_document)?.as_array()?.clone(),
+                        Err(_) => vec![],
+                    };
+                    for object_id in refs {
+                        if !list.contains(&Reference(*object_id)) {
+                            list.push(Reference(*object_id));
+                        }
+                    }
+                    if !list.is_empty() {
+                        vri_entry.set(key, Array(list));
+                    }
+                }
+                vri_entry.set(
+                    "TU",
+                    String(
+                        now.format("D:%Y%m%d%H%M%S+00'00'")
+                            .to_string()
+                            .as_bytes()
+                            .to_vec(),
+                        StringFormat::Literal,
+                    ),
+                );
+                vri.set(vri_key, Dictionary(vri_entry));
+            }
+        }
+        dss.set("VRI", Dictionary(vri));
+
+        let dss_id = self.raw_document.new_document.add_object(dss);
+        self.raw_document
+            .new_document
+            .get_object_mut(root_id)?
+            .as_dict_mut()?
+            .set("DSS", Reference(dss_id));
+        Ok(())
+    }
+
+    /// The validation data in the `DSS` of the document, empty when there is no `DSS`.
+    pub fn document_security_store(&self) -> Result<ValidationData, Error> {
+        match self.dss()? {
+            Some(dss) => self.read_validation_data(dss, [b"Certs", b"OCSPs", b"CRLs"]),
+            None => Ok(ValidationData::default()),
+        }
+    }
+
+    /// The validation data in the `VRI` entry of each signature, in the order of `signatures()`.
+    /// `None` for signatures without `VRI` entry.
+    pub fn signature_validation_data(&self) -> Result<Vec<Option<ValidationData>>, Error> {
+        let prev_document = self.raw_document.get_prev_documents();
+        let vri = match self.dss()?.map(|dss| dss.get(b"VRI")) {
+            Some(Ok(vri)) => Some(vri.deref(prev_document)?.as_dict()?),
+            _ => None,
+        };
+        let mut signatures = vec![];
+        for form in self.acro_forms()?.iter() {
+            if let FormComponent::SignedSignature { contents, .. } = form.get_form_component() {
+                let vri_entry = match vri.map(|vri| vri.get(Self::vri_key(contents).as_bytes())) {
+                    Some(Ok(vri_entry)) => vri_entry.deref(prev_document)?.as_dict()?,
+                    _ => {
+                        signatures.push(None);
+                        continue;
+                    }
+                };
+                signatures.push(Some(
+                    self.read_validation_data(vri_entry, [b"Cert", b"OCSP", b"CRL"])?,
+                ));
+            }
+        }
+        Ok(signatures)
+    }
+
+    fn dss(&self) -> Result<Option<&Dictionary>, Error> {
+        let prev_document = self.raw_document.get_prev_documents();
+        let root = prev_document
+            .trailer
+            .get(b"Root")?
+            .deref(prev_document)?
+            .as_dict()?;
+        match root.get(b"DSS") {
+            Ok(dss) => Ok(Some(dss.deref(prev_document)?.as_dict()?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Read the streams in the arrays `keys` (certificates, OCSP responses and CRLs)
+    /// of a `DSS` or `VRI` dictionary.
+    fn read_validation_data(
+        &self,
+        dictionary: &Dictionary,
+        keys: [&[u8]; 3],
+    ) -> Result<ValidationData, Error> {
+        let prev_document = self.raw_document.get_prev_documents();
+        let mut validation_data = ValidationData::default();
+        for (key, list) in keys.into_iter().zip([
+            &mut validation_data.certs,
+            &mut validation_data.ocsps,
+            &mut validation_data.crls,
+        ]) {
+            let items = match dictionary.get(key) {
+                Ok(items) => items.deref(prev_document)?.as_array()?,
+                Err(_) => continue,
+            };
+            for item in items {
+                list.push(
+                    item.deref(prev_document)?
+                        .as_stream()?
+                        .get_plain_content()?,
+                );
+            }
+        }
+        Ok(validation_data)
+    }
+
+    /// Add the `data` as streams to the array `key` of the `dss`.
+    /// Streams that already exist with the same content are reused.
+    ///
+    /// Returns the references to the streams for each item in `data`.
+    fn add_dss_streams(
+        &mut self,
+        dss: &mut Dictionary,
+        key: &str,
+        data: &[Vec<u8>],
+    ) -> Result<Vec<ObjectId>, Error> {
+        let prev_document = self.raw_document.get_prev_documents();
+        let mut list = match dss.get(key.as_bytes()) {
+            Ok(list) => list.deref(prev_document)?.as_array()?.clone(),
+            Err(_) => vec![],
+        };
+        // Content of the streams that are already in the list.
+        let mut existing: Vec<(Vec<u8>, ObjectId)> = vec![];
+        for item in &list {
+            if let Some(object_id) = item.get_object_id() {
+                let stream = prev_document.get_object(object_id)?.as_stream()?;
+                existing.push((stream.get_plain_content()?, object_id));
+            }
+        }
+
+        let mut refs = vec![];
+        for item in data {
+            let object_id = match existing.iter().find(|(content, _)| content == item) {
+                Some((_, object_id)) => *object_id,
+                None => {
+                    let object_id = self
+                        .raw_document
+                        .new_document
+                        .add_object(Stream::new(Dictionary::new(), item.clone()));
+                    existing.push((item.clone(), object_id));
+                    list.push(Object::Reference(object_id));
+                    object_id
+                }
+            };
+            if !refs.contains(&object_id) {
+                refs.push(object_id);
+            }
+        }
+
+        if !list.is_empty() {
+            dss.set(key, Object::Array(list));
+        }
+        Ok(refs)
+    }
+
+    /// The key of the `VRI` entry: uppercase hex of the SHA-1 of the signature `Contents`.
+    fn vri_key(contents: &[u8]) -> String {
+        DigestAlgorithm::Sha1
+            .digest_data(contents)
+            .iter()
+            .map(|num| format!("{:02X}", num))
+            .collect::<Vec<String>>()
+            .join("")
+    }
+}
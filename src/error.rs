@@ -2,6 +2,12 @@
 pub enum Error {
     LoPdfError(lopdf::Error),
     TryFromIntError(std::num::TryFromIntError),
+    X509CertificateError(x509_certificate::X509CertificateError),
+    /// The signature does not fit in the space reserved in `Contents` (sizes in bytes).
+    ContentsTooSmall {
+        available: usize,
+        needed: usize,
+    },
     Other(String),
 }
 
@@ -33,3 +39,17 @@ impl From<std::io::Error> for Error {
         Error::LoPdfError(lopdf::Error::from(err))
     }
 }
+
+impl From<x509_certificate::X509CertificateError> for Error {
+    fn from(err: x509_certificate::X509CertificateError) -> Self {
+        Error::X509CertificateError(err)
+    }
+}
+
+/// Mapped to `Other`, so the variants of `Error` do not depend on the enabled features.
+#[cfg(feature = "pkcs11")]
+impl From<cryptoki::error::Error> for Error {
+    fn from(err: cryptoki::error::Error) -> Self {
+        Error::Other(format!("PKCS#11: {}", err))
+    }
+}
@@ -0,0 +1,122 @@
+//! Locking form fields when a signature is applied (`FieldMDP`).
+//!
+//! Structure:
+//! - Signature field (dictionary)
+//!   - Lock (dictionary, `Type` = `SigFieldLock`)
+//!   - V (signature dictionary)
+//!     - Reference (array)
+//!       - SigRef (dictionary, `TransformMethod` = `FieldMDP`)
+//!         - TransformParams (dictionary, same `Action` and `Fields` as `Lock`)
+
+use crate::error::Error;
+use crate::lopdf_utils;
+use lopdf::{Dictionary, Object, ObjectId, StringFormat};
+
+/// The form fields that are locked when the signature is applied.
+/// Fields are identified by their fully qualified name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldLock {
+    /// Lock all fields in the document.
+    All,
+    /// Lock only these fields.
+    Include(Vec<String>),
+    /// Lock all fields except these.
+    Exclude(Vec<String>),
+}
+
+impl FieldLock {
+    fn action(&self) -> &'static str {
+        match self {
+            FieldLock::All => "All",
+            FieldLock::Include(_) => "Include",
+            FieldLock::Exclude(_) => "Exclude",
+        }
+    }
+
+    fn fields(&self) -> Option<&[String]> {
+        match self {
+            FieldLock::All => None,
+            FieldLock::Include(fields) | FieldLock::Exclude(fields) => Some(fields),
+        }
+    }
+
+    /// Check if the field with the fully qualified `field_name` is locked.
+    /// Locking a field also locks its descendants.
+    pub(crate) fn is_locked(&self, field_name: &str) -> bool {
+        let matches = |fields: &[String]| {
+            fields.iter().any(|field| {
+                field_name == field
+                    || field_name
+                        .strip_prefix(field.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+        };
+        match self {
+            FieldLock::All => true,
+            FieldLock::Include(fields) => matches(fields),
+            FieldLock::Exclude(fields) => !matches(fields),
+        }
+    }
+
+    /// Read the `Action` and `Fields` of a `Lock` or `TransformParams` dictionary.
+    pub(crate) fn from_dictionary(dictionary: &Dictionary) -> Result<Self, Error> {
+        let fields = || -> Result<Vec<String>, Error> {
+            let mut fields = vec![];
+            for field in dictionary.get(b"Fields")?.as_array()? {
+                fields.push(lopdf_utils::as_option_text_string(Some(field))?.unwrap_or_default());
+            }
+            Ok(fields)
+        };
+        match dictionary.get(b"Action")?.as_name()? {
+            b"All" => Ok(FieldLock::All),
+            b"Include" => Ok(FieldLock::Include(fields()?)),
+            b"Exclude" => Ok(FieldLock::Exclude(fields()?)),
+            action => Err(Error::Other(format!(
+                "Unknown field lock action `{}`.",
+                String::from_utf8_lossy(action)
+            ))),
+        }
+    }
+
+    /// The entries shared by `Lock` and `TransformParams`.
+    fn to_dictionary(&self, r#type: &str) -> lopdf::Dictionary {
+        use lopdf::Object::*;
+
+        let mut dictionary = lopdf::Dictionary::from_iter(vec![
+            ("Type", Name(r#type.as_bytes().to_vec())),
+            ("Action", Name(self.action().as_bytes().to_vec())),
+        ]);
+        if let Some(fields) = self.fields() {
+            dictionary.set(
+                "Fields",
+                Array(
+                    fields
+                        .iter()
+                        .map(|field| String(field.as_bytes().to_vec(), StringFormat::Literal))
+                        .collect(),
+                ),
+            );
+        }
+        dictionary
+    }
+
+    /// The `Lock` dictionary of the signature field.
+    pub(crate) fn lock_dictionary(&self) -> Object {
+        Object::Dictionary(self.to_dictionary("SigFieldLock"))
+    }
+
+    /// The `SigRef` dictionary for the `Reference` array of the signature dictionary.
+    pub(crate) fn field_mdp_reference(&self, root_obj_id: ObjectId) -> Object {
+        use lopdf::Object::*;
+
+        let mut transform_params = self.to_dictionary("TransformParams");
+        transform_params.set("V", Name("1.2".as_bytes().to_vec()));
+        Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("Type", Name("SigRef".as_bytes().to_vec())),
+            ("TransformMethod", Name("FieldMDP".as_bytes().to_vec())),
+            ("TransformParams", Dictionary(transform_params)),
+            // The fields are checked in the document catalog.
+            ("Data", Reference(root_obj_id)),
+        ]))
+    }
+}
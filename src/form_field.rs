@@ -0,0 +1,155 @@
+use crate::acro_form::{AcroForm, FormComponent};
+use crate::pdf_object::PdfObjectDeref;
+use crate::{error::Error, PDFSigningDocument, Rectangle};
+use lopdf::{Document, Object, ObjectId};
+
+/// A form field of the document, as stored in the `AcroForm`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    /// Fully qualified name, the partial names (`T`) of the field and its parents
+    /// separated by periods.
+    pub name: Option<String>,
+    /// Partial name (`T`) of the field.
+    pub partial_name: Option<String>,
+    /// Name shown to the user (`TU`).
+    pub alternate_name: Option<String>,
+    pub field_type: FieldType,
+    /// Field flags (`Ff`), the meaning depends on the `field_type`.
+    pub flags: u32,
+    /// Value (`V`) of text, button and choice fields.
+    pub value: Option<FieldValue>,
+    /// Default value (`DV`), used when the form is reset.
+    pub default_value: Option<FieldValue>,
+    /// Default appearance string (`DA`) of text, for example `/Helv 12 Tf 0 g`.
+    pub default_appearance: Option<String>,
+    /// Quadding (`Q`) of text: 0 left, 1 centered, 2 right.
+    pub quadding: Option<i64>,
+    /// The widget annotations that show the field.
+    pub widgets: Vec<FormFieldWidget>,
+    /// Signature field that has a signature value, always `false` for other fields.
+    pub signed: bool,
+}
+
+/// Type of a form field (`FT`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// Push button, check box or radio button (`Btn`).
+    Button,
+    /// Text field (`Tx`).
+    Text,
+    /// List or combo box (`Ch`).
+    Choice,
+    /// Signature field (`Sig`).
+    Signature,
+    Unknown,
+}
+
+/// Value (`V`) of a form field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// Text of a text or choice field.
+    Text(String),
+    /// State of a check box or radio button, for example `Off`.
+    Name(String),
+    /// Selected options of a choice field.
+    List(Vec<String>),
+}
+
+/// A widget annotation of a form field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormFieldWidget {
+    pub object_id: ObjectId,
+    /// Page number, starting at 1.
+    pub page: Option<u32>,
+    /// Position on the page.
+    pub rect: Option<Rectangle>,
+}
+
+impl FieldValue {
+    /// Read a value, `None` for values that are not text, names or lists (rich text streams,
+    /// signature dictionaries).
+    pub(crate) fn from_object(raw_doc: &Document, value: &Object) -> Option<Self> {
+        match value.deref(raw_doc).ok()? {
+            Object::String(..) => Some(FieldValue::Text(Self::text(value)?)),
+            Object::Name(name) => {
+                Some(FieldValue::Name(String::from_utf8_lossy(name).into_owned()))
+            }
+            Object::Array(list) => Some(FieldValue::List(
+                list.iter()
+                    .filter_map(|item| Self::text(item.deref(raw_doc).ok()?))
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn text(value: &Object) -> Option<String> {
+        lopdf::decode_text_string(value).ok()
+    }
+}
+
+impl From<&FormComponent> for FieldType {
+    fn from(component: &FormComponent) -> Self {
+        match component {
+            FormComponent::Button | FormComponent::Radio | FormComponent::CheckBox => {
+                FieldType::Button
+            }
+            FormComponent::Text => FieldType::Text,
+            FormComponent::ListBox | FormComponent::ComboBox => FieldType::Choice,
+            FormComponent::EmptySignature | FormComponent::SignedSignature { .. } => {
+                FieldType::Signature
+            }
+            FormComponent::Unknown => FieldType::Unknown,
+        }
+    }
+}
+
+impl PDFSigningDocument {
+    /// List the form fields, in the order of the `AcroForm`.
+    pub fn form_fields(&self) -> Result<Vec<FormField>, Error> {
+        let raw_doc = self.raw_document.get_prev_documents();
+        self.acro_forms()?
+            .iter()
+            .map(|form| FormField::load(raw_doc, form))
+            .collect()
+    }
+}
+
+impl FormField {
+    fn load(raw_doc: &Document, form: &AcroForm) -> Result<Self, Error> {
+        let widgets = match form.get_object_id() {
+            Some(_) => form
+                .get_widgets(raw_doc)?
+                .into_iter()
+                .map(|widget_id| {
+                    Ok(FormFieldWidget {
+                        object_id: widget_id,
+                        page: AcroForm::get_widget_page_number(raw_doc, widget_id),
+                        rect: AcroForm::get_widget_rect(raw_doc, widget_id)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            // Fields that are not indirect objects can not be referenced by widgets.
+            None => vec![],
+        };
+        let form_component = form.get_form_component();
+        Ok(FormField {
+            name: form.get_fully_qualified_name().map(str::to_owned),
+            partial_name: form.get_partial_field_name().map(str::to_owned),
+            alternate_name: form.get_alternate_field_name().map(str::to_owned),
+            field_type: form_component.into(),
+            flags: form.get_field_flags(),
+            value: form.get_value().cloned(),
+            default_value: form.get_default_value().cloned(),
+            default_appearance: form.get_default_appearance().map(str::to_owned),
+            quadding: form.get_quadding(),
+            widgets,
+            signed: matches!(form_component, FormComponent::SignedSignature { .. }),
+        })
+    }
+
+    /// Page number of the first widget, starting at 1.
+    pub fn page(&self) -> Option<u32> {
+        self.widgets.iter().find_map(|widget| widget.page)
+    }
+}
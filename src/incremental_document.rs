@@ -0,0 +1,511 @@
+//! A PDF file with a new incremental update on top of it.
+//!
+//! Same as `lopdf::IncrementalDocument`, but the previous revisions are owned by this crate.
+//! A written incremental update is moved into them, so signing many fields does not copy
+//! the document for every signature.
+//!
+//! The functions at the end of this file are ported from the writer of `lopdf` 0.34 and
+//! write the same output. `lopdf` can only write the previous revisions and the update
+//! together, its `Writer` is in a private module. Signing needs to write only the update,
+//! to hash it while writing. Remove them when `lopdf` exports its writer.
+
+use crate::error::Error;
+use lopdf::xref::{Xref, XrefEntry, XrefSection, XrefType};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct IncrementalDocument {
+    /// The PDF file, all previous revisions.
+    bytes_documents: Vec<u8>,
+    /// The objects of all previous revisions combined.
+    /// Do not edit this document as it will not be saved.
+    prev_documents: Document,
+    /// The new incremental update, appended to the previous revisions when written.
+    pub new_document: Document,
+}
+
+impl IncrementalDocument {
+    /// Load a PDF document from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::load_from(std::fs::File::open(path)?)
+    }
+
+    /// Load a PDF document from an arbitrary source.
+    pub fn load_from<R: Read>(mut source: R) -> Result<Self, Error> {
+        let mut buffer = Vec::new();
+        source.read_to_end(&mut buffer)?;
+        let document = Document::load_mem(&buffer)?;
+        Ok(Self::create_from(buffer, document))
+    }
+
+    /// Create an `IncrementalDocument` from the bytes of a PDF file and the document read
+    /// from them. If they do not match exactly this might result in broken PDFs.
+    pub fn create_from(prev_bytes: Vec<u8>, prev_documents: Document) -> Self {
+        Self {
+            bytes_documents: prev_bytes,
+            new_document: Self::new_update(&prev_documents),
+            prev_documents,
+        }
+    }
+
+    /// Get the structure of the previous documents (all previous revisions combined).
+    pub fn get_prev_documents(&self) -> &Document {
+        &self.prev_documents
+    }
+
+    /// Get the bytes of the previous documents.
+    pub fn get_prev_documents_bytes(&self) -> &[u8] {
+        &self.bytes_documents
+    }
+
+    /// Clone an object from the previous documents to the new document, so it can be changed
+    /// in the incremental update. If the object already exists nothing is done.
+    pub fn opt_clone_object_to_new_document(&mut self, object_id: ObjectId) -> Result<(), Error> {
+        if !self.new_document.has_object(object_id) {
+            let old_object = self.prev_documents.get_object(object_id)?;
+            self.new_document.set_object(object_id, old_object.clone());
+        }
+        Ok(())
+    }
+
+    /// Add an XObject to the `Resources` of a page.
+    pub fn add_xobject<N: Into<Vec<u8>>>(
+        &mut self,
+        page_id: ObjectId,
+        xobject_name: N,
+        xobject_id: ObjectId,
+    ) -> Result<(), Error> {
+        let resources = self.get_or_create_resources(page_id)?;
+        if !resources.has(b"XObject") {
+            resources.set("XObject", Dictionary::new());
+        }
+        let xobjects_id = match resources.get(b"XObject")? {
+            Object::Reference(xobjects_id) => Some(*xobjects_id),
+            _ => None,
+        };
+        let xobjects = match xobjects_id {
+            Some(xobjects_id) => {
+                self.opt_clone_object_to_new_document(xobjects_id)?;
+                self.new_document.get_object_mut(xobjects_id)?
+            }
+            None => self.get_or_create_resources(page_id)?.get_mut(b"XObject")?,
+        };
+        xobjects
+            .as_dict_mut()?
+            .set(xobject_name, Object::Reference(xobject_id));
+        Ok(())
+    }
+
+    /// The `Resources` dictionary of a page in the new document, created when missing.
+    fn get_or_create_resources(&mut self, page_id: ObjectId) -> Result<&mut Dictionary, Error> {
+        self.opt_clone_object_to_new_document(page_id)?;
+        let resources_id = match self.new_document.get_dictionary(page_id)?.get(b"Resources") {
+            Ok(Object::Reference(resources_id)) => Some(*resources_id),
+            _ => None,
+        };
+        if let Some(resources_id) = resources_id {
+            self.opt_clone_object_to_new_document(resources_id)?;
+            return Ok(self.new_document.get_dictionary_mut(resources_id)?);
+        }
+        let page = self.new_document.get_dictionary_mut(page_id)?;
+        if !page.has(b"Resources") {
+            page.set("Resources", Dictionary::new());
+        }
+        Ok(page.get_mut(b"Resources")?.as_dict_mut()?)
+    }
+
+    /// Write the previous revisions and the new incremental update.
+    /// Streams of the update are compressed when written, the document is not changed.
+    pub fn save_to<W: Write>(&self, target: &mut W) -> Result<(), Error> {
+        target.write_all(&self.bytes_documents)?;
+        self.write_update(target)
+    }
+
+    /// Write only the new incremental update, to be appended to `get_prev_documents_bytes()`.
+    pub(crate) fn write_update<W: Write>(&self, target: &mut W) -> Result<(), Error> {
+        let mut target = CountingWrite {
+            inner: target,
+            bytes_written: self.bytes_documents.len(),
+        };
+        let mut xref = Xref::new(self.new_document.max_id + 1, self.xref_type());
+
+        if self
+            .bytes_documents
+            .last()
+            .is_some_and(|byte| *byte != b'\n')
+        {
+            writeln!(target)?;
+        }
+        writeln!(target, "%PDF-{}", self.new_document.version)?;
+
+        for (&(id, generation), object) in &self.new_document.objects {
+            if object
+                .type_name()
+                .map(|name| ["ObjStm", "XRef", "Linearized"].contains(&name))
+                .ok()
+                != Some(true)
+            {
+                let object = Self::compressed(object)?;
+                write_indirect_object(&mut target, id, generation, &object, &mut xref)?;
+            }
+        }
+
+        let xref_start = target.bytes_written;
+        let mut trailer = self.new_document.trailer.clone();
+        match xref.cross_reference_type {
+            XrefType::CrossReferenceTable => {
+                write_xref(&mut target, &xref)?;
+                trailer.set("Size", i64::from(self.new_document.max_id + 1));
+                target.write_all(b"trailer\n")?;
+                write_dictionary(&mut target, &trailer)?;
+            }
+            XrefType::CrossReferenceStream => {
+                write_cross_reference_stream(
+                    &mut target,
+                    &mut xref,
+                    trailer,
+                    self.new_document.max_id + 1,
+                    xref_offset(xref_start)?,
+                )?;
+            }
+        }
+        write!(target, "\nstartxref\n{}\n%%EOF", xref_start)?;
+        Ok(())
+    }
+
+    /// Add the incremental update written with `write_update()` to the previous revisions,
+    /// and start a new incremental update. The objects are moved, not copied.
+    ///
+    /// `update` has to contain the objects of the new document as written, `xref_start` is
+    /// the offset of its cross-reference section in the file.
+    pub(crate) fn append_update(&mut self, update: &[u8], xref_start: usize) {
+        let xref_type = self.xref_type();
+        let new_document = std::mem::take(&mut self.new_document);
+        let prev_documents = &mut self.prev_documents;
+        prev_documents.objects.extend(new_document.objects);
+        prev_documents.trailer = new_document.trailer;
+        prev_documents.max_id = new_document.max_id;
+        // The cross-reference stream is an object as well.
+        if matches!(xref_type, XrefType::CrossReferenceStream) {
+            prev_documents.max_id += 1;
+        }
+        prev_documents
+            .trailer
+            .set("Size", i64::from(prev_documents.max_id + 1));
+        prev_documents.xref_start = xref_start;
+
+        self.bytes_documents.extend_from_slice(update);
+        self.new_document = Self::new_update(&self.prev_documents);
+    }
+
+    /// Drop all changes in the new incremental update.
+    pub(crate) fn discard_update(&mut self) {
+        self.new_document = Self::new_update(&self.prev_documents);
+    }
+
+    fn new_update(prev_documents: &Document) -> Document {
+        let mut new_document = Document::new_from_prev(prev_documents);
+        // Set PDF version, version 1.5 is the minimum version required.
+        new_document.version = "1.5".to_owned();
+        new_document
+    }
+
+    fn xref_type(&self) -> XrefType {
+        self.prev_documents.reference_table.cross_reference_type
+    }
+
+    /// The object as written: streams that allow compression and have no filter yet are
+    /// compressed, like `Document::compress()`.
+    fn compressed(object: &Object) -> Result<Cow<'_, Object>, Error> {
+        match object {
+            Object::Stream(stream)
+                if stream.allows_compression && stream.dict.get(b"Filter").is_err() =>
+            {
+                let mut stream = stream.clone();
+                stream.compress()?;
+                Ok(Cow::Owned(Object::Stream(stream)))
+            }
+            _ => Ok(Cow::Borrowed(object)),
+        }
+    }
+}
+
+/// Keeps track of the offset in the PDF file.
+struct CountingWrite<W: Write> {
+    inner: W,
+    bytes_written: usize,
+}
+
+impl<W: Write> Write for CountingWrite<W> {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        let bytes = self.inner.write(buffer)?;
+        self.bytes_written += bytes;
+        Ok(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Offsets in the cross-reference section of `lopdf` are `u32`.
+fn xref_offset(offset: usize) -> Result<u32, Error> {
+    u32::try_from(offset)
+        .map_err(|_| Error::from("PDF files larger than 4 GiB can not be written."))
+}
+
+fn write_indirect_object<W: Write>(
+    target: &mut CountingWrite<W>,
+    id: u32,
+    generation: u16,
+    object: &Object,
+    xref: &mut Xref,
+) -> Result<(), Error> {
+    let offset = xref_offset(target.bytes_written)?;
+    xref.insert(id, XrefEntry::Normal { offset, generation });
+    write!(
+        target,
+        "{} {} obj\n{}",
+        id,
+        generation,
+        if need_separator(object) { " " } else { "" }
+    )?;
+    write_object(target, object)?;
+    writeln!(
+        target,
+        "{}\nendobj",
+        if need_end_separator(object) { " " } else { "" }
+    )?;
+    Ok(())
+}
+
+/// Cross-reference table, with a section for each range of consecutive objects.
+fn write_xref(target: &mut dyn Write, xref: &Xref) -> Result<(), Error> {
+    writeln!(target, "xref")?;
+    let mut xref_section = XrefSection::new(0);
+    xref_section.add_unusable_free_entry();
+    for object_id in 1..xref.size {
+        if xref_section.is_empty() {
+            xref_section = XrefSection::new(object_id);
+        }
+        match xref.get(object_id) {
+            Some(XrefEntry::Compressed { .. }) | Some(XrefEntry::UnusableFree) => {
+                xref_section.add_unusable_free_entry()
+            }
+            Some(entry) => xref_section.add_entry(entry.clone()),
+            None if !xref_section.is_empty() => {
+                xref_section.write_xref_section(target)?;
+                xref_section = XrefSection::new(object_id);
+            }
+            None => {}
+        }
+    }
+    if !xref_section.is_empty() {
+        xref_section.write_xref_section(target)?;
+    }
+    Ok(())
+}
+
+/// Cross-reference stream, it replaces the table and the trailer.
+/// `id` is the object number of the stream, the next free number.
+fn write_cross_reference_stream<W: Write>(
+    target: &mut CountingWrite<W>,
+    xref: &mut Xref,
+    mut trailer: Dictionary,
+    id: u32,
+    xref_start: u32,
+) -> Result<(), Error> {
+    xref.insert(
+        id,
+        XrefEntry::Normal {
+            offset: xref_start,
+            generation: 0,
+        },
+    );
+
+    let mut sections: Vec<XrefSection> = vec![];
+    for object_id in 1..=id {
+        match xref.get(object_id) {
+            Some(entry) => match sections.last_mut() {
+                Some(section)
+                    if section.starting_id + section.entries.len() as u32 == object_id =>
+                {
+                    section.add_entry(entry.clone())
+                }
+                _ => {
+                    let mut section = XrefSection::new(object_id);
+                    section.add_entry(entry.clone());
+                    sections.push(section);
+                }
+            },
+            None => continue,
+        }
+    }
+
+    // Each entry is `[u8, u32, u16]`.
+    let mut content = vec![];
+    let mut index = vec![];
+    for section in sections {
+        index.push(Object::Integer(section.starting_id.into()));
+        index.push(Object::Integer(section.entries.len() as i64));
+        for (object_id, entry) in (section.starting_id..).zip(section.entries) {
+            match entry {
+                XrefEntry::Free => {
+                    content.push(0);
+                    content.extend(object_id.to_be_bytes());
+                    content.extend([0, 0]);
+                }
+                XrefEntry::UnusableFree => {
+                    content.push(0);
+                    content.extend(object_id.to_be_bytes());
+                    content.extend(u16::MAX.to_be_bytes());
+                }
+                XrefEntry::Normal { offset, generation } => {
+                    content.push(1);
+                    content.extend(offset.to_be_bytes());
+                    content.extend(generation.to_be_bytes());
+                }
+                XrefEntry::Compressed { container, index } => {
+                    content.push(2);
+                    content.extend(container.to_be_bytes());
+                    content.extend(index.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    trailer.set("Type", Object::Name(b"XRef".to_vec()));
+    trailer.set("Size", i64::from(id + 1));
+    trailer.set(
+        "W",
+        Object::Array(vec![
+            Object::Integer(1),
+            Object::Integer(4),
+            Object::Integer(2),
+        ]),
+    );
+    trailer.set("Index", Object::Array(index));
+    trailer.remove(b"Filter");
+    trailer.set("Length", content.len() as i64);
+    let stream = Object::Stream(Stream {
+        dict: trailer,
+        allows_compression: true,
+        content,
+        start_position: None,
+    });
+    write_indirect_object(target, id, 0, &stream, xref)
+}
+
+fn need_separator(object: &Object) -> bool {
+    use lopdf::Object::*;
+    matches!(
+        object,
+        Null | Boolean(_) | Integer(_) | Real(_) | Reference(_)
+    )
+}
+
+fn need_end_separator(object: &Object) -> bool {
+    use lopdf::Object::*;
+    matches!(
+        object,
+        Null | Boolean(_) | Integer(_) | Real(_) | Name(_) | Reference(_) | Stream(_)
+    )
+}
+
+fn write_object(target: &mut dyn Write, object: &Object) -> Result<(), Error> {
+    match object {
+        Object::Null => target.write_all(b"null")?,
+        Object::Boolean(true) => target.write_all(b"true")?,
+        Object::Boolean(false) => target.write_all(b"false")?,
+        Object::Integer(value) => write!(target, "{}", value)?,
+        Object::Real(value) => write!(target, "{}", value)?,
+        Object::Name(name) => write_name(target, name)?,
+        Object::String(text, format) => write_string(target, text, format)?,
+        Object::Array(array) => {
+            target.write_all(b"[")?;
+            for (index, object) in array.iter().enumerate() {
+                if index > 0 && need_separator(object) {
+                    target.write_all(b" ")?;
+                }
+                write_object(target, object)?;
+            }
+            target.write_all(b"]")?;
+        }
+        Object::Dictionary(dictionary) => write_dictionary(target, dictionary)?,
+        Object::Stream(stream) => {
+            write_dictionary(target, &stream.dict)?;
+            target.write_all(b"stream\n")?;
+            target.write_all(&stream.content)?;
+            target.write_all(b"\nendstream")?;
+        }
+        Object::Reference((id, generation)) => write!(target, "{} {} R", id, generation)?,
+    }
+    Ok(())
+}
+
+fn write_name(target: &mut dyn Write, name: &[u8]) -> Result<(), Error> {
+    target.write_all(b"/")?;
+    for &byte in name {
+        // White-space, delimiters and bytes outside of `!` to `~` are written as `#XX`.
+        if b" \t\n\r\x0C()<>[]{}/%#".contains(&byte) || !(33..=126).contains(&byte) {
+            write!(target, "#{:02X}", byte)?;
+        } else {
+            target.write_all(&[byte])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_string(target: &mut dyn Write, text: &[u8], format: &StringFormat) -> Result<(), Error> {
+    match format {
+        // Backslashes, carriage returns and unbalanced parentheses are escaped.
+        StringFormat::Literal => {
+            let mut escape = vec![];
+            let mut parentheses = vec![];
+            for (index, byte) in text.iter().enumerate() {
+                match byte {
+                    b'(' => parentheses.push(index),
+                    b')' if parentheses.pop().is_none() => escape.push(index),
+                    b')' => {}
+                    b'\\' | b'\r' => escape.push(index),
+                    _ => {}
+                }
+            }
+            escape.extend(parentheses);
+
+            target.write_all(b"(")?;
+            for (index, &byte) in text.iter().enumerate() {
+                if escape.contains(&index) {
+                    target.write_all(&[b'\\', if byte == b'\r' { b'r' } else { byte }])?;
+                } else {
+                    target.write_all(&[byte])?;
+                }
+            }
+            target.write_all(b")")?;
+        }
+        StringFormat::Hexadecimal => {
+            target.write_all(b"<")?;
+            for byte in text {
+                write!(target, "{:02X}", byte)?;
+            }
+            target.write_all(b">")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_dictionary(target: &mut dyn Write, dictionary: &Dictionary) -> Result<(), Error> {
+    target.write_all(b"<<")?;
+    for (key, value) in dictionary {
+        write_name(target, key)?;
+        if need_separator(value) {
+            target.write_all(b" ")?;
+        }
+        write_object(target, value)?;
+    }
+    target.write_all(b">>")?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Apply a written incremental update to the document, without parsing the whole file again.
+//!
+//! After writing, the objects of the new incremental update are moved to the previous
+//! document, so the next incremental update can be added on top of it.
+
+use crate::error::Error;
+use crate::{ByteRange, PDFSigningDocument};
+use lopdf::{Object, StringFormat};
+
+impl PDFSigningDocument {
+    /// Append `update` to the document. It has to be the incremental update written with
+    /// `write_update()`, with the signature (if any) filled in at `byte_range`.
+    ///
+    /// Only the update is added to the bytes of the document and the objects are moved,
+    /// nothing is copied or parsed again.
+    pub(crate) fn append_incremental_update(
+        &mut self,
+        update: &[u8],
+        byte_range: Option<&ByteRange>,
+    ) -> Result<(), Error> {
+        let offset = self.raw_document.get_prev_documents_bytes().len();
+        if let Some(byte_range) = byte_range {
+            self.fill_in_signature_placeholder(update, offset, byte_range)?;
+        }
+        let xref_start = Self::xref_start(update)?;
+        self.raw_document.append_update(update, xref_start);
+        Ok(())
+    }
+
+    /// Drop all changes in the new incremental update.
+    pub(crate) fn discard_incremental_update(&mut self) {
+        self.raw_document.discard_update();
+        // Images added in the dropped update can not be reused.
+        let max_id = self.raw_document.get_prev_documents().max_id;
+        self.image_signature_object_id
+            .retain(|_, object_id| object_id.0 <= max_id);
+    }
+
+    /// Set `ByteRange` and `Contents` of the signature dictionary to the values in the
+    /// `update`, which starts at `offset` in the file.
+    fn fill_in_signature_placeholder(
+        &mut self,
+        update: &[u8],
+        offset: usize,
+        byte_range: &ByteRange,
+    ) -> Result<(), Error> {
+        let placeholder = Self::byte_range_placeholder();
+        let signature_dictionary = self
+            .raw_document
+            .new_document
+            .objects
+            .values_mut()
+            .filter_map(|object| object.as_dict_mut().ok())
+            .find(|dict| dict.get(b"ByteRange").ok() == Some(&placeholder))
+            .ok_or_else(|| Error::from("Signature placeholder not found in document."))?;
+
+        // The `Contents` hex string, without `<` and `>`.
+        let contents_hex = byte_range
+            .0
+            .get(1..3)
+            .and_then(|range| update.get((range[0] + 1 - offset)..(range[1] - 1 - offset)))
+            .ok_or_else(|| Error::from("Signature `Contents` is outside of the update."))?;
+        let contents = contents_hex
+            .chunks(2)
+            .map(|hex| {
+                std::str::from_utf8(hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| Error::from("Signature `Contents` is not a hex string."))
+            })
+            .collect::<Result<Vec<u8>, Error>>()?;
+
+        signature_dictionary.set(
+            "ByteRange",
+            Object::Array(
+                byte_range
+                    .0
+                    .iter()
+                    .map(|value| Object::Integer(*value as i64))
+                    .collect(),
+            ),
+        );
+        signature_dictionary.set(
+            "Contents",
+            Object::String(contents, StringFormat::Hexadecimal),
+        );
+        Ok(())
+    }
+
+    /// The offset of the last cross-reference section, from `startxref` at the end of the file.
+    fn xref_start(update: &[u8]) -> Result<usize, Error> {
+        let tail = &update[update.len().saturating_sub(64)..];
+        let tail = String::from_utf8_lossy(tail);
+        tail.rsplit_once("startxref")
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .and_then(|offset| offset.parse().ok())
+            .ok_or_else(|| Error::from("`startxref` not found at the end of the document."))
+    }
+}
@@ -1,32 +1,81 @@
 mod acro_form;
 mod byte_range;
+mod certification;
+mod cms;
+mod deferred_signing;
 mod digitally_sign;
+mod document_timestamp;
+mod dss;
 mod error;
+mod field_lock;
+mod form_field;
 mod image_insert;
 mod image_insert_to_page;
 mod image_xobject;
+mod incremental_document;
+mod incremental_update;
 mod lopdf_utils;
+mod modification_detection;
 mod pdf_object;
+mod pdf_signer;
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
 mod rectangle;
+mod revision_diff;
+mod revisions;
+mod revocation;
+mod signature_algorithm;
+mod signature_field;
+mod signature_format;
 mod signature_image;
 mod signature_info;
+mod signing_keys;
+mod timestamp;
+mod trust_store;
 mod user_signature_info;
+mod verify;
 
 use acro_form::AcroForm;
 use byte_range::ByteRange;
+use chrono::{SubsecRound, Utc};
+use digitally_sign::SigningTarget;
 use image_insert::InsertImage;
 use image_insert_to_page::InsertImageToPage;
 use lopdf::{
     content::{Content, Operation},
-    Document, IncrementalDocument, Object, ObjectId,
+    Document, Object, ObjectId,
 };
 use pdf_object::PdfObjectDeref;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::{BufWriter, Cursor, Seek, Write};
 use std::{fs::File, path::Path};
 
+pub use acro_form::{SignBuildDictionary, SignBuildProperties};
+pub use certification::DocMdpPermission;
+pub use deferred_signing::{ExternalSignature, PreparedSignature};
+pub use dss::ValidationData;
 pub use error::Error;
+pub use field_lock::FieldLock;
+pub use form_field::{FieldType, FieldValue, FormField, FormFieldWidget};
+pub use incremental_document::IncrementalDocument;
 pub use lopdf;
+pub use modification_detection::{Modification, ModificationKind, ModificationReport};
+pub use pdf_signer::PdfSigner;
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::{Pkcs11KeyId, Pkcs11Signer};
+pub use rectangle::Rectangle;
+pub use revision_diff::{ObjectChange, ObjectDiff, ObjectOwner, RevisionDiffReport};
+pub use revisions::Revision;
+pub use revocation::{CertificateRevocation, RevocationSource, RevocationStatus};
+pub use signature_algorithm::SignatureAlgorithm;
+pub use signature_format::SignatureFormat;
+pub use signature_info::SignatureInfo;
+pub use signing_keys::SigningKeys;
+pub use timestamp::{HttpTimestampProvider, LocalTimestampAuthority, TimestampProvider};
+pub use trust_store::{ChainProblem, ChainValidation, KeyPurpose, TrustStatus, TrustStore};
 pub use user_signature_info::{UserFormSignatureInfo, UserSignatureInfo};
+pub use verify::{SignatureStatus, SignatureVerification, TimestampKind, TimestampVerification};
 
 /// The whole PDF document. This struct only loads part of the document on demand.
 #[derive(Debug, Clone)]
@@ -83,21 +132,35 @@ impl PDFSigningDocument {
         Ok(())
     }
 
+    /// The loaded form fields, or the form fields read from the document when not loaded.
+    fn acro_forms(&self) -> Result<Cow<'_, [AcroForm]>, Error> {
+        Ok(match &self.acro_form {
+            Some(acro_forms) => Cow::Borrowed(acro_forms),
+            None => Cow::Owned(AcroForm::load_all_forms(
+                self.raw_document.get_prev_documents(),
+            )?),
+        })
+    }
+
     /// Save document to file
     pub fn save_document<P: AsRef<Path>>(&self, path: P) -> Result<File, Error> {
-        // Create clone so we can compress the clone, not the original.
-        let mut raw_document = self.raw_document.clone();
-        raw_document.new_document.compress();
-        Ok(raw_document.save(path)?)
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_document(&mut file)?;
+        file.into_inner()
+            .map_err(|err| Error::from(err.into_error()))
     }
 
-    /// Write document to Writer or buffer
+    /// Write document to Writer or buffer.
+    /// The streams of the new incremental update are compressed while writing.
     pub fn write_document<W: std::io::Write>(&self, target: &mut W) -> Result<(), Error> {
-        // Create clone so we can compress the clone, not the original.
-        let mut raw_document = self.raw_document.clone();
-        raw_document.new_document.compress();
-        raw_document.save_to(target)?;
-        Ok(())
+        self.raw_document.save_to(target)
+    }
+
+    /// Compress the new incremental update and write only the update,
+    /// to be applied with `append_incremental_update()`.
+    fn write_update<W: std::io::Write>(&mut self, target: &mut W) -> Result<(), Error> {
+        self.raw_document.new_document.compress();
+        self.raw_document.write_update(target)
     }
 
     pub fn get_incr_document_ref(&self) -> &IncrementalDocument {
@@ -112,21 +175,44 @@ impl PDFSigningDocument {
         &self.raw_document.new_document
     }
 
+    /// Sign all empty signature fields of the users, each signature in a new incremental
+    /// update. Returns the signed PDF file, the document itself is also updated.
     pub fn sign_document(
         &mut self,
         users_signature_info: Vec<UserSignatureInfo>,
     ) -> Result<Vec<u8>, Error> {
-        self.load_all()?;
-        // Set PDF version, version 1.5 is the minimum version required.
-        self.raw_document.new_document.version = "1.5".to_owned();
+        // Returns the initial document when no signing was done.
+        self.sign_document_to(users_signature_info, Cursor::new(Vec::new()))
+            .map(Cursor::into_inner)
+    }
 
-        // loop over AcroForm elements
-        let mut acro_forms = self.acro_form.clone();
-        let mut last_binary_pdf = None;
+    /// Same as `sign_document()`, but the signed document is written to `target`.
+    ///
+    /// The file is written once, each signature is hashed while it is written and its
+    /// `Contents` are filled in by seeking back. `ByteRange` offsets are relative to the
+    /// position of `target` when called.
+    pub fn sign_document_to<W: Write + Seek>(
+        &mut self,
+        users_signature_info: Vec<UserSignatureInfo>,
+        target: W,
+    ) -> Result<W, Error> {
+        let mut target = SigningTarget::new(target);
+        self.sign_form_fields(users_signature_info, &mut target)?;
+        // The rest of the document, all of it when no field was signed.
+        target.write_prev_documents(self.raw_document.get_prev_documents_bytes(), None)?;
+        Ok(target.file)
+    }
 
-        // Take the first form field (if there is any)
-        let mut form_field_current = acro_forms.as_ref().and_then(|list| list.first().cloned());
-        let mut form_field_index = 0;
+    /// Sign all empty signature fields of the users, each signature in a new incremental update.
+    ///
+    /// On error the unfinished incremental update is dropped,
+    /// the signatures before it stay applied to this document.
+    fn sign_form_fields<W: Write + Seek>(
+        &mut self,
+        users_signature_info: Vec<UserSignatureInfo>,
+        target: &mut SigningTarget<W>,
+    ) -> Result<(), Error> {
+        self.load_all()?;
 
         // Covert `Vec<UserSignatureInfo>` to `HashMap<String, UserSignatureInfo>`
         let users_signature_info_map: HashMap<String, UserSignatureInfo> = users_signature_info
@@ -134,82 +220,60 @@ impl PDFSigningDocument {
             .map(|info| (info.user_id.clone(), info.clone()))
             .collect();
 
-        // Make sure we never end up in an infinite loop, should not happen.
-        // But better safe then sorry.
-        let mut loop_counter: u16 = 0;
-        // Loop over all the form fields and sign them one by one.
-        while let Some(form_field) = form_field_current {
-            loop_counter += 1;
-            if loop_counter >= 10000 {
-                log::error!(
-                    "Infinite loop detected and prevented. Please check file: `{}`.",
-                    self.file_name
-                );
-                break;
-            }
-            // Check if it is a signature and it is already signed.
-            if !form_field.is_empty_signature() {
-                // Go to next form field if pdf did not change
-                form_field_index += 1;
-                form_field_current = acro_forms
-                    .as_ref()
-                    .and_then(|list| list.get(form_field_index).cloned());
-                // Go back to start of while loop
-                continue;
+        // Signing a field only changes that field, so the fields are visited once.
+        let form_field_count = self.acro_form.as_ref().map_or(0, Vec::len);
+        for form_field_index in 0..form_field_count {
+            if let Err(err) =
+                self.sign_form_field(form_field_index, &users_signature_info_map, target)
+            {
+                self.discard_incremental_update();
+                return Err(err);
             }
+        }
+        Ok(())
+    }
 
-            // TODO: Debug code, can be removed
-            // if form_field_index == 1 {
-            //     form_field_index += 1;
-            //     form_field_current = acro_forms
-            //         .as_ref()
-            //         .and_then(|list| list.get(form_field_index).cloned());
-            //     continue;
-            // }
-
-            // Update pdf (when nothing else is incorrect)
-            // Insert signature images into pdf itself.
-            let pdf_document_user_info_opt =
-                self.add_signature_images(form_field, &users_signature_info_map)?;
-
-            // PDF has been updated, now we need to digitally sign it.
-            if let Some((pdf_document_image, user_form_info)) = pdf_document_user_info_opt {
-                // Digitally sign the document using a cert.
-                let user_info = users_signature_info_map
-                    .get(&user_form_info.user_id)
-                    .ok_or_else(|| Error::Other("User was not found".to_owned()))?;
+    /// Sign the form field when it is an empty signature field of one of the users.
+    fn sign_form_field<W: Write + Seek>(
+        &mut self,
+        form_field_index: usize,
+        users_signature_info_map: &HashMap<String, UserSignatureInfo>,
+        target: &mut SigningTarget<W>,
+    ) -> Result<(), Error> {
+        let acro_forms = self.acro_form.as_deref().unwrap_or_default();
+        let form_field = acro_forms[form_field_index].clone();
+        // Check if it is a signature and it is already signed.
+        if !form_field.is_empty_signature() {
+            return Ok(());
+        }
 
-                let new_binary_pdf = pdf_document_image.digitally_sign_document(user_info)?;
-                // Reload file
-                self.copy_from(Self::read_from(
-                    &*new_binary_pdf,
-                    pdf_document_image.file_name,
-                )?);
-                self.load_all()?;
-                self.raw_document.new_document.version = "1.5".to_owned();
-                acro_forms = self.acro_form.clone();
-                // Set as return value
-                last_binary_pdf = Some(new_binary_pdf);
-                // Reset form field index
-                form_field_index = 0;
-            } else {
-                // Go to next form field because pdf did not change
-                form_field_index += 1;
-            }
+        // Update pdf (when nothing else is incorrect)
+        // Insert signature images into pdf itself.
+        // The same time is used for `M` and the signing time in the CMS.
+        let signing_time = Utc::now().trunc_subsecs(0);
+        let user_form_info = match self.add_signature_images(
+            form_field.clone(),
+            users_signature_info_map,
+            signing_time,
+        )? {
+            Some(user_form_info) => user_form_info,
+            // Field is not for one of the users, pdf did not change.
+            None => return Ok(()),
+        };
 
-            // Load next form field (or set to `0` depending on index.)
-            form_field_current = acro_forms
-                .as_ref()
-                .and_then(|list| list.get(form_field_index).cloned());
-        }
+        // PDF has been updated, now we need to digitally sign it.
+        let user_info = users_signature_info_map
+            .get(&user_form_info.user_id)
+            .ok_or_else(|| Error::Other("User was not found".to_owned()))?;
+        let (update, byte_range) = self.digitally_sign_document(user_info, signing_time, target)?;
 
-        match last_binary_pdf {
-            Some(last_binary_pdf) => Ok(last_binary_pdf),
-            None => {
-                // No signing done, so just return initial document.
-                Ok(self.raw_document.get_prev_documents_bytes().to_vec())
-            }
+        // Continue with the signed document, without reading it again.
+        self.append_incremental_update(&update, Some(&byte_range))?;
+        let signed_form_field = form_field.reload(self.raw_document.get_prev_documents())?;
+        if let Some(acro_forms) = self.acro_form.as_mut() {
+            acro_forms[form_field_index] = signed_form_field;
         }
+        Ok(())
     }
 
     // pub fn add_signature_to_form<R: Read>(
@@ -248,15 +312,13 @@ impl InsertImageToPage for PDFSigningDocument {
         xobject_name: N,
         xobject_id: ObjectId,
     ) -> Result<(), Error> {
-        Ok(self
-            .raw_document
-            .add_xobject(page_id, xobject_name, xobject_id)?)
+        self.raw_document
+            .add_xobject(page_id, xobject_name, xobject_id)
     }
 
     fn opt_clone_object_to_new_document(&mut self, object_id: ObjectId) -> Result<(), Error> {
-        Ok(self
-            .raw_document
-            .opt_clone_object_to_new_document(object_id)?)
+        self.raw_document
+            .opt_clone_object_to_new_document(object_id)
     }
 
     fn add_to_page_content(
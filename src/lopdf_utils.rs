@@ -1,5 +1,10 @@
 use crate::Error;
-use lopdf::Object;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use lopdf::{Dictionary, Object};
+use std::collections::HashSet;
+
+/// Maximum depth of the field hierarchy that is followed using `Kids` or `Parent`.
+pub(crate) const MAX_FIELD_DEPTH: usize = 32;
 
 pub(crate) fn as_option_name(obj: Option<&Object>) -> Result<Option<String>, Error> {
     Ok(obj
@@ -81,3 +86,77 @@ pub(crate) fn as_byte_range(obj: Option<&Object>) -> Result<Vec<(u64, u64)>, Err
     }
     Ok(result)
 }
+
+/// Decode a text string, the encoding depends on the byte order mark.
+pub(crate) fn as_option_decoded_text_string(obj: Option<&Object>) -> Result<Option<String>, Error> {
+    Ok(obj.map(lopdf::decode_text_string).transpose()?)
+}
+
+pub(crate) fn as_option_bool(obj: Option<&Object>) -> Result<Option<bool>, Error> {
+    Ok(obj.map(|obj| obj.as_bool()).transpose()?)
+}
+
+/// Parse a date (`D:YYYYMMDDHHmmSSOHH'mm'`), `None` when it is not a valid date.
+pub(crate) fn as_option_date(obj: Option<&Object>) -> Option<DateTime<Utc>> {
+    let text = obj?.as_str().ok()?;
+    parse_date(std::str::from_utf8(text).ok()?)
+}
+
+/// Parse a PDF date, all parts after the year are optional.
+/// Without a time zone the date is assumed to be UTC.
+pub(crate) fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim().strip_prefix("D:").unwrap_or(text.trim());
+    let (digits, time_zone) = text.split_at(
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len()),
+    );
+    if !(4..=14).contains(&digits.len()) || digits.len() % 2 != 0 {
+        return None;
+    }
+    let part = |start: usize, default: u32| match digits.get(start..start + 2) {
+        Some(part) => part.parse().ok(),
+        None => Some(default),
+    };
+    let date = NaiveDate::from_ymd_opt(digits[..4].parse().ok()?, part(4, 1)?, part(6, 1)?)?
+        .and_hms_opt(part(8, 0)?, part(10, 0)?, part(12, 0)?)?;
+
+    let offset_seconds = match time_zone.chars().next() {
+        None | Some('Z') => 0,
+        Some(sign @ ('+' | '-')) => {
+            let offset: String = time_zone[1..]
+                .chars()
+                .filter(char::is_ascii_digit)
+                .collect();
+            let hours: i64 = offset.get(..2)?.parse().ok()?;
+            let minutes: i64 = offset.get(2..4).unwrap_or("0").parse().ok()?;
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '-' {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+        _ => return None,
+    };
+    Some(Utc.from_utc_datetime(&(date - Duration::seconds(offset_seconds))))
+}
+
+/// Compare two versions of an object. Streams that are written again have a different
+/// position in the file.
+pub(crate) fn same_object(old: &Object, new: &Object) -> bool {
+    match (old, new) {
+        (Object::Stream(old), Object::Stream(new)) => {
+            old.dict == new.dict && old.content == new.content
+        }
+        (old, new) => old == new,
+    }
+}
+
+/// The keys with a different value, including added and removed keys.
+pub(crate) fn changed_keys<'d>(old: &'d Dictionary, new: &'d Dictionary) -> HashSet<&'d [u8]> {
+    old.iter()
+        .chain(new.iter())
+        .map(|(key, _)| key.as_slice())
+        .filter(|key| old.get(key).ok() != new.get(key).ok())
+        .collect()
+}
@@ -0,0 +1,767 @@
+//! Detect changes made to the document after a signature.
+//!
+//! The revision covered by the `ByteRange` of a signature is compared with the whole document.
+//! Every object that was changed, added or removed is classified. Changes that are allowed
+//! after signing (new signatures, `DSS`, form fill and annotations) are checked against the
+//! `DocMDP` permission of the certification signature and the `FieldMDP` lock of the signature.
+//!
+//! Known shadow attacks are reported as disallowed:
+//! - Overlays: a form field or widget is added, or the appearance of a field is changed
+//!   without changing its value.
+//! - Replaced cross-reference entries: an object is pointed to another object that was
+//!   hidden in the signed revision.
+
+use crate::lopdf_utils;
+use crate::pdf_object::PdfObjectDeref;
+use crate::{DocMdpPermission, Error, FieldLock};
+use lopdf::xref::XrefEntry;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+
+/// The kind of change made after a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModificationKind {
+    /// A signature field was added or signed.
+    Signature,
+    /// A document time-stamp was added.
+    DocumentTimestamp,
+    /// The Document Security Store (`DSS`) was added or changed.
+    DocumentSecurityStore,
+    /// The value of a form field was changed.
+    FormFill,
+    /// An annotation was added, changed or removed.
+    Annotation,
+    /// A page content stream or an XObject drawn on a page was changed.
+    ContentStream,
+    /// A page, its resources or the page tree was changed.
+    Page,
+    /// A font was changed.
+    Font,
+    /// Shadow attack: a form field or widget was added, or a field was changed without
+    /// changing its value (for example its appearance).
+    Overlay,
+    /// Shadow attack: the cross-reference entry of an object points to a different object
+    /// in the signed revision.
+    ReplacedXrefEntry,
+    /// Any other change, for example to the document catalog.
+    Other,
+}
+
+/// A change made after a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Modification {
+    pub object_id: ObjectId,
+    pub kind: ModificationKind,
+    /// Fully qualified name of the form field, for form fields and signatures.
+    pub field_name: Option<String>,
+    /// The change is allowed by the `DocMDP` and `FieldMDP` rules.
+    pub allowed: bool,
+}
+
+/// The changes made to the document after a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModificationReport {
+    /// Length in bytes of the signed revision, the end of the `ByteRange`.
+    pub signed_revision_length: usize,
+    /// The signature covers the whole document.
+    pub covers_whole_document: bool,
+    /// Permission of the certification signature, `None` when the document is not certified.
+    pub doc_mdp_permission: Option<DocMdpPermission>,
+    /// The fields locked by the signature (`FieldMDP`).
+    pub field_lock: Option<FieldLock>,
+    pub modifications: Vec<Modification>,
+}
+
+impl ModificationReport {
+    /// All changes after the signature are allowed.
+    pub fn is_allowed(&self) -> bool {
+        self.modifications
+            .iter()
+            .all(|modification| modification.allowed)
+    }
+
+    /// Compare the `signed_revision` of the signature in field `field_id` with `document`,
+    /// the current revision.
+    pub(crate) fn new(
+        signed_revision: &Document,
+        signed_revision_length: usize,
+        document: &Document,
+        document_length: usize,
+        field_id: Option<ObjectId>,
+    ) -> Result<Self, Error> {
+        let doc_mdp_permission = RevisionDiff::doc_mdp_permission(signed_revision)?;
+        let field_lock = match field_id {
+            Some(field_id) => RevisionDiff::field_lock(signed_revision, field_id)?,
+            None => None,
+        };
+
+        let diff = RevisionDiff::new(signed_revision, signed_revision_length, document);
+        let mut modifications: Vec<Modification> = diff
+            .modifications()
+            .into_iter()
+            .map(|(object_id, kind, field_name)| {
+                let allowed = Self::is_allowed_kind(kind, doc_mdp_permission)
+                    && !matches!(
+                        (&field_lock, &field_name),
+                        (Some(field_lock), Some(field_name)) if field_lock.is_locked(field_name)
+                    );
+                Modification {
+                    object_id,
+                    kind,
+                    field_name,
+                    allowed,
+                }
+            })
+            .collect();
+        modifications.sort_by_key(|modification| modification.object_id);
+
+        Ok(ModificationReport {
+            signed_revision_length,
+            covers_whole_document: signed_revision_length == document_length,
+            doc_mdp_permission,
+            field_lock,
+            modifications,
+        })
+    }
+
+    /// The changes allowed by the `DocMDP` permission. Validation data and document
+    /// time-stamps are always allowed (PAdES).
+    fn is_allowed_kind(kind: ModificationKind, permission: Option<DocMdpPermission>) -> bool {
+        match kind {
+            ModificationKind::DocumentSecurityStore | ModificationKind::DocumentTimestamp => true,
+            ModificationKind::Signature | ModificationKind::FormFill => {
+                permission != Some(DocMdpPermission::NoChanges)
+            }
+            ModificationKind::Annotation => matches!(
+                permission,
+                None | Some(DocMdpPermission::FormFillingAndAnnotations)
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// The role of an object in the signed revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Catalog,
+    AcroForm,
+    /// The `Fields` array of the `AcroForm`.
+    Fields,
+    /// Page, page tree node or page resources.
+    Page,
+    ContentStream,
+    Font,
+    /// The `Annots` array of a page.
+    Annots,
+    /// An annotation, or the appearance stream of the annotation.
+    Annotation(ObjectId),
+}
+
+struct RevisionDiff<'a> {
+    signed: &'a Document,
+    signed_length: usize,
+    current: &'a Document,
+    roles: HashMap<ObjectId, Role>,
+}
+
+impl<'a> RevisionDiff<'a> {
+    fn new(signed: &'a Document, signed_length: usize, current: &'a Document) -> Self {
+        let mut diff = RevisionDiff {
+            signed,
+            signed_length,
+            current,
+            roles: HashMap::new(),
+        };
+        diff.find_roles();
+        diff
+    }
+
+    /// All changes as `(object, kind, field name)`.
+    fn modifications(&self) -> Vec<(ObjectId, ModificationKind, Option<String>)> {
+        let mut modifications = vec![];
+        for (object_id, old) in &self.signed.objects {
+            let new = self.current.objects.get(object_id);
+            if new.is_some_and(|new| lopdf_utils::same_object(old, new)) {
+                continue;
+            }
+            if let Some((kind, field_name)) = self.classify_changed(*object_id, old, new) {
+                modifications.push((*object_id, kind, field_name));
+            }
+        }
+        for (object_id, new) in &self.current.objects {
+            if self.signed.objects.contains_key(object_id) {
+                continue;
+            }
+            if let Some((kind, field_name)) = self.classify_new(*object_id, new) {
+                modifications.push((*object_id, kind, field_name));
+            }
+        }
+        for object_id in self.replaced_xref_entries() {
+            modifications.push((object_id, ModificationKind::ReplacedXrefEntry, None));
+        }
+        modifications
+    }
+
+    /// Objects in the signed part of the file that the cross-reference table of the current
+    /// revision points to, while the signed revision uses another object at that place.
+    fn replaced_xref_entries(&self) -> Vec<ObjectId> {
+        let signed_entries = &self.signed.reference_table.entries;
+        self.current
+            .reference_table
+            .entries
+            .iter()
+            .filter(|(id, entry)| {
+                let offset = match entry {
+                    XrefEntry::Normal { offset, .. } => *offset,
+                    XrefEntry::Compressed { container, .. } => {
+                        match self.current.reference_table.entries.get(container) {
+                            Some(XrefEntry::Normal { offset, .. }) => *offset,
+                            _ => return false,
+                        }
+                    }
+                    _ => return false,
+                };
+                (offset as usize) < self.signed_length
+                    && !Self::same_xref_entry(signed_entries.get(id), entry)
+            })
+            .map(|(id, entry)| {
+                let generation = match entry {
+                    XrefEntry::Normal { generation, .. } => *generation,
+                    _ => 0,
+                };
+                (*id, generation)
+            })
+            .collect()
+    }
+
+    fn same_xref_entry(old: Option<&XrefEntry>, new: &XrefEntry) -> bool {
+        match (old, new) {
+            (
+                Some(XrefEntry::Normal { offset, generation }),
+                XrefEntry::Normal {
+                    offset: new_offset,
+                    generation: new_generation,
+                },
+            ) => offset == new_offset && generation == new_generation,
+            (
+                Some(XrefEntry::Compressed { container, index }),
+                XrefEntry::Compressed {
+                    container: new_container,
+                    index: new_index,
+                },
+            ) => container == new_container && index == new_index,
+            _ => false,
+        }
+    }
+
+    /// Classify an object of the signed revision that was changed, or removed when `new`
+    /// is `None`. Returns `None` for changes that are part of another change.
+    fn classify_changed(
+        &self,
+        object_id: ObjectId,
+        old: &Object,
+        new: Option<&Object>,
+    ) -> Option<(ModificationKind, Option<String>)> {
+        let old_dict = Self::dictionary(old);
+        let new_dict = new.and_then(Self::dictionary);
+        let changed_keys = || match (old_dict, new_dict) {
+            (Some(old_dict), Some(new_dict)) => lopdf_utils::changed_keys(old_dict, new_dict),
+            _ => HashSet::new(),
+        };
+
+        match self.roles.get(&object_id) {
+            Some(Role::Catalog) => {
+                let changed_keys = changed_keys();
+                let allowed_keys = HashSet::from([
+                    b"AcroForm".as_slice(),
+                    b"DSS".as_slice(),
+                    b"Extensions".as_slice(),
+                ]);
+                let allowed = match (old_dict, new_dict) {
+                    (Some(old_dict), Some(new_dict)) => {
+                        changed_keys.is_subset(&allowed_keys)
+                            && (!changed_keys.contains(b"AcroForm".as_slice())
+                                || self.acro_form_change_allowed(old_dict, new_dict))
+                    }
+                    _ => false,
+                };
+                if !allowed {
+                    Some((ModificationKind::Other, None))
+                } else if changed_keys.contains(b"DSS".as_slice()) {
+                    Some((ModificationKind::DocumentSecurityStore, None))
+                } else {
+                    None
+                }
+            }
+            Some(Role::AcroForm) => match (old_dict, new_dict) {
+                (Some(old_dict), Some(new_dict))
+                    if Self::acro_form_keys_allowed(old_dict, new_dict) =>
+                {
+                    None
+                }
+                _ => Some((ModificationKind::Other, None)),
+            },
+            Some(Role::Fields) => {
+                if Self::is_extended(old, new) {
+                    None
+                } else {
+                    Some((ModificationKind::Other, None))
+                }
+            }
+            Some(Role::Annots) => {
+                if Self::is_extended(old, new) {
+                    None
+                } else {
+                    Some((ModificationKind::Annotation, None))
+                }
+            }
+            Some(Role::Page) => {
+                let changed_keys = changed_keys();
+                if new_dict.is_some() && changed_keys.iter().all(|key| *key == b"Annots") {
+                    let old_annots = old_dict?.get(b"Annots").ok();
+                    let new_annots = new_dict?.get(b"Annots").ok();
+                    match (old_annots, new_annots) {
+                        (Some(old_annots), Some(new_annots))
+                            if Self::is_extended(old_annots, Some(new_annots)) =>
+                        {
+                            None
+                        }
+                        (None, Some(_)) => None,
+                        _ => Some((ModificationKind::Annotation, None)),
+                    }
+                } else if changed_keys.contains(b"Contents".as_slice()) {
+                    Some((ModificationKind::ContentStream, None))
+                } else {
+                    Some((ModificationKind::Page, None))
+                }
+            }
+            Some(Role::ContentStream) => Some((ModificationKind::ContentStream, None)),
+            Some(Role::Font) => Some((ModificationKind::Font, None)),
+            Some(Role::Annotation(annotation_id)) => Some(self.classify_annotation(*annotation_id)),
+            None => {
+                if let Some(old_dict) = old_dict {
+                    if Self::is_type(old_dict, b"DSS") || Self::is_type(old_dict, b"VRI") {
+                        return Some((ModificationKind::DocumentSecurityStore, None));
+                    }
+                    if Self::is_field(old_dict) {
+                        return Some(self.classify_field(object_id));
+                    }
+                }
+                Some((ModificationKind::Other, None))
+            }
+        }
+    }
+
+    /// Classify an object that is not in the signed revision. Only objects that change the
+    /// document by themselves are reported, other new objects are only used by changed objects.
+    fn classify_new(
+        &self,
+        object_id: ObjectId,
+        new: &Object,
+    ) -> Option<(ModificationKind, Option<String>)> {
+        let dict = Self::dictionary(new)?;
+        if Self::is_type(dict, b"DSS") {
+            Some((ModificationKind::DocumentSecurityStore, None))
+        } else if Self::is_type(dict, b"Sig") || Self::is_type(dict, b"DocTimeStamp") {
+            // Reported with the signature field.
+            None
+        } else if Self::is_widget(dict) || (Self::is_field(dict) && !dict.has(b"Kids")) {
+            Some(self.classify_field(object_id))
+        } else if dict.has(b"Subtype") && dict.has(b"Rect") && !matches!(new, Object::Stream(_)) {
+            Some((ModificationKind::Annotation, None))
+        } else {
+            None
+        }
+    }
+
+    /// Classify a change to an annotation or its appearance.
+    fn classify_annotation(&self, annotation_id: ObjectId) -> (ModificationKind, Option<String>) {
+        let annotation = self
+            .current
+            .get_dictionary(annotation_id)
+            .or_else(|_| self.signed.get_dictionary(annotation_id));
+        match annotation {
+            Ok(annotation) if Self::is_widget(annotation) => self.classify_field(annotation_id),
+            _ => (ModificationKind::Annotation, None),
+        }
+    }
+
+    /// Classify a change to a form field or widget: signing a signature field is allowed,
+    /// a change to a field without changing its value is an overlay.
+    fn classify_field(&self, field_id: ObjectId) -> (ModificationKind, Option<String>) {
+        let field_name = Self::field_name(self.current, field_id)
+            .or_else(|| Self::field_name(self.signed, field_id));
+        let field_type = Self::field_type(self.current, field_id)
+            .or_else(|| Self::field_type(self.signed, field_id));
+        let old_value = Self::field_value(self.signed, field_id);
+        let new_value = Self::field_value(self.current, field_id);
+        let is_new = !self.signed.objects.contains_key(&field_id);
+
+        let kind = match field_type.as_deref() {
+            Some(b"Sig") => match (old_value, new_value) {
+                (None, Some(Object::Dictionary(signature)))
+                    if Self::is_type(signature, b"DocTimeStamp") =>
+                {
+                    ModificationKind::DocumentTimestamp
+                }
+                (None, Some(_)) => ModificationKind::Signature,
+                // A new signature field that is not signed yet.
+                (None, None) if is_new => ModificationKind::Signature,
+                (None, None) => ModificationKind::Overlay,
+                // A signature that was removed or replaced.
+                _ => ModificationKind::Other,
+            },
+            _ if is_new => ModificationKind::Overlay,
+            _ if old_value != new_value => ModificationKind::FormFill,
+            _ => ModificationKind::Overlay,
+        };
+        (kind, field_name)
+    }
+
+    /// `Perms/DocMDP` in the catalog, `P` defaults to 2.
+    fn doc_mdp_permission(document: &Document) -> Result<Option<DocMdpPermission>, Error> {
+        let root = document.trailer.get(b"Root")?.deref(document)?.as_dict()?;
+        let signature = root
+            .get(b"Perms")
+            .ok()
+            .and_then(|perms| perms.deref(document).ok())
+            .and_then(|perms| perms.as_dict().ok())
+            .and_then(|perms| perms.get(b"DocMDP").ok());
+        let signature = match signature {
+            Some(signature) => signature.deref(document)?.as_dict()?,
+            None => return Ok(None),
+        };
+        match Self::transform_params(document, signature, b"DocMDP")? {
+            Some(transform_params) => {
+                let p_value = lopdf_utils::as_option_integer(transform_params.get(b"P").ok())?;
+                Ok(Some(DocMdpPermission::try_from(p_value.unwrap_or(2))?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The `FieldMDP` lock in the signature of the field.
+    fn field_lock(document: &Document, field_id: ObjectId) -> Result<Option<FieldLock>, Error> {
+        let signature = match Self::field_value(document, field_id) {
+            Some(Object::Dictionary(signature)) => signature,
+            _ => return Ok(None),
+        };
+        Self::transform_params(document, signature, b"FieldMDP")?
+            .map(FieldLock::from_dictionary)
+            .transpose()
+    }
+
+    /// The `TransformParams` of the `SigRef` with `transform_method` in the signature.
+    fn transform_params<'d>(
+        document: &'d Document,
+        signature: &'d Dictionary,
+        transform_method: &[u8],
+    ) -> Result<Option<&'d Dictionary>, Error> {
+        let references = match signature.get(b"Reference") {
+            Ok(references) => references.deref(document)?.as_array()?,
+            Err(_) => return Ok(None),
+        };
+        for reference in references {
+            let reference = reference.deref(document)?.as_dict()?;
+            if reference.get(b"TransformMethod")?.as_name()? == transform_method {
+                return match reference.get(b"TransformParams") {
+                    Ok(transform_params) => Ok(Some(transform_params.deref(document)?.as_dict()?)),
+                    Err(_) => Ok(None),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// Set the role of the objects in the signed revision.
+    fn find_roles(&mut self) {
+        let document = self.signed;
+        for (object_id, object) in &document.objects {
+            if Self::dictionary(object).is_some_and(|dict| Self::is_type(dict, b"Pages")) {
+                self.roles.insert(*object_id, Role::Page);
+            }
+        }
+        for page_id in document.get_pages().into_values() {
+            self.roles.insert(page_id, Role::Page);
+            let Ok(page) = document.get_dictionary(page_id) else {
+                continue;
+            };
+            if let Ok(contents) = page.get(b"Contents") {
+                self.set_role(contents, Role::ContentStream);
+            }
+            if let Ok(resources) = page.get(b"Resources") {
+                self.set_resources_role(resources, Role::Page, &mut HashSet::new());
+            }
+            if let Ok(annotations) = page.get(b"Annots") {
+                self.set_role(annotations, Role::Annots);
+                for annotation in Self::array_items(document, annotations) {
+                    let Some(annotation_id) = annotation.get_object_id() else {
+                        continue;
+                    };
+                    self.roles
+                        .entry(annotation_id)
+                        .or_insert(Role::Annotation(annotation_id));
+                    if let Ok(appearance) = document
+                        .get_dictionary(annotation_id)
+                        .and_then(|annotation| annotation.get(b"AP"))
+                    {
+                        self.set_appearance_role(appearance, Role::Annotation(annotation_id));
+                    }
+                }
+            }
+        }
+
+        let Ok(root) = document.trailer.get(b"Root") else {
+            return;
+        };
+        self.set_role(root, Role::Catalog);
+        let acro_form = root
+            .deref(document)
+            .and_then(|root| Ok(root.as_dict()?.get(b"AcroForm")?));
+        if let Ok(acro_form) = acro_form {
+            self.set_role(acro_form, Role::AcroForm);
+            if let Ok(fields) = acro_form
+                .deref(document)
+                .and_then(|acro_form| Ok(acro_form.as_dict()?.get(b"Fields")?))
+            {
+                self.set_role(fields, Role::Fields);
+            }
+        }
+    }
+
+    /// Set the role of `object` when it is a reference, existing roles are kept.
+    /// A reference to an array also sets the role of the references in the array.
+    fn set_role(&mut self, object: &Object, role: Role) {
+        if let Some(object_id) = object.get_object_id() {
+            self.roles.entry(object_id).or_insert(role);
+        }
+        if role == Role::ContentStream {
+            for item in Self::array_items(self.signed, object) {
+                if let Some(object_id) = item.get_object_id() {
+                    self.roles.entry(object_id).or_insert(role);
+                }
+            }
+        }
+    }
+
+    /// Fonts and XObjects used on a page, also in Form XObjects.
+    fn set_resources_role(&mut self, resources: &Object, role: Role, seen: &mut HashSet<ObjectId>) {
+        let document = self.signed;
+        self.set_role(resources, role);
+        let Ok(resources) = resources.deref(document).and_then(|r| Ok(r.as_dict()?)) else {
+            return;
+        };
+        if let Ok(fonts) = resources.get(b"Font") {
+            self.set_role(fonts, Role::Font);
+            if let Ok(fonts) = fonts.deref(document).and_then(|f| Ok(f.as_dict()?)) {
+                for (_, font) in fonts.iter() {
+                    self.set_font_role(font, seen);
+                }
+            }
+        }
+        if let Ok(xobjects) = resources.get(b"XObject") {
+            self.set_role(xobjects, Role::ContentStream);
+            if let Ok(xobjects) = xobjects.deref(document).and_then(|x| Ok(x.as_dict()?)) {
+                for (_, xobject) in xobjects.iter() {
+                    let Some(xobject_id) = xobject.get_object_id() else {
+                        continue;
+                    };
+                    if !seen.insert(xobject_id) {
+                        continue;
+                    }
+                    self.set_role(xobject, Role::ContentStream);
+                    if let Ok(xobject_resources) = document
+                        .get_object(xobject_id)
+                        .and_then(|xobject| xobject.as_stream())
+                        .and_then(|xobject| xobject.dict.get(b"Resources"))
+                    {
+                        self.set_resources_role(xobject_resources, Role::ContentStream, seen);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The font, its descriptor, descendant fonts and embedded font files.
+    fn set_font_role(&mut self, font: &Object, seen: &mut HashSet<ObjectId>) {
+        let document = self.signed;
+        if let Some(font_id) = font.get_object_id() {
+            if !seen.insert(font_id) {
+                return;
+            }
+        }
+        self.set_role(font, Role::Font);
+        let Ok(font) = font.deref(document).and_then(|f| Ok(f.as_dict()?)) else {
+            return;
+        };
+        for key in [b"FontDescriptor".as_slice(), b"ToUnicode", b"Encoding"] {
+            if let Ok(value) = font.get(key) {
+                self.set_role(value, Role::Font);
+            }
+        }
+        if let Ok(descriptor) = font
+            .get(b"FontDescriptor")
+            .and_then(|d| document.dereference(d))
+            .and_then(|(_, d)| d.as_dict())
+        {
+            for key in [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"] {
+                if let Ok(font_file) = descriptor.get(key) {
+                    self.set_role(font_file, Role::Font);
+                }
+            }
+        }
+        if let Ok(descendant_fonts) = font.get(b"DescendantFonts") {
+            self.set_role(descendant_fonts, Role::Font);
+            for descendant_font in Self::array_items(document, descendant_fonts) {
+                self.set_font_role(descendant_font, seen);
+            }
+        }
+    }
+
+    /// Appearance streams in `AP`, per appearance type (`N`, `R`, `D`) a stream or a
+    /// dictionary of streams per state.
+    fn set_appearance_role(&mut self, appearance: &Object, role: Role) {
+        let document = self.signed;
+        self.set_role(appearance, role);
+        let Ok(appearance) = appearance.deref(document).and_then(|a| Ok(a.as_dict()?)) else {
+            return;
+        };
+        for (_, appearance_type) in appearance.iter() {
+            self.set_role(appearance_type, role);
+            if let Ok(states) = appearance_type
+                .deref(document)
+                .and_then(|states| Ok(states.as_dict()?))
+            {
+                for (_, state) in states.iter() {
+                    self.set_role(state, role);
+                }
+            }
+        }
+    }
+
+    /// Only the entries `Fields`, `SigFlags`, `DR`, `DA` and `NeedAppearances` may change.
+    fn acro_form_keys_allowed(old: &Dictionary, new: &Dictionary) -> bool {
+        let allowed_keys = HashSet::from([
+            b"Fields".as_slice(),
+            b"SigFlags".as_slice(),
+            b"DR".as_slice(),
+            b"DA".as_slice(),
+            b"NeedAppearances".as_slice(),
+        ]);
+        lopdf_utils::changed_keys(old, new).is_subset(&allowed_keys)
+            && match (old.get(b"Fields"), new.get(b"Fields")) {
+                (Ok(old_fields), new_fields) => Self::is_extended(old_fields, new_fields.ok()),
+                (Err(_), _) => true,
+            }
+    }
+
+    /// Check an `AcroForm` of the catalog that changed.
+    fn acro_form_change_allowed(&self, old_root: &Dictionary, new_root: &Dictionary) -> bool {
+        let old_acro_form = old_root
+            .get(b"AcroForm")
+            .ok()
+            .and_then(|acro_form| acro_form.deref(self.signed).ok())
+            .and_then(|acro_form| acro_form.as_dict().ok());
+        let new_acro_form = new_root
+            .get(b"AcroForm")
+            .ok()
+            .and_then(|acro_form| acro_form.deref(self.current).ok())
+            .and_then(|acro_form| acro_form.as_dict().ok());
+        match (old_acro_form, new_acro_form) {
+            (Some(old_acro_form), Some(new_acro_form)) => {
+                Self::acro_form_keys_allowed(old_acro_form, new_acro_form)
+            }
+            (None, Some(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// `new` is an array that contains all items of the array `old`.
+    fn is_extended(old: &Object, new: Option<&Object>) -> bool {
+        match (old.as_array(), new.map(Object::as_array)) {
+            (Ok(old), Some(Ok(new))) => old.iter().all(|item| new.contains(item)),
+            _ => false,
+        }
+    }
+
+    fn array_items<'d>(document: &'d Document, object: &'d Object) -> &'d [Object] {
+        object
+            .deref(document)
+            .and_then(|array| Ok(array.as_array()?))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The dictionary of a dictionary or stream.
+    fn dictionary(object: &Object) -> Option<&Dictionary> {
+        match object {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        }
+    }
+
+    fn is_type(dict: &Dictionary, r#type: &[u8]) -> bool {
+        dict.get(b"Type").and_then(Object::as_name).ok() == Some(r#type)
+    }
+
+    fn is_widget(dict: &Dictionary) -> bool {
+        dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget".as_slice())
+    }
+
+    fn is_field(dict: &Dictionary) -> bool {
+        dict.has(b"FT") || (dict.has(b"T") && !dict.has(b"Subtype"))
+    }
+
+    /// The field and its ancestors using `Parent`, starting with the field itself.
+    fn field_ancestors(document: &Document, field_id: ObjectId) -> Vec<&Dictionary> {
+        let mut ancestors = vec![];
+        let mut next = document.get_dictionary(field_id).ok();
+        while let Some(field) = next {
+            if ancestors.len() == lopdf_utils::MAX_FIELD_DEPTH {
+                break;
+            }
+            ancestors.push(field);
+            next = field
+                .get(b"Parent")
+                .and_then(|parent| document.dereference(parent))
+                .and_then(|(_, parent)| parent.as_dict())
+                .ok();
+        }
+        ancestors
+    }
+
+    /// Fully qualified field name, the partial names (`T`) of the ancestors joined by `.`.
+    fn field_name(document: &Document, field_id: ObjectId) -> Option<String> {
+        let mut names: Vec<String> = Self::field_ancestors(document, field_id)
+            .into_iter()
+            .filter_map(|field| {
+                lopdf_utils::as_option_text_string(field.get(b"T").ok())
+                    .ok()
+                    .flatten()
+            })
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        names.reverse();
+        Some(names.join("."))
+    }
+
+    /// The field type (`FT`), can be inherited.
+    fn field_type(document: &Document, field_id: ObjectId) -> Option<Vec<u8>> {
+        Self::field_ancestors(document, field_id)
+            .into_iter()
+            .find_map(|field| field.get(b"FT").and_then(Object::as_name).ok())
+            .map(<[u8]>::to_vec)
+    }
+
+    /// The field value (`V`), can be inherited.
+    fn field_value(document: &Document, field_id: ObjectId) -> Option<&Object> {
+        Self::field_ancestors(document, field_id)
+            .into_iter()
+            .find_map(|field| field.get(b"V").ok())
+            .and_then(|value| value.deref(document).ok())
+    }
+}
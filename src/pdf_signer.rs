@@ -0,0 +1,49 @@
+use crate::{Error, SignatureAlgorithm};
+use x509_certificate::CapturedX509Certificate;
+
+/// Creates the cryptographic signature for a PDF signature.
+///
+/// Implement this trait to sign with keys that are not in memory (HSM, KMS, ...).
+/// The CMS structure itself is created by this crate.
+///
+/// Implement `sign()` for signers that hash the data themselves, or only `sign_digest()`
+/// for signers that accept a digest (most KMS and HSM APIs).
+pub trait PdfSigner: Send + Sync {
+    /// X.509 certificate that belongs to the signing key.
+    fn signing_certificate(&self) -> &CapturedX509Certificate;
+
+    /// Extra certificates (intermediates, root) added to the signature.
+    fn certificate_chain(&self) -> &[CapturedX509Certificate] {
+        &[]
+    }
+
+    /// The signature algorithms that can be used with this key, the first one is preferred.
+    fn supported_algorithms(&self) -> Vec<SignatureAlgorithm>;
+
+    /// Sign the `data` using `algorithm`, which is one of the `supported_algorithms()`.
+    ///
+    /// By default the `data` is hashed with `algorithm.digest_algorithm()` and signed with
+    /// `sign_digest()`. Ed25519 signs the data itself, so Ed25519 signers have to implement this.
+    ///
+    /// Returns the raw signature value.
+    fn sign(&self, data: &[u8], algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error> {
+        if algorithm == SignatureAlgorithm::Ed25519 {
+            return Err(Error::from(
+                "Ed25519 signs the data, the signer has to implement `sign()`.",
+            ));
+        }
+        self.sign_digest(&algorithm.digest_algorithm().digest_data(data), algorithm)
+    }
+
+    /// Sign the `digest` of the data, created with `algorithm.digest_algorithm()`.
+    ///
+    /// Not available for Ed25519 (PureEdDSA has no pre-hashed form).
+    /// Returns the raw signature value.
+    fn sign_digest(&self, digest: &[u8], algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error> {
+        let _ = digest;
+        Err(Error::Other(format!(
+            "The signer can not sign a digest with `{}`.",
+            algorithm
+        )))
+    }
+}
@@ -0,0 +1,292 @@
+//! `PdfSigner` for keys on a PKCS#11 token (HSM, smart card, SoftHSM, ...).
+//!
+//! The data is hashed in this crate, only the digest is sent to the token.
+//! Supported mechanisms: `CKM_RSA_PKCS`, `CKM_RSA_PKCS_PSS` and `CKM_ECDSA`.
+
+use crate::{Error, PdfSigner, SignatureAlgorithm};
+use bcder::{
+    encode::{self, PrimitiveContent, Values},
+    Mode, OctetString, Oid, Unsigned,
+};
+use cryptoki::{
+    context::{CInitializeArgs, CInitializeFlags, Pkcs11},
+    error::RvError,
+    mechanism::{
+        rsa::{PkcsMgfType, PkcsPssParams},
+        Mechanism, MechanismType,
+    },
+    object::{Attribute, AttributeType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::{AuthPin, Ulong},
+};
+use std::path::Path;
+use std::sync::Mutex;
+use x509_certificate::{CapturedX509Certificate, DigestAlgorithm, KeyAlgorithm};
+
+/// How the private key is found on the token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pkcs11KeyId {
+    /// `CKA_LABEL` of the key.
+    Label(String),
+    /// `CKA_ID` of the key.
+    Id(Vec<u8>),
+}
+
+/// Signs with a private key on a PKCS#11 token.
+///
+/// The certificate is the one with the same `CKA_ID` (or label) as the key.
+/// The issuers of the certificate that are on the token are added as the chain.
+pub struct Pkcs11Signer {
+    session: Mutex<Session>,
+    private_key: ObjectHandle,
+    signing_certificate: CapturedX509Certificate,
+    certificate_chain: Vec<CapturedX509Certificate>,
+    supported_algorithms: Vec<SignatureAlgorithm>,
+}
+
+impl Pkcs11Signer {
+    /// Load the PKCS#11 `module_path` and log in to the token with label `token_label`.
+    pub fn new(
+        module_path: impl AsRef<Path>,
+        token_label: &str,
+        pin: &str,
+        key: Pkcs11KeyId,
+    ) -> Result<Self, Error> {
+        let pkcs11 = Pkcs11::new(module_path)?;
+        match pkcs11.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK)) {
+            Err(cryptoki::error::Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {}
+            result => result?,
+        }
+
+        let mut slot = None;
+        for token_slot in pkcs11.get_slots_with_token()? {
+            if pkcs11.get_token_info(token_slot)?.label().trim_end() == token_label {
+                slot = Some(token_slot);
+                break;
+            }
+        }
+        let slot = slot
+            .ok_or_else(|| Error::Other(format!("PKCS#11: Token `{}` not found.", token_label)))?;
+        let mechanisms = pkcs11.get_mechanism_list(slot)?;
+
+        let session = pkcs11.open_ro_session(slot)?;
+        session.login(UserType::User, Some(&AuthPin::from(pin)))?;
+
+        let key_attribute = match &key {
+            Pkcs11KeyId::Label(label) => Attribute::Label(label.as_bytes().to_vec()),
+            Pkcs11KeyId::Id(id) => Attribute::Id(id.clone()),
+        };
+        let private_key = *session
+            .find_objects(&[Attribute::Class(ObjectClass::PRIVATE_KEY), key_attribute])?
+            .first()
+            .ok_or_else(|| Error::Other(format!("PKCS#11: Private key {:?} not found.", key)))?;
+
+        // Find the certificate with the `CKA_ID` of the key, the label is used as fallback.
+        let mut certificate_attributes = vec![];
+        for attribute in session.get_attributes(private_key, &[AttributeType::Id])? {
+            if let Attribute::Id(id) = attribute {
+                if !id.is_empty() {
+                    certificate_attributes.push(Attribute::Id(id));
+                }
+            }
+        }
+        if let Pkcs11KeyId::Label(label) = &key {
+            certificate_attributes.push(Attribute::Label(label.as_bytes().to_vec()));
+        }
+        let mut signing_certificate = None;
+        for attribute in certificate_attributes {
+            let objects =
+                session.find_objects(&[Attribute::Class(ObjectClass::CERTIFICATE), attribute])?;
+            if let Some(object) = objects.first() {
+                signing_certificate = Some(Self::read_certificate(&session, *object)?);
+                break;
+            }
+        }
+        let signing_certificate = signing_certificate.ok_or_else(|| {
+            Error::Other(format!(
+                "PKCS#11: Certificate for private key {:?} not found.",
+                key
+            ))
+        })?;
+
+        let mut token_certificates = vec![];
+        for object in session.find_objects(&[Attribute::Class(ObjectClass::CERTIFICATE)])? {
+            // Ignore certificates that can not be parsed, they can not be in the chain.
+            if let Ok(certificate) = Self::read_certificate(&session, object) {
+                token_certificates.push(certificate);
+            }
+        }
+        let certificate_chain = Self::build_chain(&signing_certificate, &token_certificates);
+
+        let supported_algorithms =
+            Self::algorithms_for_mechanisms(signing_certificate.key_algorithm(), &mechanisms);
+
+        Ok(Pkcs11Signer {
+            session: Mutex::new(session),
+            private_key,
+            signing_certificate,
+            certificate_chain,
+            supported_algorithms,
+        })
+    }
+
+    fn read_certificate(
+        session: &Session,
+        object: ObjectHandle,
+    ) -> Result<CapturedX509Certificate, Error> {
+        for attribute in session.get_attributes(object, &[AttributeType::Value])? {
+            if let Attribute::Value(der) = attribute {
+                return Ok(CapturedX509Certificate::from_der(der)?);
+            }
+        }
+        Err(Error::from("PKCS#11: Certificate has no value."))
+    }
+
+    /// The issuers of `certificate` in `certificates`, ordered from issuer to root.
+    fn build_chain(
+        certificate: &CapturedX509Certificate,
+        certificates: &[CapturedX509Certificate],
+    ) -> Vec<CapturedX509Certificate> {
+        let mut chain: Vec<CapturedX509Certificate> = vec![];
+        let mut current = certificate;
+        while !current.subject_is_issuer() {
+            let issuer = certificates.iter().find(|issuer| {
+                issuer.subject_name() == current.issuer_name()
+                    && *issuer != certificate
+                    && !chain.contains(issuer)
+            });
+            match issuer {
+                Some(issuer) => {
+                    chain.push(issuer.clone());
+                    current = issuer;
+                }
+                None => break,
+            }
+        }
+        chain
+    }
+
+    /// The algorithms that can be created with the `mechanisms` of the token.
+    fn algorithms_for_mechanisms(
+        key_algorithm: Option<KeyAlgorithm>,
+        mechanisms: &[MechanismType],
+    ) -> Vec<SignatureAlgorithm> {
+        let digest_algorithms = [
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Sha384,
+            DigestAlgorithm::Sha512,
+        ];
+        let mut algorithms = vec![];
+        match key_algorithm {
+            Some(KeyAlgorithm::Rsa) => {
+                if mechanisms.contains(&MechanismType::RSA_PKCS) {
+                    algorithms.extend(digest_algorithms.map(SignatureAlgorithm::RsaPkcs1));
+                }
+                if mechanisms.contains(&MechanismType::RSA_PKCS_PSS) {
+                    algorithms.extend(digest_algorithms.map(SignatureAlgorithm::RsaPss));
+                }
+            }
+            Some(key_algorithm @ KeyAlgorithm::Ecdsa(_))
+                if mechanisms.contains(&MechanismType::ECDSA) =>
+            {
+                // The digest that matches the curve first.
+                algorithms.push(SignatureAlgorithm::default_for_key(key_algorithm));
+                for digest_algorithm in digest_algorithms {
+                    let algorithm = SignatureAlgorithm::Ecdsa(digest_algorithm);
+                    if !algorithms.contains(&algorithm)
+                        && algorithm.is_supported_by_key(key_algorithm)
+                    {
+                        algorithms.push(algorithm);
+                    }
+                }
+            }
+            _ => {}
+        }
+        algorithms
+    }
+
+    /// ```ASN.1
+    /// DigestInfo ::= SEQUENCE {
+    ///   digestAlgorithm AlgorithmIdentifier,
+    ///   digest OCTET STRING }
+    /// ```
+    fn digest_info(digest_algorithm: DigestAlgorithm, digest: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut der = Vec::new();
+        encode::sequence((
+            encode::sequence((Oid::from(digest_algorithm).encode(), ().encode())),
+            OctetString::encode_slice(digest),
+        ))
+        .write_encoded(Mode::Der, &mut der)?;
+        Ok(der)
+    }
+
+    /// Convert the `r || s` output of `CKM_ECDSA` to a DER `Ecdsa-Sig-Value`.
+    fn ecdsa_signature_der(signature: &[u8]) -> Result<Vec<u8>, Error> {
+        let (r, s) = signature.split_at(signature.len() / 2);
+        let invalid = |_| Error::from("PKCS#11: Invalid ECDSA signature.");
+        let r = Unsigned::from_slice(r).map_err(invalid)?;
+        let s = Unsigned::from_slice(s).map_err(invalid)?;
+        let mut der = Vec::new();
+        encode::sequence(((&r).encode(), (&s).encode())).write_encoded(Mode::Der, &mut der)?;
+        Ok(der)
+    }
+
+    fn mechanism_type(digest_algorithm: DigestAlgorithm) -> (MechanismType, PkcsMgfType) {
+        match digest_algorithm {
+            DigestAlgorithm::Sha1 => (MechanismType::SHA1, PkcsMgfType::MGF1_SHA1),
+            DigestAlgorithm::Sha256 => (MechanismType::SHA256, PkcsMgfType::MGF1_SHA256),
+            DigestAlgorithm::Sha384 => (MechanismType::SHA384, PkcsMgfType::MGF1_SHA384),
+            DigestAlgorithm::Sha512 => (MechanismType::SHA512, PkcsMgfType::MGF1_SHA512),
+        }
+    }
+}
+
+impl PdfSigner for Pkcs11Signer {
+    fn signing_certificate(&self) -> &CapturedX509Certificate {
+        &self.signing_certificate
+    }
+
+    fn certificate_chain(&self) -> &[CapturedX509Certificate] {
+        &self.certificate_chain
+    }
+
+    fn supported_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        self.supported_algorithms.clone()
+    }
+
+    fn sign_digest(&self, digest: &[u8], algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error> {
+        if !self.supported_algorithms.contains(&algorithm) {
+            return Err(Error::Other(format!(
+                "Signature algorithm `{}` is not supported by the key.",
+                algorithm
+            )));
+        }
+        let digest_algorithm = algorithm.digest_algorithm();
+
+        let session = self
+            .session
+            .lock()
+            .map_err(|_| Error::from("PKCS#11: Session lock is poisoned."))?;
+        match algorithm {
+            SignatureAlgorithm::RsaPkcs1(_) => Ok(session.sign(
+                &Mechanism::RsaPkcs,
+                self.private_key,
+                &Self::digest_info(digest_algorithm, digest)?,
+            )?),
+            SignatureAlgorithm::RsaPss(_) => {
+                let (hash_alg, mgf) = Self::mechanism_type(digest_algorithm);
+                let params = PkcsPssParams {
+                    hash_alg,
+                    mgf,
+                    s_len: Ulong::try_from(SignatureAlgorithm::pss_salt_length(digest_algorithm))?,
+                };
+                Ok(session.sign(&Mechanism::RsaPkcsPss(params), self.private_key, digest)?)
+            }
+            SignatureAlgorithm::Ecdsa(_) => {
+                let signature = session.sign(&Mechanism::Ecdsa, self.private_key, digest)?;
+                Self::ecdsa_signature_der(&signature)
+            }
+            SignatureAlgorithm::Ed25519 => Err(Error::from("PKCS#11: Ed25519 is not supported.")),
+        }
+    }
+}
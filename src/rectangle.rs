@@ -1,7 +1,26 @@
-#[derive(Debug, Clone, Default)]
+use crate::Error;
+
+/// A rectangle in default user space, two opposite corners (`[x1 y1 x2 y2]`).
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Rectangle {
     pub x1: f32,
     pub y1: f32,
     pub x2: f32,
     pub y2: f32,
 }
+
+impl Rectangle {
+    /// Read a rectangle array (`[x1 y1 x2 y2]`), `None` when it has less than 4 numbers.
+    pub(crate) fn from_object(rect: &lopdf::Object) -> Result<Option<Self>, Error> {
+        let rect = rect.as_array()?;
+        if rect.len() < 4 {
+            return Ok(None);
+        }
+        Ok(Some(Rectangle {
+            x1: rect[0].as_float()?,
+            y1: rect[1].as_float()?,
+            x2: rect[2].as_float()?,
+            y2: rect[3].as_float()?,
+        }))
+    }
+}
@@ -0,0 +1,449 @@
+//! Compare two revisions of a document, object by object.
+//!
+//! Each added, modified or deleted object is listed with the parts of the document that use
+//! it: pages, annotations, form fields and entries of the document catalog. The owners are
+//! found by following the references from those parts, pages are only reached from the page
+//! tree and fields only from `AcroForm`.
+
+use crate::error::Error;
+use crate::pdf_object::PdfObjectDeref;
+use crate::{lopdf_utils, PDFSigningDocument};
+use lopdf::{Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How an object changed between two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A part of the document that uses an object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectOwner {
+    /// A page, its content or resources. Page numbers start at 1.
+    Page(u32),
+    /// An annotation on a page, or its appearance.
+    Annotation {
+        page: u32,
+        annotation_id: ObjectId,
+        subtype: Option<String>,
+    },
+    /// A form field, its value or widgets. The name is fully qualified.
+    FormField(String),
+    /// An entry of the document catalog, for example `DSS` or `Outlines`.
+    Catalog(String),
+}
+
+/// A changed object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDiff {
+    pub object_id: ObjectId,
+    pub change: ObjectChange,
+    /// `Type` or `Subtype` of a dictionary or stream, otherwise the kind of object.
+    pub object_type: String,
+    /// Entries of a dictionary or stream that were added, changed or removed.
+    pub changed_keys: Vec<String>,
+    /// The data of a stream changed.
+    pub stream_changed: bool,
+    /// The parts of the document that use the object, in the revision that contains it.
+    pub owners: Vec<ObjectOwner>,
+}
+
+/// The objects that changed from one revision to another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionDiffReport {
+    /// Index of the old revision, see `PDFSigningDocument::revisions()`.
+    pub from_revision: usize,
+    pub to_revision: usize,
+    pub changes: Vec<ObjectDiff>,
+}
+
+impl PDFSigningDocument {
+    /// Compare revision `from` with revision `to`, the indexes of `revisions()`.
+    pub fn revision_diff(&self, from: usize, to: usize) -> Result<RevisionDiffReport, Error> {
+        let pdf_file_data = self.raw_document.get_prev_documents_bytes();
+        let revision_ends = Self::revision_ends(pdf_file_data);
+        let load = |index: usize| match revision_ends.get(index) {
+            Some((_, length)) => Ok(Document::load_mem(&pdf_file_data[..*length])?),
+            None => Err(Error::Other(format!("Revision {} does not exist.", index))),
+        };
+        Ok(RevisionDiffReport::new(from, &load(from)?, to, &load(to)?))
+    }
+}
+
+impl RevisionDiffReport {
+    fn new(from_revision: usize, old: &Document, to_revision: usize, new: &Document) -> Self {
+        let old_owners = OwnerMap::new(old);
+        let new_owners = OwnerMap::new(new);
+        let mut changes = vec![];
+        for (object_id, old_object) in &old.objects {
+            let diff = match new.objects.get(object_id) {
+                Some(new_object) if lopdf_utils::same_object(old_object, new_object) => continue,
+                Some(new_object) => ObjectDiff::new(
+                    *object_id,
+                    ObjectChange::Modified,
+                    Some(old_object),
+                    new_object,
+                    new_owners.get(*object_id),
+                ),
+                None => ObjectDiff::new(
+                    *object_id,
+                    ObjectChange::Deleted,
+                    None,
+                    old_object,
+                    old_owners.get(*object_id),
+                ),
+            };
+            changes.push(diff);
+        }
+        for (object_id, new_object) in &new.objects {
+            if !old.objects.contains_key(object_id) {
+                changes.push(ObjectDiff::new(
+                    *object_id,
+                    ObjectChange::Added,
+                    None,
+                    new_object,
+                    new_owners.get(*object_id),
+                ));
+            }
+        }
+        // Cross-reference and object streams only store other objects.
+        changes.retain(|diff| !matches!(diff.object_type.as_str(), "XRef" | "ObjStm"));
+        changes.sort_by_key(|diff| diff.object_id);
+        RevisionDiffReport {
+            from_revision,
+            to_revision,
+            changes,
+        }
+    }
+}
+
+impl ObjectDiff {
+    /// `old` is only set for modified objects, `object` is the object in the revision
+    /// that contains it.
+    fn new(
+        object_id: ObjectId,
+        change: ObjectChange,
+        old: Option<&Object>,
+        object: &Object,
+        owners: Vec<ObjectOwner>,
+    ) -> Self {
+        let (changed_keys, stream_changed) = match (old, object) {
+            (Some(Object::Stream(old)), Object::Stream(new)) => (
+                Self::changed_keys(&old.dict, &new.dict),
+                old.content != new.content,
+            ),
+            (Some(Object::Dictionary(old)), Object::Dictionary(new)) => {
+                (Self::changed_keys(old, new), false)
+            }
+            _ => (vec![], false),
+        };
+        ObjectDiff {
+            object_id,
+            change,
+            object_type: Self::object_type(object),
+            changed_keys,
+            stream_changed,
+            owners,
+        }
+    }
+
+    fn object_type(object: &Object) -> String {
+        let dict = match object {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        };
+        let type_name = dict.and_then(|dict| {
+            dict.type_name()
+                .or_else(|_| dict.get(b"Subtype").and_then(Object::as_name_str))
+                .or_else(|_| dict.get(b"FT").and(Ok("Field")))
+                .ok()
+        });
+        type_name
+            .unwrap_or(match object {
+                Object::Null => "Null",
+                Object::Boolean(_) => "Boolean",
+                Object::Integer(_) => "Integer",
+                Object::Real(_) => "Real",
+                Object::Name(_) => "Name",
+                Object::String(..) => "String",
+                Object::Array(_) => "Array",
+                Object::Dictionary(_) => "Dictionary",
+                Object::Stream(_) => "Stream",
+                Object::Reference(_) => "Reference",
+            })
+            .to_owned()
+    }
+
+    /// The keys with a different value, sorted.
+    fn changed_keys(old: &lopdf::Dictionary, new: &lopdf::Dictionary) -> Vec<String> {
+        let mut keys: Vec<String> = lopdf_utils::changed_keys(old, new)
+            .into_iter()
+            .map(|key| String::from_utf8_lossy(key).into_owned())
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+/// The owners of the objects of one revision.
+struct OwnerMap<'a> {
+    document: &'a Document,
+    /// Page and page tree objects, references to them are not followed.
+    page_tree: HashSet<ObjectId>,
+    owners: HashMap<ObjectId, Vec<ObjectOwner>>,
+}
+
+impl<'a> OwnerMap<'a> {
+    fn new(document: &'a Document) -> Self {
+        let mut owner_map = OwnerMap {
+            document,
+            page_tree: HashSet::new(),
+            owners: HashMap::new(),
+        };
+        for (object_id, object) in &document.objects {
+            if let Ok(dict) = object.as_dict() {
+                if dict.type_is(b"Pages") || dict.type_is(b"Page") {
+                    owner_map.page_tree.insert(*object_id);
+                }
+            }
+        }
+        owner_map.add_pages();
+        if let Ok(root) = document.trailer.get(b"Root").and_then(|root| {
+            root.deref(document)
+                .map_err(|_| lopdf::Error::Type)
+                .and_then(Object::as_dict)
+        }) {
+            if let Ok(fields) = root
+                .get(b"AcroForm")
+                .ok()
+                .and_then(|acro_form| acro_form.deref(document).ok())
+                .ok_or(lopdf::Error::DictKey)
+                .and_then(Object::as_dict)
+                .and_then(|acro_form| acro_form.get(b"Fields"))
+            {
+                for field in owner_map.array_items(fields) {
+                    owner_map.add_field(field, None, 0);
+                }
+            }
+            // Catalog entries only own the objects that are not used by another part.
+            let owned: HashSet<ObjectId> = owner_map.owners.keys().copied().collect();
+            for (key, value) in root.iter() {
+                if key == b"Pages" {
+                    continue;
+                }
+                let key = String::from_utf8_lossy(key).into_owned();
+                owner_map.add(value, ObjectOwner::Catalog(key), &[b"Fields"], &owned);
+            }
+        }
+        owner_map
+    }
+
+    fn get(&self, object_id: ObjectId) -> Vec<ObjectOwner> {
+        self.owners.get(&object_id).cloned().unwrap_or_default()
+    }
+
+    /// Pages with their content and resources, and the annotations on them.
+    fn add_pages(&mut self) {
+        let document = self.document;
+        for (page_number, page_id) in document.get_pages() {
+            self.add(
+                &Object::Reference(page_id),
+                ObjectOwner::Page(page_number),
+                &[b"Annots"],
+                &HashSet::new(),
+            );
+            let Ok(annotations) = document
+                .get_dictionary(page_id)
+                .and_then(|page| page.get(b"Annots"))
+            else {
+                continue;
+            };
+            if let Some(annotations_id) = annotations.get_object_id() {
+                self.push(annotations_id, ObjectOwner::Page(page_number));
+            }
+            for annotation in self.array_items(annotations) {
+                let subtype = annotation
+                    .deref(document)
+                    .ok()
+                    .and_then(|annotation| annotation.as_dict().ok())
+                    .and_then(|annotation| annotation.get(b"Subtype").ok())
+                    .and_then(|subtype| subtype.as_name_str().ok())
+                    .map(str::to_owned);
+                let Some(annotation_id) = annotation.get_object_id() else {
+                    continue;
+                };
+                let owner = ObjectOwner::Annotation {
+                    page: page_number,
+                    annotation_id,
+                    subtype,
+                };
+                self.add(annotation, owner, &[b"Parent", b"Popup"], &HashSet::new());
+            }
+        }
+    }
+
+    /// A field with its value and widgets, then its kids.
+    fn add_field(&mut self, field: &Object, parent_name: Option<&str>, depth: usize) {
+        let document = self.document;
+        let Ok(field_dict) = field.deref(document).and_then(|field| Ok(field.as_dict()?)) else {
+            return;
+        };
+        let partial_name = lopdf_utils::as_option_text_string(field_dict.get(b"T").ok())
+            .ok()
+            .flatten();
+        let name = match (parent_name, partial_name) {
+            (Some(parent_name), Some(partial_name)) => format!("{}.{}", parent_name, partial_name),
+            (None, Some(partial_name)) => partial_name,
+            // Widgets without a name belong to their parent field.
+            (Some(parent_name), None) => parent_name.to_owned(),
+            (None, None) => String::new(),
+        };
+        self.add(
+            field,
+            ObjectOwner::FormField(name.clone()),
+            &[b"Kids", b"Parent"],
+            &HashSet::new(),
+        );
+        if depth == lopdf_utils::MAX_FIELD_DEPTH {
+            return;
+        }
+        if let Ok(kids) = field_dict.get(b"Kids") {
+            if let Some(kids_id) = kids.get_object_id() {
+                self.push(kids_id, ObjectOwner::FormField(name.clone()));
+            }
+            for kid in self.array_items(kids) {
+                self.add_field(kid, Some(&name), depth + 1);
+            }
+        }
+    }
+
+    /// Add `owner` to `object` and all objects it refers to, except through `skip_keys`,
+    /// `P` and pages. Objects in `stop` are not changed or followed.
+    fn add(
+        &mut self,
+        object: &Object,
+        owner: ObjectOwner,
+        skip_keys: &[&[u8]],
+        stop: &HashSet<ObjectId>,
+    ) {
+        let document = self.document;
+        let start = object.get_object_id();
+        let mut seen = HashSet::new();
+        let mut todo = vec![object];
+        while let Some(object) = todo.pop() {
+            match object {
+                Object::Reference(object_id) => {
+                    let is_page = self.page_tree.contains(object_id) && start != Some(*object_id);
+                    if is_page || stop.contains(object_id) || !seen.insert(*object_id) {
+                        continue;
+                    }
+                    self.push(*object_id, owner.clone());
+                    if let Some(object) = document.objects.get(object_id) {
+                        todo.push(object);
+                    }
+                }
+                Object::Array(items) => todo.extend(items),
+                Object::Dictionary(dict) => todo.extend(
+                    dict.iter()
+                        .filter(|(key, _)| *key != b"P" && !skip_keys.contains(&key.as_slice()))
+                        .map(|(_, value)| value),
+                ),
+                Object::Stream(stream) => todo.extend(
+                    stream
+                        .dict
+                        .iter()
+                        .filter(|(key, _)| *key != b"P" && !skip_keys.contains(&key.as_slice()))
+                        .map(|(_, value)| value),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    fn push(&mut self, object_id: ObjectId, owner: ObjectOwner) {
+        let owners = self.owners.entry(object_id).or_default();
+        if !owners.contains(&owner) {
+            owners.push(owner);
+        }
+    }
+
+    fn array_items(&self, object: &'a Object) -> &'a [Object] {
+        object
+            .deref(self.document)
+            .and_then(|array| Ok(array.as_array()?))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for ObjectOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectOwner::Page(page) => write!(f, "page {}", page),
+            ObjectOwner::Annotation {
+                page,
+                annotation_id,
+                subtype,
+            } => write!(
+                f,
+                "{} annotation {} {} R on page {}",
+                subtype.as_deref().unwrap_or("unknown"),
+                annotation_id.0,
+                annotation_id.1,
+                page
+            ),
+            ObjectOwner::FormField(name) => write!(f, "form field `{}`", name),
+            ObjectOwner::Catalog(key) => write!(f, "catalog entry `{}`", key),
+        }
+    }
+}
+
+impl fmt::Display for ObjectDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let change = match self.change {
+            ObjectChange::Added => "Added",
+            ObjectChange::Modified => "Modified",
+            ObjectChange::Deleted => "Deleted",
+        };
+        write!(
+            f,
+            "{} {} {} R ({})",
+            change, self.object_id.0, self.object_id.1, self.object_type
+        )?;
+        if !self.changed_keys.is_empty() {
+            write!(f, ", entries {}", self.changed_keys.join(", "))?;
+        }
+        if self.stream_changed {
+            write!(f, ", stream data")?;
+        }
+        for (index, owner) in self.owners.iter().enumerate() {
+            let separator = if index == 0 { ": " } else { ", " };
+            write!(f, "{}{}", separator, owner)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RevisionDiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Changes from revision {} to {}:",
+            self.from_revision, self.to_revision
+        )?;
+        for change in &self.changes {
+            writeln!(f, "- {}", change)?;
+        }
+        Ok(())
+    }
+}
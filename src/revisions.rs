@@ -0,0 +1,139 @@
+//! Revisions of a document: the original file and each incremental update appended to it.
+//!
+//! A revision ends after the `%%EOF` marker of its trailer, the part of the file before that
+//! is a complete document. A signature covers the revision that ends with its `ByteRange`.
+
+use crate::error::Error;
+use crate::{PDFSigningDocument, SignatureInfo};
+
+/// One revision of the document.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// Number of the revision, 0 is the original document.
+    pub index: usize,
+    /// Length of the file up to and including this revision, in bytes.
+    pub length: u64,
+    /// The signatures added in this revision.
+    pub signatures: Vec<SignatureInfo>,
+}
+
+impl PDFSigningDocument {
+    /// List the revisions of the document, the last one is the current document.
+    pub fn revisions(&self) -> Result<Vec<Revision>, Error> {
+        let pdf_file_data = self.raw_document.get_prev_documents_bytes();
+        let revision_ends = Self::revision_ends(pdf_file_data);
+        let mut revisions: Vec<Revision> = revision_ends
+            .iter()
+            .enumerate()
+            .map(|(index, (_, length))| Revision {
+                index,
+                length: *length as u64,
+                signatures: vec![],
+            })
+            .collect();
+        for signature in self.signatures()? {
+            let signed_length = Self::signed_length(&signature)?;
+            // The signature is part of the first revision that contains the whole `ByteRange`.
+            let index = revisions
+                .iter()
+                .position(|revision| revision.length >= signed_length);
+            match index {
+                Some(index) => {
+                    // The end of line after `%%EOF` can be part of the next update.
+                    if revision_ends[index].0 as u64 <= signed_length {
+                        revisions[index].length = signed_length;
+                    }
+                    revisions[index].signatures.push(signature);
+                }
+                None => log::warn!(
+                    "Signature {:?} does not end in a revision.",
+                    signature.field_name
+                ),
+            }
+        }
+        Ok(revisions)
+    }
+
+    /// The document as it was when `signature` was created: the file up to the end of its
+    /// `ByteRange`. Changes made after the signature are not included.
+    pub fn signed_revision(&self, signature: &SignatureInfo) -> Result<Self, Error> {
+        let pdf_file_data = self.raw_document.get_prev_documents_bytes();
+        // Checks that the `ByteRange` is in the file.
+        Self::signed_ranges(pdf_file_data, &signature.byte_range)?;
+        self.truncated(Self::signed_length(signature)?)
+    }
+
+    /// The document up to and including `revision`.
+    pub fn revision(&self, revision: &Revision) -> Result<Self, Error> {
+        self.truncated(revision.length)
+    }
+
+    /// Read the first `length` bytes of the file as a new document.
+    fn truncated(&self, length: u64) -> Result<Self, Error> {
+        let pdf_file_data = self.raw_document.get_prev_documents_bytes();
+        let length = usize::try_from(length)?;
+        if length > pdf_file_data.len() {
+            return Err(Error::from("Revision is longer than the document."));
+        }
+        Self::read_from(&pdf_file_data[..length], self.file_name.clone())
+    }
+
+    /// The end of the last range of the `ByteRange`.
+    fn signed_length(signature: &SignatureInfo) -> Result<u64, Error> {
+        signature
+            .byte_range
+            .last()
+            .and_then(|(offset, length)| offset.checked_add(*length))
+            .ok_or_else(|| Error::from("Signature `ByteRange` is not valid."))
+    }
+
+    /// The end of each revision: after `startxref`, the offset and `%%EOF`,
+    /// without and with the end of line that follows.
+    pub(crate) fn revision_ends(pdf_file_data: &[u8]) -> Vec<(usize, usize)> {
+        const STARTXREF: &[u8] = b"startxref";
+        const EOF: &[u8] = b"%%EOF";
+        let mut ends = vec![];
+        let mut position = 0;
+        while let Some(found) = pdf_file_data[position..]
+            .windows(STARTXREF.len())
+            .position(|window| window == STARTXREF)
+        {
+            position += found + STARTXREF.len();
+            let rest = &pdf_file_data[position..];
+            let offset_start = rest
+                .iter()
+                .position(|byte| !byte.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            let offset_length = rest[offset_start..]
+                .iter()
+                .position(|byte| !byte.is_ascii_digit())
+                .unwrap_or(rest.len() - offset_start);
+            let eof_start = offset_start
+                + offset_length
+                + rest[offset_start + offset_length..]
+                    .iter()
+                    .position(|byte| !byte.is_ascii_whitespace())
+                    .unwrap_or(0);
+            if offset_length == 0 || !rest[eof_start..].starts_with(EOF) {
+                continue;
+            }
+            let eof_end = position + eof_start + EOF.len();
+            let end = if pdf_file_data[eof_end..].starts_with(b"\r\n") {
+                eof_end + 2
+            } else if pdf_file_data[eof_end..].starts_with(b"\n")
+                || pdf_file_data[eof_end..].starts_with(b"\r")
+            {
+                eof_end + 1
+            } else {
+                eof_end
+            };
+            ends.push((eof_end, end));
+            position = end;
+        }
+        if ends.is_empty() {
+            // No trailer found, the whole file is one revision.
+            ends.push((pdf_file_data.len(), pdf_file_data.len()));
+        }
+        ends
+    }
+}
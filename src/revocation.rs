@@ -0,0 +1,619 @@
+//! Offline revocation checking with the CRLs and OCSP responses embedded in the document.
+//!
+//! The revocation data comes from the `DSS` (`PDFSigningDocument::document_security_store()`)
+//! and from the `adbe-revocationInfoArchival` attribute of each signature
+//! (`SignatureVerification::revocation_info`). Nothing is fetched.
+//!
+//! Only CRLs signed by the issuer of the certificate are used (no indirect or delta CRLs).
+//! OCSP responses are signed by the issuer or by an OCSP responder certificate of the issuer.
+
+use crate::{ChainProblem, ChainValidation, Error, SignatureAlgorithm, TrustStore, ValidationData};
+use bcder::{
+    decode::{Constructed, DecodeError, Source},
+    BitString, ConstOid, Integer, Mode, OctetString, Oid, Tag,
+};
+use chrono::{DateTime, Utc};
+use x509_certificate::{
+    asn1time::{GeneralizedTime, Time},
+    rfc3280::Name,
+    rfc5280::{AlgorithmIdentifier, Extensions},
+    CapturedX509Certificate, DigestAlgorithm,
+};
+
+/// Adobe revocation information signed attribute
+///
+/// 1.2.840.113583.1.1.8
+pub(crate) const OID_ADBE_REVOCATION_INFO_ARCHIVAL: ConstOid =
+    Oid(&[42, 134, 72, 134, 247, 47, 1, 1, 8]);
+/// Basic OCSP response type (RFC 6960)
+///
+/// 1.3.6.1.5.5.7.48.1.1
+const OID_OCSP_BASIC: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 48, 1, 1]);
+/// OCSP signing extended key usage (RFC 5280)
+///
+/// 1.3.6.1.5.5.7.3.9
+const OID_OCSP_SIGNING: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 3, 9]);
+/// CRL entry reason code extension (RFC 5280)
+///
+/// 2.5.29.21
+const OID_CRL_REASON: ConstOid = Oid(&[85, 29, 21]);
+
+/// `removeFromCRL` reason, the certificate is no longer revoked (RFC 5280 5.3.1).
+const CRL_REASON_REMOVE_FROM_CRL: u8 = 8;
+
+/// Revocation status of a certificate at the validation time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevocationStatus {
+    /// Not revoked at the validation time.
+    Good,
+    Revoked {
+        revocation_time: DateTime<Utc>,
+        /// `CRLReason` code (RFC 5280 5.3.1).
+        reason: Option<u8>,
+    },
+    /// No usable CRL or OCSP response was found.
+    Unknown,
+}
+
+/// Where the revocation status was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevocationSource {
+    Crl,
+    Ocsp,
+}
+
+/// The revocation status of one certificate in the chain.
+#[derive(Debug, Clone)]
+pub struct CertificateRevocation {
+    pub certificate: CapturedX509Certificate,
+    pub status: RevocationStatus,
+    /// `None` when the status is `Unknown`.
+    pub source: Option<RevocationSource>,
+}
+
+/// A parsed certificate revocation list.
+struct Crl {
+    issuer: Name,
+    next_update: Option<DateTime<Utc>>,
+    /// Serial number, revocation date and reason.
+    revoked: Vec<(Integer, DateTime<Utc>, Option<u8>)>,
+    signed_data: SignedPart,
+}
+
+/// A parsed basic OCSP response.
+struct OcspResponse {
+    responder: ResponderId,
+    produced_at: DateTime<Utc>,
+    responses: Vec<SingleResponse>,
+    /// Certificates included in the response, used to find the responder.
+    certificates: Vec<CapturedX509Certificate>,
+    signed_data: SignedPart,
+}
+
+enum ResponderId {
+    ByName(Name),
+    /// SHA-1 of the public key of the responder.
+    ByKey(Vec<u8>),
+}
+
+struct SingleResponse {
+    hash_algorithm: AlgorithmIdentifier,
+    issuer_name_hash: Vec<u8>,
+    issuer_key_hash: Vec<u8>,
+    serial_number: Integer,
+    status: RevocationStatus,
+    next_update: Option<DateTime<Utc>>,
+}
+
+/// The signed part of a CRL or OCSP response with its signature.
+struct SignedPart {
+    data: Vec<u8>,
+    signature_algorithm: AlgorithmIdentifier,
+    signature: Vec<u8>,
+}
+
+impl ValidationData {
+    /// Decode the value of an `adbe-revocationInfoArchival` attribute.
+    pub(crate) fn from_revocation_info_archival(data: &[u8]) -> Result<Self, Error> {
+        Constructed::decode(data, Mode::Der, |cons| {
+            cons.take_sequence(|cons| {
+                let crls = cons
+                    .take_opt_constructed_if(Tag::CTX_0, |cons| cons.take_sequence(take_values))?
+                    .unwrap_or_default();
+                let ocsps = cons
+                    .take_opt_constructed_if(Tag::CTX_1, |cons| cons.take_sequence(take_values))?
+                    .unwrap_or_default();
+                // `otherRevInfo` is not supported.
+                cons.skip_all()?;
+                Ok(ValidationData {
+                    certs: vec![],
+                    ocsps,
+                    crls,
+                })
+            })
+        })
+        .map_err(|err| Error::Other(format!("Revocation info is not valid: {}", err)))
+    }
+
+    /// Add the data of `other` that is not already included.
+    pub fn merge(&mut self, other: &ValidationData) {
+        for (list, other_list) in [
+            (&mut self.certs, &other.certs),
+            (&mut self.ocsps, &other.ocsps),
+            (&mut self.crls, &other.crls),
+        ] {
+            for item in other_list {
+                if !list.contains(item) {
+                    list.push(item.clone());
+                }
+            }
+        }
+    }
+
+    /// Check if the certificates in the `chain` were revoked at its validation time.
+    ///
+    /// The trust anchor is not checked. Invalid CRLs and OCSP responses are ignored, a
+    /// certificate without usable revocation data is `RevocationStatus::Unknown`.
+    pub fn check_revocation(&self, chain: &ChainValidation) -> Vec<CertificateRevocation> {
+        let crls: Vec<Crl> = self
+            .crls
+            .iter()
+            .filter_map(|crl| {
+                Crl::parse(crl)
+                    .map_err(|err| log::warn!("CRL is ignored: {:?}", err))
+                    .ok()
+            })
+            .collect();
+        let ocsp_responses: Vec<OcspResponse> = self
+            .ocsps
+            .iter()
+            .filter_map(|ocsp| {
+                OcspResponse::parse(ocsp)
+                    .map_err(|err| log::warn!("OCSP response is ignored: {:?}", err))
+                    .ok()
+            })
+            .collect();
+        let certificates: Vec<CapturedX509Certificate> = self
+            .certs
+            .iter()
+            .filter_map(|cert| CapturedX509Certificate::from_der(cert.clone()).ok())
+            .collect();
+
+        let anchored = !chain.problems.contains(&ChainProblem::NoTrustAnchor);
+        let checked_length = if anchored {
+            chain.chain.len() - 1
+        } else {
+            chain.chain.len()
+        };
+        chain.chain[..checked_length]
+            .iter()
+            .enumerate()
+            .map(|(index, certificate)| {
+                let (status, source) = match chain.chain.get(index + 1) {
+                    Some(issuer) => Self::revocation_status(
+                        certificate,
+                        issuer,
+                        &crls,
+                        &ocsp_responses,
+                        &certificates,
+                        chain.validation_time,
+                    ),
+                    // Revocation data can not be checked without the issuer.
+                    None => (RevocationStatus::Unknown, None),
+                };
+                CertificateRevocation {
+                    certificate: certificate.clone(),
+                    status,
+                    source,
+                }
+            })
+            .collect()
+    }
+
+    /// Combine the statements of all CRLs and OCSP responses about the `certificate`.
+    /// A revocation before `validation_time` wins over a good status.
+    fn revocation_status(
+        certificate: &CapturedX509Certificate,
+        issuer: &CapturedX509Certificate,
+        crls: &[Crl],
+        ocsp_responses: &[OcspResponse],
+        certificates: &[CapturedX509Certificate],
+        validation_time: DateTime<Utc>,
+    ) -> (RevocationStatus, Option<RevocationSource>) {
+        let crl_statuses = crls
+            .iter()
+            .filter_map(|crl| crl.status(certificate, issuer, validation_time))
+            .map(|status| (status, RevocationSource::Crl));
+        let ocsp_statuses = ocsp_responses
+            .iter()
+            .filter_map(|response| {
+                response.status(certificate, issuer, certificates, validation_time)
+            })
+            .map(|status| (status, RevocationSource::Ocsp));
+        let statuses: Vec<_> = crl_statuses.chain(ocsp_statuses).collect();
+
+        let revoked = statuses
+            .iter()
+            .filter(|(status, _)| {
+                matches!(status, RevocationStatus::Revoked { revocation_time, .. }
+                    if *revocation_time <= validation_time)
+            })
+            .min_by_key(|(status, _)| match status {
+                RevocationStatus::Revoked {
+                    revocation_time, ..
+                } => Some(*revocation_time),
+                _ => None,
+            });
+        if let Some((status, source)) = revoked {
+            return (status.clone(), Some(*source));
+        }
+        // Revoked after the validation time, so it was good at the validation time.
+        match statuses
+            .iter()
+            .find(|(status, _)| status != &RevocationStatus::Unknown)
+        {
+            Some((_, source)) => (RevocationStatus::Good, Some(*source)),
+            None => (RevocationStatus::Unknown, None),
+        }
+    }
+}
+
+impl Crl {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let signed_data = SignedPart::parse(data)?;
+        Constructed::decode(signed_data.data.as_slice(), Mode::Der, |cons| {
+            cons.take_sequence(|cons| {
+                // `version`
+                cons.take_opt_u8()?;
+                AlgorithmIdentifier::take_from(cons)?;
+                let issuer = Name::take_from(cons)?;
+                Time::take_from(cons)?;
+                let next_update = Time::take_opt_from(cons)?.map(DateTime::from);
+                let mut revoked = vec![];
+                cons.take_opt_sequence(|cons| {
+                    while let Some(entry) = cons.take_opt_sequence(|cons| {
+                        let serial_number = Integer::take_from(cons)?;
+                        let revocation_date = DateTime::from(Time::take_from(cons)?);
+                        let reason = Extensions::take_opt_from(cons)?
+                            .iter()
+                            .flat_map(|extensions| extensions.iter())
+                            .find(|extension| extension.id == OID_CRL_REASON)
+                            .and_then(|extension| reason_code(&extension.value.to_bytes()));
+                        Ok((serial_number, revocation_date, reason))
+                    })? {
+                        revoked.push(entry);
+                    }
+                    Ok(())
+                })?;
+                // `crlExtensions`
+                cons.skip_all()?;
+                Ok((issuer, next_update, revoked))
+            })
+        })
+        .map(|(issuer, next_update, revoked)| Crl {
+            issuer,
+            next_update,
+            revoked,
+            signed_data,
+        })
+        .map_err(|err| Error::Other(format!("CRL is not valid: {}", err)))
+    }
+
+    /// The status of the `certificate`, `None` when this CRL is not from its `issuer` or
+    /// expired before the `validation_time`.
+    fn status(
+        &self,
+        certificate: &CapturedX509Certificate,
+        issuer: &CapturedX509Certificate,
+        validation_time: DateTime<Utc>,
+    ) -> Option<RevocationStatus> {
+        if &self.issuer != certificate.issuer_name()
+            || self
+                .next_update
+                .is_some_and(|next_update| next_update < validation_time)
+            || !self.signed_data.is_signed_by(issuer)
+        {
+            return None;
+        }
+        let status = match self
+            .revoked
+            .iter()
+            .find(|(serial_number, _, _)| serial_number == certificate.serial_number_asn1())
+        {
+            Some((_, _, Some(CRL_REASON_REMOVE_FROM_CRL))) | None => RevocationStatus::Good,
+            Some((_, revocation_time, reason)) => RevocationStatus::Revoked {
+                revocation_time: *revocation_time,
+                reason: *reason,
+            },
+        };
+        Some(status)
+    }
+}
+
+impl OcspResponse {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let invalid = |err| Error::Other(format!("OCSP response is not valid: {}", err));
+        let (response_status, response) = Constructed::decode(data, Mode::Der, |cons| {
+            cons.take_sequence(|cons| {
+                let response_status =
+                    cons.take_primitive_if(Tag::ENUMERATED, |prim| prim.to_u8())?;
+                let response = cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+                    cons.take_sequence(|cons| {
+                        let response_type = Oid::take_from(cons)?;
+                        let response = OctetString::take_from(cons)?;
+                        Ok((response_type, response.to_bytes().to_vec()))
+                    })
+                })?;
+                Ok((response_status, response))
+            })
+        })
+        .map_err(invalid)?;
+        let response = match response {
+            Some((response_type, response))
+                if response_status == 0 && response_type == OID_OCSP_BASIC =>
+            {
+                response
+            }
+            _ => return Err("OCSP response is not a successful basic response.".into()),
+        };
+
+        let (signed_data, certificates) =
+            Constructed::decode(response.as_slice(), Mode::Der, |cons| {
+                cons.take_sequence(|cons| {
+                    let signed_data = SignedPart::take_from(cons)?;
+                    let certificates = cons
+                        .take_opt_constructed_if(Tag::CTX_0, |cons| {
+                            cons.take_sequence(take_values)
+                        })?
+                        .unwrap_or_default();
+                    Ok((signed_data, certificates))
+                })
+            })
+            .map_err(invalid)?;
+        let certificates = certificates
+            .into_iter()
+            .map(CapturedX509Certificate::from_der)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (responder, produced_at, responses) =
+            Constructed::decode(signed_data.data.as_slice(), Mode::Der, |cons| {
+                cons.take_sequence(|cons| {
+                    // `version`
+                    cons.take_opt_constructed_if(Tag::CTX_0, |cons| cons.skip_all())?;
+                    let responder =
+                        match cons.take_opt_constructed_if(Tag::CTX_1, Name::take_from)? {
+                            Some(name) => ResponderId::ByName(name),
+                            None => ResponderId::ByKey(
+                                cons.take_constructed_if(Tag::CTX_2, OctetString::take_from)?
+                                    .to_bytes()
+                                    .to_vec(),
+                            ),
+                        };
+                    let produced_at = GeneralizedTime::take_from_allow_fractional_z(cons)?;
+                    let responses = cons.take_sequence(|cons| {
+                        let mut responses = vec![];
+                        while let Some(response) = SingleResponse::take_opt_from(cons)? {
+                            responses.push(response);
+                        }
+                        Ok(responses)
+                    })?;
+                    // `responseExtensions`
+                    cons.skip_all()?;
+                    Ok((responder, DateTime::from(produced_at), responses))
+                })
+            })
+            .map_err(invalid)?;
+
+        Ok(OcspResponse {
+            responder,
+            produced_at,
+            responses,
+            certificates,
+            signed_data,
+        })
+    }
+
+    /// The status of the `certificate`, `None` when this response is not about it, expired
+    /// before the `validation_time`, or is not signed by the `issuer` or its OCSP responder.
+    fn status(
+        &self,
+        certificate: &CapturedX509Certificate,
+        issuer: &CapturedX509Certificate,
+        certificates: &[CapturedX509Certificate],
+        validation_time: DateTime<Utc>,
+    ) -> Option<RevocationStatus> {
+        let response = self.responses.iter().find(|response| {
+            &response.serial_number == certificate.serial_number_asn1()
+                && response.is_issued_by(issuer)
+        })?;
+        if response
+            .next_update
+            .is_some_and(|next_update| next_update < validation_time)
+        {
+            return None;
+        }
+        if !self.is_signed_by_responder(issuer, certificates) {
+            return None;
+        }
+        Some(response.status.clone())
+    }
+
+    /// The response is signed by the `issuer`, or by a responder certificate issued by it
+    /// for OCSP signing (RFC 6960 4.2.2.2).
+    fn is_signed_by_responder(
+        &self,
+        issuer: &CapturedX509Certificate,
+        certificates: &[CapturedX509Certificate],
+    ) -> bool {
+        std::iter::once(issuer)
+            .chain(&self.certificates)
+            .chain(certificates)
+            .filter(|responder| self.is_responder(responder))
+            .filter(|responder| {
+                *responder == issuer
+                    || (responder.issuer_name() == issuer.subject_name()
+                        && responder.verify_signed_by_certificate(issuer).is_ok()
+                        && responder.time_constraints_valid(Some(self.produced_at))
+                        && TrustStore::extended_key_usage(responder).is_some_and(|key_purposes| {
+                            key_purposes
+                                .iter()
+                                .any(|key_purpose| key_purpose == &OID_OCSP_SIGNING)
+                        }))
+            })
+            .any(|responder| self.signed_data.is_signed_by(responder))
+    }
+
+    fn is_responder(&self, certificate: &CapturedX509Certificate) -> bool {
+        match &self.responder {
+            ResponderId::ByName(name) => certificate.subject_name() == name,
+            ResponderId::ByKey(key_hash) => {
+                &DigestAlgorithm::Sha1.digest_data(&certificate.public_key_data()) == key_hash
+            }
+        }
+    }
+}
+
+impl SingleResponse {
+    fn take_opt_from<S: Source>(
+        cons: &mut Constructed<S>,
+    ) -> Result<Option<Self>, DecodeError<S::Error>> {
+        cons.take_opt_sequence(|cons| {
+            let (hash_algorithm, issuer_name_hash, issuer_key_hash, serial_number) = cons
+                .take_sequence(|cons| {
+                    Ok((
+                        AlgorithmIdentifier::take_from(cons)?,
+                        OctetString::take_from(cons)?.to_bytes().to_vec(),
+                        OctetString::take_from(cons)?.to_bytes().to_vec(),
+                        Integer::take_from(cons)?,
+                    ))
+                })?;
+            let status = if cons
+                .take_opt_primitive_if(Tag::CTX_0, |prim| prim.skip_all())?
+                .is_some()
+            {
+                RevocationStatus::Good
+            } else if let Some(status) = cons.take_opt_constructed_if(Tag::CTX_1, |cons| {
+                let revocation_time = GeneralizedTime::take_from_allow_fractional_z(cons)?;
+                let reason = cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+                    cons.take_primitive_if(Tag::ENUMERATED, |prim| prim.to_u8())
+                })?;
+                Ok(RevocationStatus::Revoked {
+                    revocation_time: DateTime::from(revocation_time),
+                    reason,
+                })
+            })? {
+                status
+            } else {
+                cons.take_primitive_if(Tag::CTX_2, |prim| prim.skip_all())?;
+                RevocationStatus::Unknown
+            };
+            // `thisUpdate`
+            GeneralizedTime::take_from_allow_fractional_z(cons)?;
+            let next_update = cons
+                .take_opt_constructed_if(Tag::CTX_0, |cons| {
+                    GeneralizedTime::take_from_allow_fractional_z(cons)
+                })?
+                .map(DateTime::from);
+            // `singleExtensions`
+            cons.skip_all()?;
+            Ok(SingleResponse {
+                hash_algorithm,
+                issuer_name_hash,
+                issuer_key_hash,
+                serial_number,
+                status,
+                next_update,
+            })
+        })
+    }
+
+    /// The `CertID` hashes match the name and public key of the `issuer`.
+    fn is_issued_by(&self, issuer: &CapturedX509Certificate) -> bool {
+        let Ok(digest_algorithm) = DigestAlgorithm::try_from(&self.hash_algorithm) else {
+            return false;
+        };
+        let mut issuer_name = vec![];
+        if bcder::encode::Values::write_encoded(
+            &issuer.subject_name().encode_ref(),
+            Mode::Der,
+            &mut issuer_name,
+        )
+        .is_err()
+        {
+            return false;
+        }
+        digest_algorithm.digest_data(&issuer_name) == self.issuer_name_hash
+            && digest_algorithm.digest_data(&issuer.public_key_data()) == self.issuer_key_hash
+    }
+}
+
+impl SignedPart {
+    /// Parse a `SEQUENCE { tbs, signatureAlgorithm, signature }`, other fields are ignored.
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        Constructed::decode(data, Mode::Der, |cons| {
+            cons.take_sequence(|cons| {
+                let signed_data = Self::take_from(cons)?;
+                cons.skip_all()?;
+                Ok(signed_data)
+            })
+        })
+        .map_err(|err| Error::Other(format!("Signed data is not valid: {}", err)))
+    }
+
+    fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, DecodeError<S::Error>> {
+        let data = cons.capture_one()?.as_slice().to_vec();
+        let signature_algorithm = AlgorithmIdentifier::take_from(cons)?;
+        let signature = BitString::take_from(cons)?.octet_bytes().to_vec();
+        Ok(SignedPart {
+            data,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    fn is_signed_by(&self, signer: &CapturedX509Certificate) -> bool {
+        // The digest algorithm is only used for `rsaEncryption` and `id-ecPublicKey`.
+        SignatureAlgorithm::from_algorithm_identifier(
+            &self.signature_algorithm,
+            DigestAlgorithm::Sha256,
+        )
+        .ok()
+        .zip(signer.key_algorithm())
+        .and_then(|(signature_algorithm, key_algorithm)| {
+            signature_algorithm
+                .verification_algorithm(key_algorithm)
+                .ok()
+        })
+        .is_some_and(|verification_algorithm| {
+            signer
+                .verify_signed_data_with_algorithm(
+                    &self.data,
+                    &self.signature,
+                    verification_algorithm,
+                )
+                .is_ok()
+        })
+    }
+}
+
+/// All remaining values, each DER encoded.
+fn take_values<S: Source>(
+    cons: &mut Constructed<S>,
+) -> Result<Vec<Vec<u8>>, DecodeError<S::Error>> {
+    let mut values = vec![];
+    loop {
+        let value = cons.capture(|cons| cons.skip_one().map(|_| ()))?;
+        if value.as_slice().is_empty() {
+            return Ok(values);
+        }
+        values.push(value.as_slice().to_vec());
+    }
+}
+
+/// Decode the value of the `reasonCode` extension.
+fn reason_code(data: &[u8]) -> Option<u8> {
+    Constructed::decode(data, Mode::Der, |cons| {
+        cons.take_primitive_if(Tag::ENUMERATED, |prim| prim.to_u8())
+    })
+    .ok()
+}
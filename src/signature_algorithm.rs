@@ -0,0 +1,363 @@
+use crate::Error;
+use bcder::{
+    decode::Constructed,
+    encode::{self, PrimitiveContent, Values},
+    Captured, ConstOid, Mode, Oid, Tag,
+};
+use cryptographic_message_syntax::Bytes;
+use ring::signature::{self as ringsig, VerificationAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use x509_certificate::{
+    rfc5280::{AlgorithmIdentifier, AlgorithmParameter},
+    DigestAlgorithm, EcdsaCurve, KeyAlgorithm,
+};
+
+/// RSASSA-PKCS1-v1_5 with SHA-1, SHA-256, SHA-384 and SHA-512 (RFC 8017)
+///
+/// 1.2.840.113549.1.1.{5,11,12,13}
+const OID_SHA1_RSA: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 5]);
+const OID_SHA256_RSA: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 11]);
+const OID_SHA384_RSA: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 12]);
+const OID_SHA512_RSA: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 13]);
+
+/// RSA public key, also used as signature algorithm in CMS (RFC 8017)
+///
+/// 1.2.840.113549.1.1.1
+const OID_RSA_ENCRYPTION: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 1]);
+
+/// RSASSA-PSS (RFC 4055)
+///
+/// 1.2.840.113549.1.1.10
+const OID_RSASSA_PSS: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 10]);
+
+/// Mask generation function MGF1 (RFC 8017)
+///
+/// 1.2.840.113549.1.1.8
+const OID_MGF1: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 8]);
+
+/// ECDSA with SHA-1, SHA-256, SHA-384 and SHA-512 (RFC 5758)
+///
+/// 1.2.840.10045.4.1 and 1.2.840.10045.4.3.{2,3,4}
+const OID_ECDSA_SHA1: ConstOid = Oid(&[42, 134, 72, 206, 61, 4, 1]);
+const OID_ECDSA_SHA256: ConstOid = Oid(&[42, 134, 72, 206, 61, 4, 3, 2]);
+const OID_ECDSA_SHA384: ConstOid = Oid(&[42, 134, 72, 206, 61, 4, 3, 3]);
+const OID_ECDSA_SHA512: ConstOid = Oid(&[42, 134, 72, 206, 61, 4, 3, 4]);
+
+/// EC public key, also used as signature algorithm in CMS (RFC 5480)
+///
+/// 1.2.840.10045.2.1
+const OID_EC_PUBLIC_KEY: ConstOid = Oid(&[42, 134, 72, 206, 61, 2, 1]);
+
+/// Ed25519 (RFC 8410)
+///
+/// 1.3.101.112
+const OID_ED25519: ConstOid = Oid(&[43, 101, 112]);
+
+/// Serde representation of `DigestAlgorithm`.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "DigestAlgorithm", rename_all = "camelCase")]
+enum DigestAlgorithmDef {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// The signature scheme and digest algorithm used to create a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureAlgorithm {
+    /// RSASSA-PKCS1-v1_5
+    RsaPkcs1(#[serde(with = "DigestAlgorithmDef")] DigestAlgorithm),
+    /// RSASSA-PSS, MGF1 with the same digest and a salt of the length of the digest.
+    RsaPss(#[serde(with = "DigestAlgorithmDef")] DigestAlgorithm),
+    /// ECDSA, the signature is DER encoded (`Ecdsa-Sig-Value`).
+    Ecdsa(#[serde(with = "DigestAlgorithmDef")] DigestAlgorithm),
+    /// Ed25519, the data is signed as is. SHA-512 is used for the content digest (RFC 8419).
+    Ed25519,
+}
+
+impl SignatureAlgorithm {
+    /// The digest algorithm used for the content and the signed data.
+    pub fn digest_algorithm(&self) -> DigestAlgorithm {
+        match self {
+            SignatureAlgorithm::RsaPkcs1(digest_algorithm)
+            | SignatureAlgorithm::RsaPss(digest_algorithm)
+            | SignatureAlgorithm::Ecdsa(digest_algorithm) => *digest_algorithm,
+            SignatureAlgorithm::Ed25519 => DigestAlgorithm::Sha512,
+        }
+    }
+
+    /// Check if the algorithm can be used with a key of type `key_algorithm`.
+    /// Only combinations that can be verified are supported, like ECDSA with the
+    /// digest of the curve.
+    pub fn is_supported_by_key(&self, key_algorithm: KeyAlgorithm) -> bool {
+        self.verification_algorithm(key_algorithm).is_ok()
+    }
+
+    /// Check if the algorithm can be used to create new signatures. SHA-1 is only
+    /// accepted to verify existing signatures.
+    pub fn is_allowed_for_signing(&self) -> bool {
+        self.digest_algorithm() != DigestAlgorithm::Sha1
+    }
+
+    /// The default algorithm for a key of type `key_algorithm`.
+    pub fn default_for_key(key_algorithm: KeyAlgorithm) -> Self {
+        match key_algorithm {
+            KeyAlgorithm::Rsa => SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha256),
+            KeyAlgorithm::Ecdsa(EcdsaCurve::Secp384r1) => {
+                SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha384)
+            }
+            KeyAlgorithm::Ecdsa(_) => SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha256),
+            KeyAlgorithm::Ed25519 => SignatureAlgorithm::Ed25519,
+        }
+    }
+
+    /// The `signatureAlgorithm` of the CMS `SignerInfo`.
+    pub(crate) fn algorithm_identifier(&self) -> AlgorithmIdentifier {
+        let oid = |oid: ConstOid| Oid(Bytes::copy_from_slice(oid.as_ref()));
+        let null = || {
+            Some(AlgorithmParameter::from_captured(Captured::from_values(
+                Mode::Der,
+                ().encode(),
+            )))
+        };
+        let (algorithm, parameters) = match self {
+            SignatureAlgorithm::RsaPkcs1(digest_algorithm) => {
+                let algorithm = match digest_algorithm {
+                    DigestAlgorithm::Sha1 => OID_SHA1_RSA,
+                    DigestAlgorithm::Sha256 => OID_SHA256_RSA,
+                    DigestAlgorithm::Sha384 => OID_SHA384_RSA,
+                    DigestAlgorithm::Sha512 => OID_SHA512_RSA,
+                };
+                (oid(algorithm), null())
+            }
+            SignatureAlgorithm::RsaPss(digest_algorithm) => (
+                oid(OID_RSASSA_PSS),
+                Some(AlgorithmParameter::from_captured(Self::pss_parameters(
+                    *digest_algorithm,
+                ))),
+            ),
+            SignatureAlgorithm::Ecdsa(digest_algorithm) => {
+                let algorithm = match digest_algorithm {
+                    DigestAlgorithm::Sha1 => OID_ECDSA_SHA1,
+                    DigestAlgorithm::Sha256 => OID_ECDSA_SHA256,
+                    DigestAlgorithm::Sha384 => OID_ECDSA_SHA384,
+                    DigestAlgorithm::Sha512 => OID_ECDSA_SHA512,
+                };
+                (oid(algorithm), None)
+            }
+            SignatureAlgorithm::Ed25519 => (oid(OID_ED25519), None),
+        };
+        AlgorithmIdentifier {
+            algorithm,
+            parameters,
+        }
+    }
+
+    /// The algorithm of a CMS `SignerInfo` from its `signatureAlgorithm`.
+    ///
+    /// `digest_algorithm` (the `digestAlgorithm` of the `SignerInfo`) is used when the
+    /// signature algorithm does not specify the digest, like `rsaEncryption`.
+    pub(crate) fn from_algorithm_identifier(
+        algorithm_identifier: &AlgorithmIdentifier,
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Self, Error> {
+        let algorithm = &algorithm_identifier.algorithm;
+        let rsa = [
+            (OID_SHA1_RSA, DigestAlgorithm::Sha1),
+            (OID_SHA256_RSA, DigestAlgorithm::Sha256),
+            (OID_SHA384_RSA, DigestAlgorithm::Sha384),
+            (OID_SHA512_RSA, DigestAlgorithm::Sha512),
+        ];
+        let ecdsa = [
+            (OID_ECDSA_SHA1, DigestAlgorithm::Sha1),
+            (OID_ECDSA_SHA256, DigestAlgorithm::Sha256),
+            (OID_ECDSA_SHA384, DigestAlgorithm::Sha384),
+            (OID_ECDSA_SHA512, DigestAlgorithm::Sha512),
+        ];
+
+        if let Some((_, digest_algorithm)) = rsa.iter().find(|(oid, _)| algorithm == oid) {
+            Ok(SignatureAlgorithm::RsaPkcs1(*digest_algorithm))
+        } else if let Some((_, digest_algorithm)) = ecdsa.iter().find(|(oid, _)| algorithm == oid) {
+            Ok(SignatureAlgorithm::Ecdsa(*digest_algorithm))
+        } else if algorithm == &OID_RSA_ENCRYPTION {
+            Ok(SignatureAlgorithm::RsaPkcs1(digest_algorithm))
+        } else if algorithm == &OID_EC_PUBLIC_KEY {
+            Ok(SignatureAlgorithm::Ecdsa(digest_algorithm))
+        } else if algorithm == &OID_ED25519 {
+            Ok(SignatureAlgorithm::Ed25519)
+        } else if algorithm == &OID_RSASSA_PSS {
+            let parameters = algorithm_identifier
+                .parameters
+                .as_ref()
+                .ok_or_else(|| Error::from("RSASSA-PSS parameters are missing."))?;
+            Self::from_pss_parameters(parameters.as_slice())
+        } else {
+            Err(Error::Other(format!(
+                "Signature algorithm `{}` is not supported.",
+                algorithm
+            )))
+        }
+    }
+
+    /// The `ring` algorithm to verify a signature with a key of type `key_algorithm`.
+    pub(crate) fn verification_algorithm(
+        &self,
+        key_algorithm: KeyAlgorithm,
+    ) -> Result<&'static dyn VerificationAlgorithm, Error> {
+        use DigestAlgorithm::*;
+        let algorithm: &'static dyn VerificationAlgorithm = match (self, key_algorithm) {
+            (SignatureAlgorithm::RsaPkcs1(Sha1), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY
+            }
+            (SignatureAlgorithm::RsaPkcs1(Sha256), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PKCS1_2048_8192_SHA256
+            }
+            (SignatureAlgorithm::RsaPkcs1(Sha384), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PKCS1_2048_8192_SHA384
+            }
+            (SignatureAlgorithm::RsaPkcs1(Sha512), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PKCS1_2048_8192_SHA512
+            }
+            (SignatureAlgorithm::RsaPss(Sha256), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PSS_2048_8192_SHA256
+            }
+            (SignatureAlgorithm::RsaPss(Sha384), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PSS_2048_8192_SHA384
+            }
+            (SignatureAlgorithm::RsaPss(Sha512), KeyAlgorithm::Rsa) => {
+                &ringsig::RSA_PSS_2048_8192_SHA512
+            }
+            (SignatureAlgorithm::Ecdsa(Sha256), KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1)) => {
+                &ringsig::ECDSA_P256_SHA256_ASN1
+            }
+            (SignatureAlgorithm::Ecdsa(Sha384), KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1)) => {
+                &ringsig::ECDSA_P256_SHA384_ASN1
+            }
+            (SignatureAlgorithm::Ecdsa(Sha256), KeyAlgorithm::Ecdsa(EcdsaCurve::Secp384r1)) => {
+                &ringsig::ECDSA_P384_SHA256_ASN1
+            }
+            (SignatureAlgorithm::Ecdsa(Sha384), KeyAlgorithm::Ecdsa(EcdsaCurve::Secp384r1)) => {
+                &ringsig::ECDSA_P384_SHA384_ASN1
+            }
+            (SignatureAlgorithm::Ed25519, KeyAlgorithm::Ed25519) => &ringsig::ED25519,
+            (algorithm, key_algorithm) => {
+                return Err(Error::Other(format!(
+                    "Verifying `{}` with a `{:?}` key is not supported.",
+                    algorithm, key_algorithm
+                )))
+            }
+        };
+        Ok(algorithm)
+    }
+
+    /// Length in bytes of the PSS salt.
+    pub(crate) fn pss_salt_length(digest_algorithm: DigestAlgorithm) -> usize {
+        match digest_algorithm {
+            DigestAlgorithm::Sha1 => 20,
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha384 => 48,
+            DigestAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// ```ASN.1
+    /// RSASSA-PSS-params ::= SEQUENCE {
+    ///   hashAlgorithm      [0] HashAlgorithm DEFAULT sha1,
+    ///   maskGenAlgorithm   [1] MaskGenAlgorithm DEFAULT mgf1SHA1,
+    ///   saltLength         [2] INTEGER DEFAULT 20,
+    ///   trailerField       [3] TrailerField DEFAULT trailerFieldBC }
+    /// ```
+    fn pss_parameters(digest_algorithm: DigestAlgorithm) -> Captured {
+        let hash_algorithm =
+            || encode::sequence((Oid::from(digest_algorithm).encode(), ().encode()));
+        let salt_length = Self::pss_salt_length(digest_algorithm) as u32;
+        Captured::from_values(
+            Mode::Der,
+            encode::sequence((
+                hash_algorithm().explicit(Tag::CTX_0),
+                encode::sequence((
+                    Oid(Bytes::copy_from_slice(OID_MGF1.as_ref())).encode(),
+                    hash_algorithm(),
+                ))
+                .explicit(Tag::CTX_1),
+                salt_length.encode().explicit(Tag::CTX_2),
+            )),
+        )
+    }
+
+    /// Parse `RSASSA-PSS-params`, only MGF1 with a salt of the length of the digest is supported.
+    fn from_pss_parameters(parameters: &[u8]) -> Result<Self, Error> {
+        let (hash_algorithm, mask_gen_algorithm, salt_length) =
+            Constructed::decode(parameters, Mode::Ber, |cons| {
+                cons.take_sequence(|cons| {
+                    let hash_algorithm =
+                        cons.take_opt_constructed_if(Tag::CTX_0, AlgorithmIdentifier::take_from)?;
+                    let mask_gen_algorithm =
+                        cons.take_opt_constructed_if(Tag::CTX_1, AlgorithmIdentifier::take_from)?;
+                    let salt_length = cons.take_opt_constructed_if(Tag::CTX_2, |cons| {
+                        cons.take_primitive_if(Tag::INTEGER, |prim| prim.to_u64())
+                    })?;
+                    // `trailerField` is always `trailerFieldBC`.
+                    cons.take_opt_constructed_if(Tag::CTX_3, |cons| cons.capture_all())?;
+                    Ok((hash_algorithm, mask_gen_algorithm, salt_length))
+                })
+            })
+            .map_err(|err| Error::Other(format!("Invalid RSASSA-PSS parameters: {}", err)))?;
+
+        // The defaults are SHA-1, MGF1 with SHA-1 and a salt length of 20.
+        let digest_algorithm = match &hash_algorithm {
+            Some(hash_algorithm) => DigestAlgorithm::try_from(hash_algorithm)?,
+            None => DigestAlgorithm::Sha1,
+        };
+        if let Some(mask_gen_algorithm) = &mask_gen_algorithm {
+            if mask_gen_algorithm.algorithm != OID_MGF1 {
+                return Err("RSASSA-PSS mask generation function is not supported.".into());
+            }
+        }
+        if salt_length.unwrap_or(20) != Self::pss_salt_length(digest_algorithm) as u64 {
+            return Err("RSASSA-PSS salt length is not supported.".into());
+        }
+        Ok(SignatureAlgorithm::RsaPss(digest_algorithm))
+    }
+}
+
+impl TryFrom<x509_certificate::SignatureAlgorithm> for SignatureAlgorithm {
+    type Error = Error;
+
+    fn try_from(algorithm: x509_certificate::SignatureAlgorithm) -> Result<Self, Error> {
+        use x509_certificate::SignatureAlgorithm as X509;
+        match algorithm {
+            X509::RsaSha1 => Ok(SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha1)),
+            X509::RsaSha256 => Ok(SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha256)),
+            X509::RsaSha384 => Ok(SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha384)),
+            X509::RsaSha512 => Ok(SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha512)),
+            X509::EcdsaSha256 => Ok(SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha256)),
+            X509::EcdsaSha384 => Ok(SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha384)),
+            X509::Ed25519 => Ok(SignatureAlgorithm::Ed25519),
+            algorithm => Err(Error::Other(format!(
+                "Signature algorithm `{}` is not supported.",
+                algorithm
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureAlgorithm::RsaPkcs1(digest_algorithm) => {
+                write!(f, "RSA PKCS#1 v1.5 with {:?}", digest_algorithm)
+            }
+            SignatureAlgorithm::RsaPss(digest_algorithm) => {
+                write!(f, "RSA-PSS with {:?}", digest_algorithm)
+            }
+            SignatureAlgorithm::Ecdsa(digest_algorithm) => {
+                write!(f, "ECDSA with {:?}", digest_algorithm)
+            }
+            SignatureAlgorithm::Ed25519 => write!(f, "Ed25519"),
+        }
+    }
+}
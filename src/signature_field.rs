@@ -0,0 +1,119 @@
+//! Add empty signature fields to a document, so they can be signed with `sign_document()`.
+
+use crate::error::Error;
+use crate::{PDFSigningDocument, Rectangle, UserFormSignatureInfo};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use lopdf::{ObjectId, StringFormat};
+
+impl PDFSigningDocument {
+    /// Add an empty signature field for a user on `page` (starting at 1, like
+    /// `FormFieldWidget::page`), in a new incremental update. The `AcroForm` is created when
+    /// the document has none.
+    ///
+    /// The partial name (`T`) of the field is always the encoded `user_form_info`, that is how
+    /// `sign_document()` finds the field of a user, so `FormField::name` is not readable.
+    /// `alternate_name` is stored as the name shown to the user (`TU`), see
+    /// `FormField::alternate_name`.
+    ///
+    /// Returns the new PDF file, the document itself is also updated.
+    pub fn add_signature_field(
+        &mut self,
+        page: u32,
+        rect: Rectangle,
+        alternate_name: &str,
+        user_form_info: UserFormSignatureInfo,
+    ) -> Result<Vec<u8>, Error> {
+        self.load_all()?;
+        let mut update = Vec::new();
+        let result = self
+            .add_signature_field_objects(page, rect, alternate_name, &user_form_info)
+            .and_then(|_| self.write_update(&mut update));
+        if let Err(err) = result {
+            self.discard_incremental_update();
+            return Err(err);
+        }
+
+        // Continue with the new document, the new field is loaded when needed.
+        self.append_incremental_update(&update, None)?;
+        self.acro_form = None;
+        Ok(self.raw_document.get_prev_documents_bytes().to_vec())
+    }
+
+    /// Add the field, with the widget annotation as its kid, to the `AcroForm` and the page.
+    fn add_signature_field_objects(
+        &mut self,
+        page: u32,
+        rect: Rectangle,
+        alternate_name: &str,
+        user_form_info: &UserFormSignatureInfo,
+    ) -> Result<ObjectId, Error> {
+        use lopdf::Object::*;
+
+        let page_id = *self
+            .raw_document
+            .get_prev_documents()
+            .get_pages()
+            .get(&page)
+            .ok_or_else(|| Error::Other(format!("Page {} does not exist.", page)))?;
+
+        let encoded_data = BASE64_STANDARD.encode(
+            serde_json::to_vec(user_form_info)
+                .map_err(|err| Error::Other(format!("Can not encode user info: {}", err)))?,
+        );
+        // Field names have to be unique.
+        if self
+            .acro_form
+            .iter()
+            .flatten()
+            .any(|form| form.get_partial_field_name() == Some(encoded_data.as_str()))
+        {
+            return Err(Error::Other(format!(
+                "A signature field for user `{}` already exists.",
+                user_form_info.user_id
+            )));
+        }
+
+        let field_id = self.raw_document.new_document.new_object_id();
+        // `F` = 4: `Print`
+        let widget_dictionary = Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("Type", Name("Annot".as_bytes().to_vec())),
+            ("Subtype", Name("Widget".as_bytes().to_vec())),
+            ("F", Integer(4)),
+            (
+                "Rect",
+                Array(vec![
+                    Real(rect.x1),
+                    Real(rect.y1),
+                    Real(rect.x2),
+                    Real(rect.y2),
+                ]),
+            ),
+            ("P", Reference(page_id)),
+            ("Parent", Reference(field_id)),
+        ]));
+        let widget_id = self.raw_document.new_document.add_object(widget_dictionary);
+        let field_dictionary = Dictionary(lopdf::Dictionary::from_iter(vec![
+            ("FT", Name("Sig".as_bytes().to_vec())),
+            (
+                "T",
+                String(encoded_data.into_bytes(), StringFormat::Literal),
+            ),
+            ("TU", lopdf::text_string(alternate_name)),
+            ("Kids", Array(vec![Reference(widget_id)])),
+        ]));
+        self.raw_document
+            .new_document
+            .objects
+            .insert(field_id, field_dictionary);
+
+        // Add annotation to page
+        self.raw_document
+            .opt_clone_object_to_new_document(page_id)?;
+        self.push_to_array(page_id, b"Annots", Reference(widget_id))?;
+
+        // Add field to `AcroForm`
+        let acro_form_id = self.get_or_create_acro_form()?;
+        self.push_to_array(acro_form_id, b"Fields", Reference(field_id))?;
+        Ok(field_id)
+    }
+}
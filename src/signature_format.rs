@@ -0,0 +1,21 @@
+/// The format of the digital signature, written as `SubFilter` in the signature dictionary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// `adbe.pkcs7.detached`: Plain CMS signature including a `signing-time` attribute.
+    #[default]
+    AdbePkcs7Detached,
+    /// `ETSI.CAdES.detached`: PAdES baseline B-B signature.
+    /// The CMS contains the ESS `signing-certificate-v2` attribute and no `signing-time`,
+    /// the claimed signing time is only stored in the `M` entry.
+    EtsiCadesDetached,
+}
+
+impl SignatureFormat {
+    /// The value for the `SubFilter` entry.
+    pub fn sub_filter(&self) -> &'static str {
+        match self {
+            SignatureFormat::AdbePkcs7Detached => "adbe.pkcs7.detached",
+            SignatureFormat::EtsiCadesDetached => "ETSI.CAdES.detached",
+        }
+    }
+}
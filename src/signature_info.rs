@@ -1,9 +1,82 @@
-use crate::PDFSigningDocument;
-use crate::{error::Error, UserSignatureInfo};
-use chrono::Utc;
+use crate::acro_form::{AcroForm, FormComponent, SignBuildProperties};
+use crate::cms::CmsBuilder;
+use crate::pdf_object::PdfObjectDeref;
+use crate::{error::Error, Rectangle, UserSignatureInfo};
+use crate::{FieldLock, PDFSigningDocument};
+use chrono::{DateTime, Utc};
 use lopdf::ObjectId;
 
+/// A signed signature field, as stored in the document. Nothing is verified,
+/// use `PDFSigningDocument::verify_signatures()` for that.
+#[derive(Debug, Clone)]
+pub struct SignatureInfo {
+    /// Partial name (`T`) of the signature field.
+    pub field_name: Option<String>,
+    /// Page number of the widget, starting at 1.
+    pub page: Option<u32>,
+    /// Position of the widget on the page.
+    pub rect: Option<Rectangle>,
+    pub filter: String,
+    pub sub_filter: Option<String>,
+    /// Time of signing (`M`), as claimed by the signer.
+    pub signing_time: Option<DateTime<Utc>>,
+    /// The name of the signer, when it can not be taken from the certificate.
+    pub name: Option<String>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    pub contact_info: Option<String>,
+    /// Build properties (`Prop_Build`) of the software that signed.
+    pub build_properties: Option<SignBuildProperties>,
+    pub byte_range: Vec<(u64, u64)>,
+    /// The CMS from `Contents`, without the padding.
+    pub contents: Vec<u8>,
+}
+
 impl PDFSigningDocument {
+    /// List the signed signature fields, in the order of the form fields.
+    pub fn signatures(&self) -> Result<Vec<SignatureInfo>, Error> {
+        let raw_doc = self.raw_document.get_prev_documents();
+        let mut signatures = vec![];
+        for form in self.acro_forms()?.iter() {
+            if let FormComponent::SignedSignature {
+                filter,
+                sub_filter,
+                contents,
+                byte_range,
+                name,
+                signing_time,
+                location,
+                reason,
+                contact_info,
+                prop_build,
+                ..
+            } = form.get_form_component()
+            {
+                let widget = form.get_widgets(raw_doc)?.first().copied();
+                signatures.push(SignatureInfo {
+                    field_name: form.get_partial_field_name().map(str::to_owned),
+                    page: widget
+                        .and_then(|widget| AcroForm::get_widget_page_number(raw_doc, widget)),
+                    rect: widget
+                        .map(|widget| AcroForm::get_widget_rect(raw_doc, widget))
+                        .transpose()?
+                        .flatten(),
+                    filter: filter.clone(),
+                    sub_filter: sub_filter.clone(),
+                    signing_time: *signing_time,
+                    name: name.clone(),
+                    reason: reason.clone(),
+                    location: location.clone(),
+                    contact_info: contact_info.clone(),
+                    build_properties: prop_build.clone(),
+                    byte_range: byte_range.clone(),
+                    contents: contents[..Self::der_length(contents)].to_vec(),
+                });
+            }
+        }
+        Ok(signatures)
+    }
+
     // Change the signature to add extra info about the signing application
     pub(crate) fn add_general_info_to_signature(
         &mut self,
@@ -11,9 +84,10 @@ impl PDFSigningDocument {
         new_appearance_id: ObjectId,
         user_signature_info: &UserSignatureInfo,
         _signature_name: &str,
+        signing_time: DateTime<Utc>,
     ) -> Result<(), Error> {
         use lopdf::{Object::*, StringFormat};
-        let _root_obj_id = self
+        let root_obj_id = self
             .raw_document
             .get_prev_documents()
             .trailer
@@ -21,115 +95,111 @@ impl PDFSigningDocument {
             .as_reference()?;
 
         // Update Annot to include image
-        // Update `AP` in `Kids`
-
-        // This code assumes that the objects in Kids are References.
-        // Get list of `Kids` ObjectIDs
-        let form_dict = self
-            .raw_document
-            .get_prev_documents()
-            .get_object(signature_obj_id)?
-            .as_dict()?;
-        let kids = if form_dict.has(b"Kids") {
-            Some(form_dict.get(b"Kids")?.as_array()?)
-        } else {
-            None
-        };
-        // Convert `Option<&Vec<Object>>` to `Option<Vec<ObjectId>>`
-        let kids: Option<Vec<ObjectId>> = match kids {
-            Some(list) => {
-                let mut new_list = vec![];
-                for obj in list {
-                    new_list.push(obj.as_reference()?);
-                }
-                Some(new_list)
-            }
-            None => None,
-        };
-
-        // TODO if kids is `None` we have to create it.
-        if kids.is_none() {
-            log::error!("Unimplemented state: `Kids` entry is missing in Signature.");
+        // Update `AP` of the widgets, in `Kids` or merged into the field.
+        let widgets =
+            AcroForm::get_field_widgets(self.raw_document.get_prev_documents(), signature_obj_id)?;
+        if widgets.is_empty() {
+            log::error!("Signature field does not have a widget annotation.");
         }
-        let kids = kids.unwrap_or_default();
+        for widget_id in widgets {
+            // Copy widget to new incremental update
+            self.raw_document
+                .opt_clone_object_to_new_document(widget_id)?;
 
-        // Loop over Kids that are `Annot`.
-        let mut found_and_replace_appearance = false;
-        for child_obj_id in kids {
-            let child_dict = self
+            let widget_dict_mut = self
                 .raw_document
-                .get_prev_documents()
-                .get_object(child_obj_id)?
-                .as_dict()?;
-
-            if child_dict.get(b"Type")?.as_name_str()? == "Annot" {
-                // Copy child to new incremental update
-                self.raw_document
-                    .opt_clone_object_to_new_document(child_obj_id)?;
-
-                let child_dict_mut = self
-                    .raw_document
-                    .new_document
-                    .get_object_mut(child_obj_id)?
-                    .as_dict_mut()?;
-
-                child_dict_mut.set(
-                    "AP",
-                    lopdf::Object::Dictionary(lopdf::Dictionary::from_iter(vec![(
-                        "N",
-                        lopdf::Object::Reference(new_appearance_id),
-                    )])),
-                );
-                // TODO Set the `F` value to 132: For docs see page 385.
-                // This will `Locked` and `Print`
-                found_and_replace_appearance = true;
-            }
-        }
+                .new_document
+                .get_object_mut(widget_id)?
+                .as_dict_mut()?;
 
-        if !found_and_replace_appearance {
-            log::error!("None of the `Kids` are of type `Annot`.");
+            widget_dict_mut.set(
+                "AP",
+                lopdf::Object::Dictionary(lopdf::Dictionary::from_iter(vec![(
+                    "N",
+                    lopdf::Object::Reference(new_appearance_id),
+                )])),
+            );
+            // `F` |= 132: `Print` and `Locked`, so the signed appearance can not change.
+            let flags = widget_dict_mut
+                .get(b"F")
+                .and_then(lopdf::Object::as_i64)
+                .unwrap_or(0);
+            widget_dict_mut.set("F", Integer(flags | 132));
         }
 
         // Update `V` tag in `FT = Sig`
         self.raw_document
             .opt_clone_object_to_new_document(signature_obj_id)?;
 
-        // The `sign_dict.extend(` is bugged see: lopdf issue #120
-        // TODO: Implementing locking of file
-        // sign_dict.set(
-        //     "Lock",
-        //     Dictionary(lopdf::Dictionary::from_iter(vec![
-        //         ("Type", Name("SigFieldLock".as_bytes().to_vec())),
-        //         ("Action", Name("All".as_bytes().to_vec())),
-        //     ])),
-        // );
+        // A `Lock` that is already on the field (set by the form author) is used
+        // when the user does not lock any fields.
+        let field_lock = match &user_signature_info.field_lock {
+            Some(field_lock) => {
+                self.raw_document
+                    .new_document
+                    .get_object_mut(signature_obj_id)?
+                    .as_dict_mut()?
+                    .set("Lock", field_lock.lock_dictionary());
+                Some(field_lock.clone())
+            }
+            None => {
+                let prev_document = self.raw_document.get_prev_documents();
+                match prev_document
+                    .get_object(signature_obj_id)?
+                    .as_dict()?
+                    .get(b"Lock")
+                {
+                    Ok(lock) => Some(FieldLock::from_dictionary(
+                        lock.deref(prev_document)?.as_dict()?,
+                    )?),
+                    Err(_) => None,
+                }
+            }
+        };
+
+        if user_signature_info.certification.is_some() {
+            self.check_can_certify()?;
+        }
 
-        // Get system time in UTC
-        let now = Utc::now();
+        // Space reserved for the signature
+        let contents_size = user_signature_info.contents_size.unwrap_or_else(|| {
+            CmsBuilder::for_signature_format(
+                user_signature_info.user_signer.as_ref(),
+                user_signature_info.signature_format,
+                signing_time,
+            )
+            .timestamp_provider(user_signature_info.timestamp_provider.as_deref())
+            .estimate_size()
+        });
 
-        let v_dictionary = Dictionary(lopdf::Dictionary::from_iter(vec![
+        let mut v_dictionary = lopdf::Dictionary::from_iter(vec![
             ("Type", Name("Sig".as_bytes().to_vec())),
             ("Filter", Name("Adobe.PPKLite".as_bytes().to_vec())),
-            ("SubFilter", Name("adbe.pkcs7.detached".as_bytes().to_vec())),
+            (
+                "SubFilter",
+                Name(
+                    user_signature_info
+                        .signature_format
+                        .sub_filter()
+                        .as_bytes()
+                        .to_vec(),
+                ),
+            ),
             // The order of `ByteRange` and `Contents` is important.
             // They should not be moved or switched in ordering.
             (
                 "ByteRange", // Set default value. This will later be filled in.
-                Array(vec![
-                    Integer(0),
-                    Integer(10000), // byte of `<`
-                    Integer(20000), // Byte of char after `>`
-                    Integer(10000), // until end of file
-                ]),
+                PDFSigningDocument::byte_range_placeholder(),
             ),
             (
                 "Contents", // Will be filled in later
-                String(vec![0u8; 9000], StringFormat::Hexadecimal),
+                PDFSigningDocument::contents_placeholder(contents_size),
             ),
             (
                 "M",
                 String(
-                    now.format("D:%Y%m%d%H%M%S+00'00'")
+                    signing_time
+                        .format("D:%Y%m%d%H%M%S+00'00'")
                         .to_string()
                         .as_bytes()
                         .to_vec(),
@@ -144,7 +214,7 @@ impl PDFSigningDocument {
                 ),
             ),
             (
-                "Prob_Build",
+                "Prop_Build",
                 Dictionary(lopdf::Dictionary::from_iter(vec![
                     (
                         "Filter",
@@ -194,36 +264,17 @@ impl PDFSigningDocument {
                     ),
                 ])),
             ),
-            // (
-            //     "Reference",
-            //     Array(vec![Dictionary(lopdf::Dictionary::from_iter(vec![
-            //         ("Type", Name("SigRef".as_bytes().to_vec())),
-            //         ("TransformMethod", Name("FieldMDP".as_bytes().to_vec())), // TODO: maybe DocMDP?
-            //         (
-            //             "TransformParams",
-            //             Dictionary(lopdf::Dictionary::from_iter(vec![
-            //                 ("Type", Name("TransformParams".as_bytes().to_vec())),
-            //                 ("Action", Name("Include".as_bytes().to_vec())),
-            //                 (
-            //                     "Fields",
-            //                     Array(vec![String(
-            //                         signature_name.as_bytes().to_vec(),
-            //                         StringFormat::Literal,
-            //                     )]),
-            //                 ),
-            //                 ("V", Name("1.2".as_bytes().to_vec())),
-            //             ])),
-            //         ),
-            //         ("Data", Reference(root_obj_id)), // Signature is over whole document
-            //         ("DigestMethod", Name("SHA1".as_bytes().to_vec())),
-            //         (
-            //             "DigestValue",
-            //             String(vec![0u8; 20], StringFormat::Hexadecimal),
-            //         ), // TODO: Sha1 = 20 bytes
-            //         ("DigestLocation", Array(vec![Integer(1500), Integer(34)])), // TODO
-            //     ]))]),
-            // ),
-        ]));
+        ]);
+        let mut references = vec![];
+        if let Some(permission) = user_signature_info.certification {
+            references.push(Self::doc_mdp_reference(permission));
+        }
+        if let Some(field_lock) = &field_lock {
+            references.push(field_lock.field_mdp_reference(root_obj_id));
+        }
+        if !references.is_empty() {
+            v_dictionary.set("Reference", Array(references));
+        }
 
         // Add `V` as new object
         let v_ref = self.raw_document.new_document.add_object(v_dictionary);
@@ -236,6 +287,10 @@ impl PDFSigningDocument {
 
         sign_dict.set("V", Reference(v_ref));
 
+        if user_signature_info.certification.is_some() {
+            self.add_doc_mdp_permission(v_ref)?;
+        }
+
         Ok(())
     }
 }
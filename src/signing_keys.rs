@@ -0,0 +1,148 @@
+use crate::{Error, PdfSigner, SignatureAlgorithm};
+use ring::{rand::SystemRandom, signature as ringsig};
+use x509_certificate::{
+    CapturedX509Certificate, DigestAlgorithm, InMemorySigningKeyPair, KeyAlgorithm, Sign, Signer,
+};
+
+/// The private key and certificates used to create the digital signature.
+///
+/// `PdfSigner` for a key pair that is loaded in memory.
+pub struct SigningKeys {
+    /// The cryptographic key pair used for signing.
+    /// `None` when the signature is created elsewhere, see `prepare_signature()`.
+    signing_key: Option<InMemorySigningKeyPair>,
+    /// X.509 certificate that belongs to the `signing_key`.
+    signing_certificate: CapturedX509Certificate,
+    /// Extra certificates (intermediates, root) added to the signature.
+    certificate_chain: Vec<CapturedX509Certificate>,
+}
+
+impl SigningKeys {
+    pub fn new(
+        signing_key: InMemorySigningKeyPair,
+        signing_certificate: CapturedX509Certificate,
+    ) -> Self {
+        SigningKeys {
+            signing_key: Some(signing_key),
+            signing_certificate,
+            certificate_chain: vec![],
+        }
+    }
+
+    /// Only the certificates, without the private key.
+    /// Used for deferred signing where the key is not available.
+    pub fn from_certificate(signing_certificate: CapturedX509Certificate) -> Self {
+        SigningKeys {
+            signing_key: None,
+            signing_certificate,
+            certificate_chain: vec![],
+        }
+    }
+
+    /// Add the certificates of the issuers of the signing certificate.
+    /// These will be embedded in the signature so validators can build the chain.
+    #[must_use]
+    pub fn certificate_chain(
+        mut self,
+        certificates: impl IntoIterator<Item = CapturedX509Certificate>,
+    ) -> Self {
+        for certificate in certificates {
+            if certificate != self.signing_certificate
+                && !self.certificate_chain.contains(&certificate)
+            {
+                self.certificate_chain.push(certificate);
+            }
+        }
+        self
+    }
+
+    pub fn get_signing_certificate(&self) -> &CapturedX509Certificate {
+        &self.signing_certificate
+    }
+}
+
+impl PdfSigner for SigningKeys {
+    fn signing_certificate(&self) -> &CapturedX509Certificate {
+        &self.signing_certificate
+    }
+
+    fn certificate_chain(&self) -> &[CapturedX509Certificate] {
+        &self.certificate_chain
+    }
+
+    fn supported_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        let Some(signing_key) = &self.signing_key else {
+            return vec![];
+        };
+        let mut algorithms: Vec<SignatureAlgorithm> = signing_key
+            .signature_algorithm()
+            .ok()
+            .and_then(|algorithm| SignatureAlgorithm::try_from(algorithm).ok())
+            .into_iter()
+            .collect();
+        // Other algorithms are only available for RSA keys, ECDSA and Ed25519 are
+        // limited to the algorithm of the key pair.
+        if signing_key.key_algorithm() == Some(KeyAlgorithm::Rsa) {
+            for digest_algorithm in [
+                DigestAlgorithm::Sha256,
+                DigestAlgorithm::Sha384,
+                DigestAlgorithm::Sha512,
+            ] {
+                for algorithm in [
+                    SignatureAlgorithm::RsaPkcs1(digest_algorithm),
+                    SignatureAlgorithm::RsaPss(digest_algorithm),
+                ] {
+                    if !algorithms.contains(&algorithm) {
+                        algorithms.push(algorithm);
+                    }
+                }
+            }
+        }
+        algorithms
+    }
+
+    fn sign(&self, data: &[u8], algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or_else(|| Error::from("No signing key available."))?;
+        if SignatureAlgorithm::try_from(signing_key.signature_algorithm()?)? == algorithm {
+            let signature = signing_key
+                .try_sign(data)
+                .map_err(|err| Error::Other(format!("Could not create signature: {}", err)))?;
+            return Ok(signature.into());
+        }
+
+        let padding: &'static dyn ringsig::RsaEncoding = match algorithm {
+            SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha256) => &ringsig::RSA_PKCS1_SHA256,
+            SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha384) => &ringsig::RSA_PKCS1_SHA384,
+            SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha512) => &ringsig::RSA_PKCS1_SHA512,
+            SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha256) => &ringsig::RSA_PSS_SHA256,
+            SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha384) => &ringsig::RSA_PSS_SHA384,
+            SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha512) => &ringsig::RSA_PSS_SHA512,
+            algorithm => {
+                return Err(Error::Other(format!(
+                    "Signature algorithm `{}` is not supported by the key.",
+                    algorithm
+                )))
+            }
+        };
+        let private_key = match signing_key {
+            InMemorySigningKeyPair::Rsa(_) => signing_key.private_key_data(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "Signature algorithm `{}` is not supported by the key.",
+                algorithm
+            ))
+        })?;
+        let key_pair = ringsig::RsaKeyPair::from_der(&private_key)
+            .map_err(|err| Error::Other(format!("Invalid RSA key: {}", err)))?;
+        let mut signature = vec![0; key_pair.public().modulus_len()];
+        key_pair
+            .sign(padding, &SystemRandom::new(), data, &mut signature)
+            .map_err(|_| Error::from("Could not create signature."))?;
+        Ok(signature)
+    }
+}
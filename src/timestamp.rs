@@ -0,0 +1,175 @@
+//! RFC 3161 time-stamp tokens for signatures.
+
+use crate::cms::CmsBuilder;
+use crate::{Error, PdfSigner};
+use bcder::{encode::Values, ConstOid, Integer, Mode, OctetString, Oid};
+use chrono::{SubsecRound, Utc};
+use cryptographic_message_syntax::{
+    asn1::rfc3161::{MessageImprint, TimeStampReq, TstInfo, OID_CONTENT_TYPE_TST_INFO},
+    time_stamp_request_http, Bytes,
+};
+use std::sync::Arc;
+use x509_certificate::{asn1time::GeneralizedTime, DigestAlgorithm};
+
+/// ETSI baseline time-stamp policy (ETSI EN 319 421)
+///
+/// 0.4.0.2023.1.1
+const OID_ETSI_BASELINE_TS_POLICY: ConstOid = Oid(&[4, 0, 143, 103, 1, 1]);
+
+/// Source of RFC 3161 time-stamp tokens.
+///
+/// Used to add a `id-aa-signatureTimeStampToken` to a signature.
+pub trait TimestampProvider: Send + Sync {
+    /// Create a time-stamp token for the `message_digest` (the message imprint).
+    ///
+    /// Returns the DER encoded `TimeStampToken` (a CMS `ContentInfo` with `SignedData`).
+    fn get_timestamp_token(
+        &self,
+        message_digest: &[u8],
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Vec<u8>, Error>;
+
+    /// Expected size of the DER encoded token in bytes, used to reserve space in the PDF.
+    /// When the token is bigger the signing is retried with more space.
+    fn estimated_token_size(&self) -> usize {
+        8192
+    }
+}
+
+/// Time-stamp authority that runs in process using a local key and certificate.
+/// No network access is needed, useful for tests and air-gapped deployments.
+///
+/// The certificate should have the `timeStamping` extended key usage.
+#[derive(Clone)]
+pub struct LocalTimestampAuthority {
+    signer: Arc<dyn PdfSigner>,
+    policy: Oid,
+}
+
+impl LocalTimestampAuthority {
+    pub fn new(signer: Arc<dyn PdfSigner>) -> Self {
+        LocalTimestampAuthority {
+            signer,
+            policy: Oid(Bytes::copy_from_slice(OID_ETSI_BASELINE_TS_POLICY.as_ref())),
+        }
+    }
+
+    /// Set the TSA policy, by default the ETSI baseline time-stamp policy is used.
+    #[must_use]
+    pub fn policy(mut self, policy: Oid) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl TimestampProvider for LocalTimestampAuthority {
+    fn get_timestamp_token(
+        &self,
+        message_digest: &[u8],
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Vec<u8>, Error> {
+        let tst_info = TstInfo {
+            version: Integer::from(1u8),
+            policy: self.policy.clone(),
+            message_imprint: MessageImprint {
+                hash_algorithm: digest_algorithm.into(),
+                hashed_message: OctetString::new(Bytes::copy_from_slice(message_digest)),
+            },
+            serial_number: Integer::from(rand::random::<u64>()),
+            // Fractional seconds are not used to keep the encoding simple.
+            gen_time: GeneralizedTime::from(Utc::now().trunc_subsecs(0)),
+            accuracy: None,
+            ordering: None,
+            nonce: None,
+            tsa: None,
+            extensions: None,
+        };
+        let mut tst_info_der = Vec::new();
+        tst_info
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut tst_info_der)?;
+
+        // RFC 5816: The `signing-certificate-v2` attribute identifies the TSA certificate.
+        CmsBuilder::new(self.signer.as_ref())
+            .content_type(Oid(Bytes::copy_from_slice(
+                OID_CONTENT_TYPE_TST_INFO.as_ref(),
+            )))
+            .content_inline()
+            .signing_certificate_v2()
+            .build_der(&tst_info_der)
+    }
+
+    fn estimated_token_size(&self) -> usize {
+        // `TSTInfo` is embedded in the token.
+        CmsBuilder::new(self.signer.as_ref()).estimate_size() + 512
+    }
+}
+
+/// Time-stamp authority that is contacted over HTTP.
+///
+/// Public time servers:
+/// - <https://freetsa.org/tsr>
+/// - <http://timestamp.digicert.com>
+#[derive(Debug, Clone)]
+pub struct HttpTimestampProvider {
+    url: String,
+}
+
+impl HttpTimestampProvider {
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        HttpTimestampProvider { url: url.into() }
+    }
+}
+
+impl TimestampProvider for HttpTimestampProvider {
+    fn get_timestamp_token(
+        &self,
+        message_digest: &[u8],
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Vec<u8>, Error> {
+        let nonce = Integer::from(rand::random::<u64>());
+        let request = TimeStampReq {
+            version: Integer::from(1u8),
+            message_imprint: MessageImprint {
+                hash_algorithm: digest_algorithm.into(),
+                hashed_message: OctetString::new(Bytes::copy_from_slice(message_digest)),
+            },
+            req_policy: None,
+            nonce: Some(nonce.clone()),
+            cert_req: Some(true),
+            extensions: None,
+        };
+        let response = time_stamp_request_http(self.url.as_str(), &request)
+            .map_err(|err| Error::Other(format!("Time-stamp request failed: {}", err)))?;
+        if !response.is_success() {
+            return Err(Error::from("Time-stamp request was not granted."));
+        }
+
+        // The token has to be for this request.
+        let tst_info = response
+            .tst_info()
+            .map_err(|err| Error::Other(format!("Invalid time-stamp token: {}", err)))?
+            .ok_or_else(|| Error::from("Time-stamp response does not contain a `TSTInfo`."))?;
+        if tst_info.nonce.as_ref() != Some(&nonce) {
+            return Err(Error::from(
+                "Time-stamp response nonce does not match the request.",
+            ));
+        }
+        let imprint = &tst_info.message_imprint;
+        if DigestAlgorithm::try_from(&imprint.hash_algorithm).ok() != Some(digest_algorithm)
+            || imprint.hashed_message.to_bytes() != message_digest
+        {
+            return Err(Error::from(
+                "Time-stamp message imprint does not match the signature.",
+            ));
+        }
+
+        let token = response
+            .time_stamp_token
+            .as_ref()
+            .ok_or_else(|| Error::from("Time-stamp response does not contain a token."))?;
+        let mut token_der = Vec::new();
+        token.write_encoded(Mode::Der, &mut token_der)?;
+        Ok(token_der)
+    }
+}
@@ -0,0 +1,390 @@
+//! Validate the certificate chain of a signer against trusted certificates.
+//!
+//! Everything is checked offline: the chain is built from the certificates included in the
+//! signature and the certificates in the `TrustStore`. Revocation is checked separately, see
+//! `ValidationData::check_revocation()`.
+
+use crate::Error;
+use bcder::{decode::Constructed, BitString, ConstOid, Mode, Oid};
+use chrono::{DateTime, Utc};
+use x509_certificate::CapturedX509Certificate;
+
+/// Key usage extension (RFC 5280)
+///
+/// 2.5.29.15
+const OID_KEY_USAGE: ConstOid = Oid(&[85, 29, 15]);
+/// Basic constraints extension (RFC 5280)
+///
+/// 2.5.29.19
+const OID_BASIC_CONSTRAINTS: ConstOid = Oid(&[85, 29, 19]);
+/// Extended key usage extension (RFC 5280)
+///
+/// 2.5.29.37
+const OID_EXTENDED_KEY_USAGE: ConstOid = Oid(&[85, 29, 37]);
+/// Any extended key usage (RFC 5280)
+///
+/// 2.5.29.37.0
+const OID_ANY_EXTENDED_KEY_USAGE: ConstOid = Oid(&[85, 29, 37, 0]);
+/// Email protection, used by most document signing certificates (RFC 5280)
+///
+/// 1.3.6.1.5.5.7.3.4
+const OID_EMAIL_PROTECTION: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 3, 4]);
+/// Time stamping (RFC 5280)
+///
+/// 1.3.6.1.5.5.7.3.8
+const OID_TIME_STAMPING: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 3, 8]);
+/// Document signing (RFC 9336)
+///
+/// 1.3.6.1.5.5.7.3.36
+const OID_DOCUMENT_SIGNING: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 3, 36]);
+/// Adobe Authentic Documents Trust (`id-kp-adobeAuthenticDocumentsTrust`)
+///
+/// 1.2.840.113583.1.1.5
+const OID_ADOBE_AUTHENTIC_DOCUMENTS: ConstOid = Oid(&[42, 134, 72, 134, 247, 47, 1, 1, 5]);
+/// Microsoft document signing
+///
+/// 1.3.6.1.4.1.311.10.3.12
+const OID_MS_DOCUMENT_SIGNING: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 10, 3, 12]);
+
+/// Key usage bits (RFC 5280 4.2.1.3)
+const KEY_USAGE_DIGITAL_SIGNATURE: usize = 0;
+const KEY_USAGE_NON_REPUDIATION: usize = 1;
+const KEY_USAGE_KEY_CERT_SIGN: usize = 5;
+
+/// Longest chain that is searched, including the signer and the trust anchor.
+const MAX_CHAIN_LENGTH: usize = 10;
+
+/// Trusted root certificates and extra intermediate certificates used to build chains.
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    /// Trust anchors, a chain that ends in one of these is trusted.
+    pub anchors: Vec<CapturedX509Certificate>,
+    /// Certificates that can be used to build a chain, but are not trusted themselves.
+    pub intermediates: Vec<CapturedX509Certificate>,
+}
+
+/// What the signer certificate is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPurpose {
+    /// Signing documents, the `keyUsage` has to allow `digitalSignature` or `nonRepudiation`.
+    DocumentSigning,
+    /// Time-stamp tokens, the `extendedKeyUsage` has to contain `timeStamping`.
+    TimeStamping,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustStatus {
+    /// The chain ends in a trust anchor and all certificates are valid at the validation time.
+    Trusted,
+    /// The chain does not end in a trust anchor, or a certificate is not allowed to be used
+    /// this way.
+    Untrusted,
+    /// The chain is trusted, but a certificate is expired (or not yet valid) at the validation
+    /// time.
+    Expired,
+}
+
+/// A problem found in the chain, with the subject of the certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainProblem {
+    /// No chain to one of the trust anchors was found.
+    NoTrustAnchor,
+    NotYetValid(String),
+    Expired(String),
+    /// An issuer is not a CA (`basicConstraints`).
+    NotCa(String),
+    /// An issuer has more CAs below it than its `pathLenConstraint` allows.
+    PathLengthExceeded(String),
+    /// The `keyUsage` does not allow the certificate to be used this way.
+    KeyUsage(String),
+    /// The `extendedKeyUsage` does not allow the certificate to be used this way.
+    ExtendedKeyUsage(String),
+}
+
+impl ChainProblem {
+    fn is_time_problem(&self) -> bool {
+        matches!(
+            self,
+            ChainProblem::NotYetValid(_) | ChainProblem::Expired(_)
+        )
+    }
+}
+
+/// The result of validating the chain of a certificate.
+#[derive(Debug, Clone)]
+pub struct ChainValidation {
+    pub status: TrustStatus,
+    /// The chain from the certificate to the trust anchor, or the longest chain found when
+    /// there is no trust anchor.
+    pub chain: Vec<CapturedX509Certificate>,
+    /// The time the validity periods were checked at.
+    pub validation_time: DateTime<Utc>,
+    /// All problems found, empty when the chain is trusted.
+    pub problems: Vec<ChainProblem>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a trust anchor, duplicates are ignored.
+    pub fn add_anchor(&mut self, certificate: CapturedX509Certificate) {
+        if !self.anchors.contains(&certificate) {
+            self.anchors.push(certificate);
+        }
+    }
+
+    /// Add an intermediate certificate, duplicates are ignored.
+    pub fn add_intermediate(&mut self, certificate: CapturedX509Certificate) {
+        if !self.intermediates.contains(&certificate) {
+            self.intermediates.push(certificate);
+        }
+    }
+
+    /// Add all certificates in the PEM data as trust anchors.
+    pub fn add_anchors_pem(&mut self, data: impl AsRef<[u8]>) -> Result<(), Error> {
+        for certificate in CapturedX509Certificate::from_pem_multiple(data)? {
+            self.add_anchor(certificate);
+        }
+        Ok(())
+    }
+
+    /// Add a DER encoded certificate as trust anchor.
+    pub fn add_anchor_der(&mut self, data: impl Into<Vec<u8>>) -> Result<(), Error> {
+        self.add_anchor(CapturedX509Certificate::from_der(data)?);
+        Ok(())
+    }
+
+    /// Add all certificates in the PEM data as intermediates.
+    pub fn add_intermediates_pem(&mut self, data: impl AsRef<[u8]>) -> Result<(), Error> {
+        for certificate in CapturedX509Certificate::from_pem_multiple(data)? {
+            self.add_intermediate(certificate);
+        }
+        Ok(())
+    }
+
+    /// Add a DER encoded certificate as intermediate.
+    pub fn add_intermediate_der(&mut self, data: impl Into<Vec<u8>>) -> Result<(), Error> {
+        self.add_intermediate(CapturedX509Certificate::from_der(data)?);
+        Ok(())
+    }
+
+    /// Build and check the chain of `certificate`.
+    ///
+    /// `certificates` are extra (untrusted) certificates to build the chain with, for example
+    /// the certificates included in the signature.
+    pub fn validate(
+        &self,
+        certificate: &CapturedX509Certificate,
+        certificates: &[CapturedX509Certificate],
+        validation_time: DateTime<Utc>,
+        purpose: KeyPurpose,
+    ) -> ChainValidation {
+        let mut chain = vec![certificate.clone()];
+        let mut longest_chain = chain.clone();
+        let anchored = self.build_chain(&mut chain, certificates, &mut longest_chain);
+        if !anchored {
+            chain = longest_chain;
+        }
+
+        let mut problems = vec![];
+        if !anchored {
+            problems.push(ChainProblem::NoTrustAnchor);
+        }
+        for (index, certificate) in chain.iter().enumerate() {
+            let subject = Self::display_name(certificate);
+            if validation_time < certificate.validity_not_before() {
+                problems.push(ChainProblem::NotYetValid(subject.clone()));
+            } else if validation_time > certificate.validity_not_after() {
+                problems.push(ChainProblem::Expired(subject.clone()));
+            }
+            // The constraints of the trust anchor itself are not checked (RFC 5280 6.1).
+            if anchored && index == chain.len() - 1 && index != 0 {
+                continue;
+            }
+            if index == 0 {
+                problems.extend(Self::check_end_entity(certificate, &subject, purpose));
+            } else {
+                problems.extend(Self::check_issuer(certificate, &subject, index - 1));
+            }
+        }
+
+        let status = if problems.iter().any(|problem| !problem.is_time_problem()) {
+            TrustStatus::Untrusted
+        } else if !problems.is_empty() {
+            TrustStatus::Expired
+        } else {
+            TrustStatus::Trusted
+        };
+        ChainValidation {
+            status,
+            chain,
+            validation_time,
+            problems,
+        }
+    }
+
+    /// Extend `chain` with issuers until a trust anchor is reached, returns `false` when no
+    /// trust anchor can be reached. The longest chain tried is kept in `longest_chain`.
+    fn build_chain(
+        &self,
+        chain: &mut Vec<CapturedX509Certificate>,
+        certificates: &[CapturedX509Certificate],
+        longest_chain: &mut Vec<CapturedX509Certificate>,
+    ) -> bool {
+        let Some(certificate) = chain.last().cloned() else {
+            return false;
+        };
+        if self.anchors.contains(&certificate) {
+            return true;
+        }
+        if chain.len() > longest_chain.len() {
+            *longest_chain = chain.clone();
+        }
+        if chain.len() >= MAX_CHAIN_LENGTH {
+            return false;
+        }
+        // Trust anchors are tried first, so the shortest trusted chain is found.
+        let candidates = self
+            .anchors
+            .iter()
+            .chain(&self.intermediates)
+            .chain(certificates)
+            .filter(|issuer| issuer.subject_name() == certificate.issuer_name())
+            .filter(|issuer| !chain.contains(issuer))
+            .filter(|issuer| certificate.verify_signed_by_certificate(issuer).is_ok())
+            .cloned()
+            .collect::<Vec<_>>();
+        for issuer in candidates {
+            chain.push(issuer);
+            if self.build_chain(chain, certificates, longest_chain) {
+                return true;
+            }
+            chain.pop();
+        }
+        false
+    }
+
+    /// Check the signer certificate can be used for `purpose`.
+    fn check_end_entity(
+        certificate: &CapturedX509Certificate,
+        subject: &str,
+        purpose: KeyPurpose,
+    ) -> Vec<ChainProblem> {
+        let mut problems = vec![];
+        if Self::key_usage_allows(
+            certificate,
+            &[KEY_USAGE_DIGITAL_SIGNATURE, KEY_USAGE_NON_REPUDIATION],
+        ) == Some(false)
+        {
+            problems.push(ChainProblem::KeyUsage(subject.to_owned()));
+        }
+        let extended_key_usage = Self::extended_key_usage(certificate);
+        let allowed = match (&extended_key_usage, purpose) {
+            // Without the extension all purposes are allowed.
+            (None, KeyPurpose::DocumentSigning) => true,
+            // A time-stamping certificate is required to have the extension (RFC 3161 2.3).
+            (None, KeyPurpose::TimeStamping) => false,
+            (Some(key_purposes), KeyPurpose::DocumentSigning) => {
+                key_purposes.iter().any(|key_purpose| {
+                    key_purpose == &OID_ANY_EXTENDED_KEY_USAGE
+                        || key_purpose == &OID_EMAIL_PROTECTION
+                        || key_purpose == &OID_DOCUMENT_SIGNING
+                        || key_purpose == &OID_ADOBE_AUTHENTIC_DOCUMENTS
+                        || key_purpose == &OID_MS_DOCUMENT_SIGNING
+                })
+            }
+            (Some(key_purposes), KeyPurpose::TimeStamping) => key_purposes
+                .iter()
+                .any(|key_purpose| key_purpose == &OID_TIME_STAMPING),
+        };
+        if !allowed {
+            problems.push(ChainProblem::ExtendedKeyUsage(subject.to_owned()));
+        }
+        problems
+    }
+
+    /// Check the issuer is a CA that can sign certificates,
+    /// `intermediates_below` is the number of CA certificates between it and the signer.
+    fn check_issuer(
+        certificate: &CapturedX509Certificate,
+        subject: &str,
+        intermediates_below: usize,
+    ) -> Vec<ChainProblem> {
+        let mut problems = vec![];
+        match Self::basic_constraints(certificate) {
+            Some((true, path_length)) => {
+                if path_length.is_some_and(|path_length| intermediates_below > path_length) {
+                    problems.push(ChainProblem::PathLengthExceeded(subject.to_owned()));
+                }
+            }
+            _ => problems.push(ChainProblem::NotCa(subject.to_owned())),
+        }
+        if Self::key_usage_allows(certificate, &[KEY_USAGE_KEY_CERT_SIGN]) == Some(false) {
+            problems.push(ChainProblem::KeyUsage(subject.to_owned()));
+        }
+        problems
+    }
+
+    /// The DER encoded value of the extension.
+    fn extension(certificate: &CapturedX509Certificate, oid: ConstOid) -> Option<Vec<u8>> {
+        certificate
+            .iter_extensions()
+            .find(|extension| extension.id == oid)
+            .map(|extension| extension.value.to_bytes().to_vec())
+    }
+
+    /// `cA` and `pathLenConstraint`, `None` when the extension is missing or not valid.
+    fn basic_constraints(certificate: &CapturedX509Certificate) -> Option<(bool, Option<usize>)> {
+        let value = Self::extension(certificate, OID_BASIC_CONSTRAINTS)?;
+        Constructed::decode(value.as_slice(), Mode::Der, |cons| {
+            cons.take_sequence(|cons| {
+                let ca = cons.take_opt_bool()?.unwrap_or(false);
+                let path_length = cons.take_opt_u64()?;
+                Ok((ca, path_length))
+            })
+        })
+        .ok()
+        .map(|(ca, path_length)| {
+            (
+                ca,
+                path_length.map(|path_length| usize::try_from(path_length).unwrap_or(usize::MAX)),
+            )
+        })
+    }
+
+    /// If the `keyUsage` has one of the `bits` set, `None` when the extension is missing.
+    fn key_usage_allows(certificate: &CapturedX509Certificate, bits: &[usize]) -> Option<bool> {
+        let value = Self::extension(certificate, OID_KEY_USAGE)?;
+        Some(
+            Constructed::decode(value.as_slice(), Mode::Der, BitString::take_from)
+                .is_ok_and(|key_usage| bits.iter().any(|bit| key_usage.bit(*bit))),
+        )
+    }
+
+    /// `None` when the extension is missing, an invalid extension allows nothing.
+    pub(crate) fn extended_key_usage(certificate: &CapturedX509Certificate) -> Option<Vec<Oid>> {
+        let value = Self::extension(certificate, OID_EXTENDED_KEY_USAGE)?;
+        Some(
+            Constructed::decode(value.as_slice(), Mode::Der, |cons| {
+                cons.take_sequence(|cons| {
+                    let mut key_purposes = vec![];
+                    while let Some(key_purpose) = Oid::take_opt_from(cons)? {
+                        key_purposes.push(key_purpose);
+                    }
+                    Ok(key_purposes)
+                })
+            })
+            .unwrap_or_default(),
+        )
+    }
+
+    /// Common name of the subject, or the whole subject when there is no common name.
+    fn display_name(certificate: &CapturedX509Certificate) -> String {
+        certificate
+            .subject_common_name()
+            .or_else(|| certificate.subject_name().user_friendly_str().ok())
+            .unwrap_or_default()
+    }
+}
@@ -1,14 +1,96 @@
-use cryptographic_message_syntax::SignerBuilder;
+use crate::{
+    DocMdpPermission, FieldLock, PdfSigner, SignatureAlgorithm, SignatureFormat, TimestampProvider,
+};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// The info provided to PDF service when a document needs to be signed.
 #[derive(Clone)]
-pub struct UserSignatureInfo<'a> {
+pub struct UserSignatureInfo {
     pub user_id: String,
     pub user_name: String,
     pub user_email: String,
     pub user_signature: Vec<u8>,
-    pub user_signing_keys: SignerBuilder<'a>,
+    /// Creates the signature, see `SigningKeys` for keys in memory.
+    pub user_signer: Arc<dyn PdfSigner>,
+    /// Format of the digital signature, `adbe.pkcs7.detached` by default.
+    pub signature_format: SignatureFormat,
+    /// Signature scheme and digest algorithm, must be one of the `supported_algorithms()`
+    /// of the `user_signer`. When `None` the preferred algorithm of the signer is used.
+    pub signature_algorithm: Option<SignatureAlgorithm>,
+    /// When set, a time-stamp token over the signature value is added to the signature.
+    pub timestamp_provider: Option<Arc<dyn TimestampProvider>>,
+    /// Number of bytes to reserve for the signature in the PDF.
+    /// When `None` the size is estimated from the certificates and time-stamp provider.
+    pub contents_size: Option<usize>,
+    /// When set, the signature is a certification signature (`DocMDP`) that only allows
+    /// these changes afterwards. Only the first signature of a document can certify it.
+    pub certification: Option<DocMdpPermission>,
+    /// Form fields that can not be changed after this signature (`FieldMDP`).
+    /// When `None`, the `Lock` of the signature field is used if it has one.
+    pub field_lock: Option<FieldLock>,
+}
+
+impl UserSignatureInfo {
+    /// An `adbe.pkcs7.detached` signature with the preferred algorithm of the `user_signer`,
+    /// the size of the signature is estimated. Use the other methods to change the defaults.
+    pub fn new(
+        user_id: impl Into<String>,
+        user_name: impl Into<String>,
+        user_email: impl Into<String>,
+        user_signature: Vec<u8>,
+        user_signer: Arc<dyn PdfSigner>,
+    ) -> Self {
+        UserSignatureInfo {
+            user_id: user_id.into(),
+            user_name: user_name.into(),
+            user_email: user_email.into(),
+            user_signature,
+            user_signer,
+            signature_format: SignatureFormat::AdbePkcs7Detached,
+            signature_algorithm: None,
+            timestamp_provider: None,
+            contents_size: None,
+            certification: None,
+            field_lock: None,
+        }
+    }
+
+    #[must_use]
+    pub fn signature_format(mut self, signature_format: SignatureFormat) -> Self {
+        self.signature_format = signature_format;
+        self
+    }
+
+    #[must_use]
+    pub fn signature_algorithm(mut self, signature_algorithm: SignatureAlgorithm) -> Self {
+        self.signature_algorithm = Some(signature_algorithm);
+        self
+    }
+
+    #[must_use]
+    pub fn timestamp_provider(mut self, timestamp_provider: Arc<dyn TimestampProvider>) -> Self {
+        self.timestamp_provider = Some(timestamp_provider);
+        self
+    }
+
+    #[must_use]
+    pub fn contents_size(mut self, contents_size: usize) -> Self {
+        self.contents_size = Some(contents_size);
+        self
+    }
+
+    #[must_use]
+    pub fn certification(mut self, certification: DocMdpPermission) -> Self {
+        self.certification = Some(certification);
+        self
+    }
+
+    #[must_use]
+    pub fn field_lock(mut self, field_lock: FieldLock) -> Self {
+        self.field_lock = Some(field_lock);
+        self
+    }
 }
 
 /// The info inside the PDF form signature.
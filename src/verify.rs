@@ -0,0 +1,676 @@
+//! Verify the signatures in a document.
+//!
+//! For every signed signature field:
+//! - The digest of the content covered by the `ByteRange` is calculated.
+//! - `Contents` is parsed as CMS `SignedData`, the `message-digest` attribute has to match.
+//! - The signature of the signer is checked with the public key of the signer certificate.
+//! - The changes made after the signature are checked, see `ModificationReport`.
+//! - Time-stamp tokens are checked: the `signature-time-stamp` attribute against the
+//!   signature value and document time-stamps against the `ByteRange`.
+//!
+//! The signer certificate is only reported, use `validate_chain()` to check it against a
+//! `TrustStore` and `check_revocation()` to check the embedded revocation data.
+
+use crate::acro_form::FormComponent;
+use crate::revocation::OID_ADBE_REVOCATION_INFO_ARCHIVAL;
+use crate::{
+    CertificateRevocation, ChainValidation, Error, KeyPurpose, ModificationReport,
+    PDFSigningDocument, SignatureAlgorithm, TrustStore, ValidationData,
+};
+use bcder::{decode::Constructed, ConstOid, Mode, OctetString, Oid};
+use chrono::{DateTime, Utc};
+use cryptographic_message_syntax::asn1::{
+    rfc3161::{TstInfo, OID_CONTENT_TYPE_TST_INFO, OID_TIME_STAMP_TOKEN},
+    rfc5652::{
+        CertificateChoices, SignedData, SignerIdentifier, SignerInfo, Time, OID_MESSAGE_DIGEST,
+        OID_SIGNING_TIME,
+    },
+};
+use lopdf::{Document, ObjectId};
+use std::ops::Deref;
+use x509_certificate::{CapturedX509Certificate, DigestAlgorithm, X509Certificate};
+
+/// Subject key identifier extension (RFC 5280)
+///
+/// 2.5.29.14
+const OID_SUBJECT_KEY_IDENTIFIER: ConstOid = Oid(&[85, 29, 14]);
+
+/// The result of verifying one signed signature field.
+#[derive(Debug, Clone)]
+pub struct SignatureVerification {
+    /// Partial name (`T`) of the signature field.
+    pub field_name: Option<String>,
+    pub sub_filter: Option<String>,
+    /// Offset and length of each range of the file covered by the signature.
+    pub byte_range: Vec<(u64, u64)>,
+    /// Digest algorithm of the signer, used for the signed content.
+    pub digest_algorithm: Option<DigestAlgorithm>,
+    pub signature_algorithm: Option<SignatureAlgorithm>,
+    /// The certificate that matches the signer of the CMS, `None` when it is not included.
+    pub signer_certificate: Option<CapturedX509Certificate>,
+    /// All certificates included in the CMS.
+    pub certificates: Vec<CapturedX509Certificate>,
+    /// The `signing-time` signed attribute, claimed by the signer.
+    pub signing_time: Option<DateTime<Utc>>,
+    /// CRLs and OCSP responses in the `adbe-revocationInfoArchival` signed attribute.
+    pub revocation_info: ValidationData,
+    /// The time-stamp tokens in the `signature-time-stamp` attributes,
+    /// or the token of a document time-stamp.
+    pub timestamps: Vec<TimestampVerification>,
+    pub status: SignatureStatus,
+    /// The changes made after the signature, `None` when the signed revision could not be read.
+    pub modifications: Option<ModificationReport>,
+}
+
+impl SignatureVerification {
+    /// The signed content is not changed, the signature is made by the signer certificate and
+    /// all changes made after the signature are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.status == SignatureStatus::Valid
+            && self
+                .modifications
+                .as_ref()
+                .is_some_and(ModificationReport::is_allowed)
+    }
+
+    /// The earliest `genTime` of the valid time-stamps. Unlike `signing_time` this time is
+    /// not claimed by the signer, check the chain of the time-stamping authority as well.
+    pub fn timestamp_time(&self) -> Option<DateTime<Utc>> {
+        self.timestamps
+            .iter()
+            .filter(|timestamp| timestamp.is_valid())
+            .filter_map(|timestamp| timestamp.gen_time)
+            .min()
+    }
+
+    /// Check the chain of the signer certificate against the `trust_store`,
+    /// `None` when the signer certificate was not found.
+    ///
+    /// The validity periods are checked at `validation_time`, or else at the time-stamp time,
+    /// or else at the signing time, or else now. The signer of a document time-stamp has to be
+    /// a time-stamping authority.
+    pub fn validate_chain(
+        &self,
+        trust_store: &TrustStore,
+        validation_time: Option<DateTime<Utc>>,
+    ) -> Option<ChainValidation> {
+        let purpose = match self.sub_filter.as_deref() {
+            Some("ETSI.RFC3161") => KeyPurpose::TimeStamping,
+            _ => KeyPurpose::DocumentSigning,
+        };
+        Some(
+            trust_store.validate(
+                self.signer_certificate.as_ref()?,
+                &self.certificates,
+                validation_time
+                    .or(self.timestamp_time())
+                    .or(self.signing_time)
+                    .unwrap_or_else(Utc::now),
+                purpose,
+            ),
+        )
+    }
+
+    /// Check if the certificates in the `chain` were revoked at the validation time of the
+    /// chain, using the `revocation_info` of the signature and the `document_security_store`.
+    ///
+    /// See `PDFSigningDocument::document_security_store()`.
+    pub fn check_revocation(
+        &self,
+        chain: &ChainValidation,
+        document_security_store: &ValidationData,
+    ) -> Vec<CertificateRevocation> {
+        let mut validation_data = self.revocation_info.clone();
+        validation_data.merge(document_security_store);
+        validation_data.check_revocation(chain)
+    }
+}
+
+/// Where a time-stamp token was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampKind {
+    /// `signature-time-stamp` unsigned attribute, the imprint is the digest of the signature.
+    Signature,
+    /// Document time-stamp (`ETSI.RFC3161`), the imprint is the digest of the `ByteRange`.
+    Document,
+}
+
+/// The result of verifying one time-stamp token.
+#[derive(Debug, Clone)]
+pub struct TimestampVerification {
+    pub kind: TimestampKind,
+    /// The time the token was created (`genTime`).
+    pub gen_time: Option<DateTime<Utc>>,
+    /// Digest algorithm of the message imprint.
+    pub imprint_algorithm: Option<DigestAlgorithm>,
+    /// The certificate of the time-stamping authority, `None` when it is not included.
+    pub tsa_certificate: Option<CapturedX509Certificate>,
+    /// All certificates included in the token.
+    pub certificates: Vec<CapturedX509Certificate>,
+    /// `DigestMismatch` when the message imprint does not match.
+    pub status: SignatureStatus,
+}
+
+impl TimestampVerification {
+    pub fn is_valid(&self) -> bool {
+        self.status == SignatureStatus::Valid
+    }
+
+    /// Check the chain of the time-stamping authority against the `trust_store`, at
+    /// `validation_time` or else at `gen_time`. `None` when the certificate was not found.
+    pub fn validate_chain(
+        &self,
+        trust_store: &TrustStore,
+        validation_time: Option<DateTime<Utc>>,
+    ) -> Option<ChainValidation> {
+        Some(trust_store.validate(
+            self.tsa_certificate.as_ref()?,
+            &self.certificates,
+            validation_time.or(self.gen_time).unwrap_or_else(Utc::now),
+            KeyPurpose::TimeStamping,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// The digest and signature are correct.
+    Valid,
+    /// The content covered by the `ByteRange` does not match the signed digest.
+    DigestMismatch,
+    /// The signature does not match the public key of the signer certificate.
+    InvalidSignature,
+    /// The signature could not be checked, for example because `Contents` is not a valid CMS.
+    Error(String),
+}
+
+impl PDFSigningDocument {
+    /// Verify all signed signature fields, returns one result per field.
+    ///
+    /// Document time-stamps (`ETSI.RFC3161`) are included, their message imprint is checked
+    /// against the `ByteRange`.
+    pub fn verify_signatures(&self) -> Result<Vec<SignatureVerification>, Error> {
+        let acro_forms = self.acro_forms()?;
+        let pdf_file_data = self.raw_document.get_prev_documents_bytes();
+        // The current revision, read again to get the cross-reference table as written.
+        let document = match Document::load_mem(pdf_file_data) {
+            Ok(document) => Some(document),
+            Err(err) => {
+                log::warn!(
+                    "Document could not be read to check modifications: {:?}",
+                    err
+                );
+                None
+            }
+        };
+
+        Ok(acro_forms
+            .iter()
+            .filter_map(|form| match form.get_form_component() {
+                FormComponent::SignedSignature {
+                    sub_filter,
+                    contents,
+                    byte_range,
+                    ..
+                } => {
+                    let mut verification = SignatureVerification {
+                        field_name: form.get_partial_field_name().map(str::to_owned),
+                        sub_filter: sub_filter.clone(),
+                        byte_range: byte_range.clone(),
+                        digest_algorithm: None,
+                        signature_algorithm: None,
+                        signer_certificate: None,
+                        certificates: vec![],
+                        signing_time: None,
+                        revocation_info: ValidationData::default(),
+                        timestamps: vec![],
+                        status: SignatureStatus::Valid,
+                        modifications: None,
+                    };
+                    verification.status =
+                        Self::verify_signature(&mut verification, pdf_file_data, contents)
+                            .unwrap_or_else(|err| SignatureStatus::Error(format!("{:?}", err)));
+                    verification.modifications = document.as_ref().and_then(|document| {
+                        Self::modification_report(
+                            pdf_file_data,
+                            document,
+                            byte_range,
+                            form.get_object_id(),
+                        )
+                        .map_err(|err| {
+                            log::warn!("Modifications could not be checked: {:?}", err);
+                        })
+                        .ok()
+                    });
+                    Some(verification)
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Check the signature in `contents`, the details found are set in `verification`.
+    fn verify_signature(
+        verification: &mut SignatureVerification,
+        pdf_file_data: &[u8],
+        contents: &[u8],
+    ) -> Result<SignatureStatus, Error> {
+        let signed_ranges = Self::signed_ranges(pdf_file_data, &verification.byte_range)?;
+
+        // `Contents` is padded with zeros.
+        let contents = &contents[..Self::der_length(contents)];
+        if verification.sub_filter.as_deref() == Some("ETSI.RFC3161") {
+            verification.timestamps.push(Self::verify_timestamp_token(
+                TimestampKind::Document,
+                contents,
+                &[],
+                |imprint_algorithm| Self::ranges_digest(&signed_ranges, imprint_algorithm),
+            ));
+        }
+        let signed_data = SignedData::decode_ber(contents)
+            .map_err(|err| Error::Other(format!("Signature is not a valid CMS: {}", err)))?;
+        verification.certificates = Self::cms_certificates(&signed_data)?;
+        let signer_info = match signed_data.signer_infos.deref().as_slice() {
+            [signer_info] => signer_info,
+            _ => return Err("Signature does not contain exactly one signer.".into()),
+        };
+        let digest_algorithm = DigestAlgorithm::try_from(&signer_info.digest_algorithm)?;
+        verification.digest_algorithm = Some(digest_algorithm);
+        let signature_algorithm = SignatureAlgorithm::from_algorithm_identifier(
+            &signer_info.signature_algorithm,
+            digest_algorithm,
+        )?;
+        verification.signature_algorithm = Some(signature_algorithm);
+        verification.signing_time = Self::signing_time(signer_info);
+        verification.revocation_info = Self::revocation_info(signer_info)?;
+        verification.timestamps.extend(Self::signature_timestamps(
+            signer_info,
+            &verification.certificates,
+        ));
+        let signer_certificate =
+            Self::find_signer_certificate(&verification.certificates, signer_info).ok_or_else(
+                || Error::from("Signer certificate is not included in the signature."),
+            )?;
+        verification.signer_certificate = Some(signer_certificate.clone());
+
+        // The signed content is the `ByteRange`, unless the content is encapsulated.
+        let encapsulated_content = signed_data
+            .content_info
+            .content
+            .as_ref()
+            .map(|content| content.to_bytes().to_vec());
+        if let Some(encapsulated_content) = &encapsulated_content {
+            if !Self::encapsulated_content_matches(
+                verification.sub_filter.as_deref(),
+                encapsulated_content,
+                &signed_ranges,
+            )? {
+                return Ok(SignatureStatus::DigestMismatch);
+            }
+        }
+
+        Self::check_signer(
+            signer_info,
+            signer_certificate,
+            signature_algorithm,
+            || match &encapsulated_content {
+                Some(encapsulated_content) => digest_algorithm.digest_data(encapsulated_content),
+                None => Self::ranges_digest(&signed_ranges, digest_algorithm),
+            },
+            || {
+                encapsulated_content
+                    .clone()
+                    .unwrap_or_else(|| signed_ranges.concat())
+            },
+        )
+    }
+
+    /// Check the `message-digest` attribute against `content_digest` and the signature over
+    /// the signed attributes. Without signed attributes the `content` is signed directly.
+    fn check_signer(
+        signer_info: &SignerInfo,
+        signer_certificate: &CapturedX509Certificate,
+        signature_algorithm: SignatureAlgorithm,
+        content_digest: impl FnOnce() -> Vec<u8>,
+        content: impl FnOnce() -> Vec<u8>,
+    ) -> Result<SignatureStatus, Error> {
+        let signed_data = match signer_info.signed_attributes_digested_content()? {
+            Some(signed_attributes) => {
+                if Self::message_digest(signer_info)? != content_digest() {
+                    return Ok(SignatureStatus::DigestMismatch);
+                }
+                signed_attributes
+            }
+            None => content(),
+        };
+
+        let key_algorithm = signer_certificate
+            .key_algorithm()
+            .ok_or_else(|| Error::from("Key algorithm of the signer is not supported."))?;
+        let verification_algorithm = signature_algorithm.verification_algorithm(key_algorithm)?;
+        match signer_certificate.verify_signed_data_with_algorithm(
+            signed_data,
+            signer_info.signature.to_bytes(),
+            verification_algorithm,
+        ) {
+            Ok(()) => Ok(SignatureStatus::Valid),
+            Err(_) => Ok(SignatureStatus::InvalidSignature),
+        }
+    }
+
+    /// Verify the `signature-time-stamp` attributes, the tokens are over the signature value.
+    fn signature_timestamps(
+        signer_info: &SignerInfo,
+        certificates: &[CapturedX509Certificate],
+    ) -> Vec<TimestampVerification> {
+        let signature = signer_info.signature.to_bytes();
+        signer_info
+            .unsigned_attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .filter(|attribute| attribute.typ == OID_TIME_STAMP_TOKEN)
+            .flat_map(|attribute| attribute.values.iter())
+            .map(|token| {
+                Self::verify_timestamp_token(
+                    TimestampKind::Signature,
+                    token.deref().as_slice(),
+                    certificates,
+                    |imprint_algorithm| imprint_algorithm.digest_data(&signature),
+                )
+            })
+            .collect()
+    }
+
+    /// Verify a time-stamp token, `message_digest` calculates the expected message imprint.
+    /// The TSA certificate can also be one of `other_certificates`.
+    fn verify_timestamp_token(
+        kind: TimestampKind,
+        token: &[u8],
+        other_certificates: &[CapturedX509Certificate],
+        message_digest: impl FnOnce(DigestAlgorithm) -> Vec<u8>,
+    ) -> TimestampVerification {
+        let mut timestamp = TimestampVerification {
+            kind,
+            gen_time: None,
+            imprint_algorithm: None,
+            tsa_certificate: None,
+            certificates: vec![],
+            status: SignatureStatus::Valid,
+        };
+        timestamp.status =
+            Self::check_timestamp_token(&mut timestamp, token, other_certificates, message_digest)
+                .unwrap_or_else(|err| SignatureStatus::Error(format!("{:?}", err)));
+        timestamp
+    }
+
+    /// Check the token, the details found are set in `timestamp`.
+    fn check_timestamp_token(
+        timestamp: &mut TimestampVerification,
+        token: &[u8],
+        other_certificates: &[CapturedX509Certificate],
+        message_digest: impl FnOnce(DigestAlgorithm) -> Vec<u8>,
+    ) -> Result<SignatureStatus, Error> {
+        let signed_data = SignedData::decode_ber(token)
+            .map_err(|err| Error::Other(format!("Time-stamp token is not a valid CMS: {}", err)))?;
+        timestamp.certificates = Self::cms_certificates(&signed_data)?;
+        let signer_info = match signed_data.signer_infos.deref().as_slice() {
+            [signer_info] => signer_info,
+            _ => return Err("Time-stamp token does not contain exactly one signer.".into()),
+        };
+        let content = match &signed_data.content_info.content {
+            Some(content) if signed_data.content_info.content_type == OID_CONTENT_TYPE_TST_INFO => {
+                content.to_bytes()
+            }
+            _ => return Err("Time-stamp token does not contain a `TSTInfo`.".into()),
+        };
+        let tst_info = Self::tst_info(&content)?;
+        timestamp.gen_time = Some(DateTime::from(tst_info.gen_time.clone()));
+        let imprint_algorithm =
+            DigestAlgorithm::try_from(&tst_info.message_imprint.hash_algorithm)?;
+        timestamp.imprint_algorithm = Some(imprint_algorithm);
+
+        let digest_algorithm = DigestAlgorithm::try_from(&signer_info.digest_algorithm)?;
+        let signature_algorithm = SignatureAlgorithm::from_algorithm_identifier(
+            &signer_info.signature_algorithm,
+            digest_algorithm,
+        )?;
+        let certificates: Vec<CapturedX509Certificate> = timestamp
+            .certificates
+            .iter()
+            .chain(other_certificates)
+            .cloned()
+            .collect();
+        let tsa_certificate = Self::find_signer_certificate(&certificates, signer_info)
+            .ok_or_else(|| Error::from("TSA certificate is not included in the time-stamp."))?;
+        timestamp.tsa_certificate = Some(tsa_certificate.clone());
+
+        if tst_info.message_imprint.hashed_message.to_bytes() != message_digest(imprint_algorithm) {
+            return Ok(SignatureStatus::DigestMismatch);
+        }
+        Self::check_signer(
+            signer_info,
+            tsa_certificate,
+            signature_algorithm,
+            || digest_algorithm.digest_data(&content),
+            || content.to_vec(),
+        )
+    }
+
+    fn tst_info(content: &[u8]) -> Result<TstInfo, Error> {
+        Constructed::decode(content, Mode::Der, TstInfo::take_from)
+            .map_err(|err| Error::Other(format!("Time-stamp token is not valid: {}", err)))
+    }
+
+    /// Compare the revision that ends at the end of the `ByteRange` with the `document`.
+    fn modification_report(
+        pdf_file_data: &[u8],
+        document: &Document,
+        byte_range: &[(u64, u64)],
+        field_id: Option<ObjectId>,
+    ) -> Result<ModificationReport, Error> {
+        // Checks that the `ByteRange` is in the file.
+        Self::signed_ranges(pdf_file_data, byte_range)?;
+        let signed_revision_length = byte_range
+            .last()
+            .map_or(0, |(offset, length)| (offset + length) as usize);
+        let signed_revision = Document::load_mem(&pdf_file_data[..signed_revision_length])?;
+        ModificationReport::new(
+            &signed_revision,
+            signed_revision_length,
+            document,
+            pdf_file_data.len(),
+            field_id,
+        )
+    }
+
+    /// The parts of the file covered by the `ByteRange`.
+    /// The ranges have to be in order and the gaps between them have to be hex strings.
+    pub(crate) fn signed_ranges<'a>(
+        pdf_file_data: &'a [u8],
+        byte_range: &[(u64, u64)],
+    ) -> Result<Vec<&'a [u8]>, Error> {
+        let invalid = || Error::from("Signature `ByteRange` is not valid.");
+        if byte_range.first().map(|(offset, _)| *offset) != Some(0) {
+            return Err(invalid());
+        }
+        let mut ranges = vec![];
+        let mut previous_end = 0;
+        for (offset, length) in byte_range {
+            let start = usize::try_from(*offset)?;
+            let end = start
+                .checked_add(usize::try_from(*length)?)
+                .ok_or_else(invalid)?;
+            if start < previous_end || end > pdf_file_data.len() {
+                return Err(invalid());
+            }
+            if start > previous_end
+                && (start - previous_end < 2
+                    || pdf_file_data[previous_end] != b'<'
+                    || pdf_file_data[start - 1] != b'>')
+            {
+                return Err(invalid());
+            }
+            ranges.push(&pdf_file_data[start..end]);
+            previous_end = end;
+        }
+        Ok(ranges)
+    }
+
+    /// Length of the DER encoded value at the start of `data`, or the length of `data` when
+    /// the length is not known (indefinite length encoding).
+    pub(crate) fn der_length(data: &[u8]) -> usize {
+        let length = match data.get(1) {
+            Some(length @ 0..=0x7f) => 2 + *length as usize,
+            Some(length @ 0x81..=0x84) => {
+                let size = (*length & 0x7f) as usize;
+                data.get(2..2 + size).map_or(data.len(), |bytes| {
+                    2 + size
+                        + bytes
+                            .iter()
+                            .fold(0usize, |length, byte| (length << 8) | *byte as usize)
+                })
+            }
+            _ => data.len(),
+        };
+        length.min(data.len())
+    }
+
+    /// The certificates in the CMS, other certificate formats are ignored.
+    fn cms_certificates(signed_data: &SignedData) -> Result<Vec<CapturedX509Certificate>, Error> {
+        let mut certificates = vec![];
+        for choice in signed_data.certificates.iter().flat_map(|set| set.iter()) {
+            if let CertificateChoices::Certificate(certificate) = choice {
+                let certificate = X509Certificate::from(certificate.deref().clone());
+                certificates.push(CapturedX509Certificate::from_ber(
+                    certificate.encode_ber()?,
+                )?);
+            }
+        }
+        Ok(certificates)
+    }
+
+    /// The certificate identified by the `sid` of the `signer_info`.
+    fn find_signer_certificate<'a>(
+        certificates: &'a [CapturedX509Certificate],
+        signer_info: &SignerInfo,
+    ) -> Option<&'a CapturedX509Certificate> {
+        certificates
+            .iter()
+            .find(|certificate| match &signer_info.sid {
+                SignerIdentifier::IssuerAndSerialNumber(issuer_and_serial_number) => {
+                    certificate.issuer_name() == &issuer_and_serial_number.issuer
+                        && certificate.serial_number_asn1()
+                            == &issuer_and_serial_number.serial_number
+                }
+                SignerIdentifier::SubjectKeyIdentifier(key_identifier) => {
+                    Self::subject_key_identifier(certificate).as_deref()
+                        == Some(key_identifier.to_bytes().as_ref())
+                }
+            })
+    }
+
+    /// The subject key identifier extension, or the SHA-1 of the public key (RFC 5280 4.2.1.2).
+    fn subject_key_identifier(certificate: &CapturedX509Certificate) -> Option<Vec<u8>> {
+        match certificate
+            .iter_extensions()
+            .find(|extension| extension.id == OID_SUBJECT_KEY_IDENTIFIER)
+        {
+            Some(extension) => Constructed::decode(
+                extension.value.to_bytes(),
+                Mode::Der,
+                OctetString::take_from,
+            )
+            .ok()
+            .map(|key_identifier| key_identifier.to_bytes().to_vec()),
+            None => Some(DigestAlgorithm::Sha1.digest_data(&certificate.public_key_data())),
+        }
+    }
+
+    /// Check the encapsulated content against the `ByteRange`.
+    ///
+    /// - `adbe.pkcs7.sha1`: the content is the SHA-1 digest of the `ByteRange`.
+    /// - `ETSI.RFC3161`: the content is a `TSTInfo`, the message imprint is the digest of the
+    ///   `ByteRange`.
+    fn encapsulated_content_matches(
+        sub_filter: Option<&str>,
+        encapsulated_content: &[u8],
+        signed_ranges: &[&[u8]],
+    ) -> Result<bool, Error> {
+        match sub_filter {
+            Some("adbe.pkcs7.sha1") => {
+                Ok(encapsulated_content
+                    == Self::ranges_digest(signed_ranges, DigestAlgorithm::Sha1))
+            }
+            Some("ETSI.RFC3161") => {
+                let tst_info = Self::tst_info(encapsulated_content)?;
+                let imprint_algorithm =
+                    DigestAlgorithm::try_from(&tst_info.message_imprint.hash_algorithm)?;
+                Ok(tst_info.message_imprint.hashed_message.to_bytes()
+                    == Self::ranges_digest(signed_ranges, imprint_algorithm))
+            }
+            _ => Err("Signature has unexpected encapsulated content.".into()),
+        }
+    }
+
+    fn ranges_digest(signed_ranges: &[&[u8]], digest_algorithm: DigestAlgorithm) -> Vec<u8> {
+        let mut digester = digest_algorithm.digester();
+        for range in signed_ranges {
+            digester.update(range);
+        }
+        digester.finish().as_ref().to_vec()
+    }
+
+    /// The value of the `message-digest` signed attribute.
+    fn message_digest(signer_info: &SignerInfo) -> Result<Vec<u8>, Error> {
+        let invalid = || Error::from("Signature has no valid `message-digest` attribute.");
+        let attribute = signer_info
+            .signed_attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .find(|attribute| attribute.typ == OID_MESSAGE_DIGEST)
+            .ok_or_else(invalid)?;
+        match attribute.values.as_slice() {
+            [value] => value
+                .deref()
+                .clone()
+                .decode(OctetString::take_from)
+                .map(|message_digest| message_digest.to_bytes().to_vec())
+                .map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The value of the `signing-time` signed attribute.
+    fn signing_time(signer_info: &SignerInfo) -> Option<DateTime<Utc>> {
+        let attribute = signer_info
+            .signed_attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .find(|attribute| attribute.typ == OID_SIGNING_TIME)?;
+        match attribute.values.as_slice() {
+            [value] => value
+                .deref()
+                .clone()
+                .decode(Time::take_from)
+                .ok()
+                .map(DateTime::from),
+            _ => None,
+        }
+    }
+
+    /// The value of the `adbe-revocationInfoArchival` signed attribute.
+    fn revocation_info(signer_info: &SignerInfo) -> Result<ValidationData, Error> {
+        let mut revocation_info = ValidationData::default();
+        let attributes = signer_info
+            .signed_attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .filter(|attribute| attribute.typ == OID_ADBE_REVOCATION_INFO_ARCHIVAL);
+        for value in attributes.flat_map(|attribute| attribute.values.iter()) {
+            revocation_info.merge(&ValidationData::from_revocation_info_archival(
+                value.deref().as_slice(),
+            )?);
+        }
+        Ok(revocation_info)
+    }
+}
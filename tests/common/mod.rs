@@ -0,0 +1,57 @@
+//! Shared fixtures of the integration tests.
+
+#![allow(dead_code)]
+
+#[path = "../../examples/common/mod.rs"]
+mod certificates;
+
+pub use certificates::*;
+use pdf_signing::{PDFSigningDocument, PdfSigner, UserSignatureInfo};
+use std::sync::Arc;
+use x509_certificate::{CapturedX509Certificate, InMemorySigningKeyPair};
+
+pub const PDF_FILE_NAME: &str = "test-small-3sig.pdf";
+
+/// A self-signed ECDSA P-256 certificate and its key.
+pub fn certificate() -> (CapturedX509Certificate, InMemorySigningKeyPair) {
+    let TestCertificate { certificate, key } = TestCertificate::new("PDF signing test", &[], None);
+    (certificate, key)
+}
+
+pub fn signer() -> Arc<dyn PdfSigner> {
+    certificates::signer()
+}
+
+pub fn user_signature_info(user_id: &str, signer: Arc<dyn PdfSigner>) -> UserSignatureInfo {
+    UserSignatureInfo::new(
+        user_id,
+        "Charlie",
+        "charlie@test.com",
+        std::fs::read("./examples/assets/sig1.png").unwrap(),
+        signer,
+    )
+}
+
+/// Sign the test document for each of the users.
+pub fn sign(user_ids: &[&str], signer: Arc<dyn PdfSigner>) -> Vec<u8> {
+    let users_signature_info = user_ids
+        .iter()
+        .map(|user_id| user_signature_info(user_id, signer.clone()))
+        .collect();
+    read_document().sign_document(users_signature_info).unwrap()
+}
+
+pub fn read_document() -> PDFSigningDocument {
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", PDF_FILE_NAME)).unwrap();
+    PDFSigningDocument::read_from(&*pdf_data, PDF_FILE_NAME.to_owned()).unwrap()
+}
+
+/// Verify all signatures of the PDF file, returns the number of signatures.
+pub fn verify_all(pdf_file_data: &[u8]) -> usize {
+    let document = PDFSigningDocument::read_from(pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let verifications = document.verify_signatures().unwrap();
+    for verification in &verifications {
+        assert!(verification.is_valid(), "{:?}", verification);
+    }
+    verifications.len()
+}
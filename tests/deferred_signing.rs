@@ -0,0 +1,89 @@
+mod common;
+
+use pdf_signing::{Error, ExternalSignature, PDFSigningDocument, PreparedSignature, SigningKeys};
+use std::sync::Arc;
+
+fn prepare() -> (PreparedSignature, Arc<dyn pdf_signing::PdfSigner>) {
+    let (certificate, key) = common::certificate();
+    let certificate_only = Arc::new(SigningKeys::from_certificate(certificate.clone()));
+    let prepared = common::read_document()
+        .prepare_signature(&common::user_signature_info("272", certificate_only))
+        .unwrap();
+    (prepared, Arc::new(SigningKeys::new(key, certificate)))
+}
+
+#[test]
+fn finish_after_json_round_trip() {
+    let (prepared, signer) = prepare();
+    let json = serde_json::to_string(&prepared).unwrap();
+    // The PDF is a base64 string.
+    assert!(json.len() < prepared.pdf.len() * 2);
+    let prepared: PreparedSignature = serde_json::from_str(&json).unwrap();
+
+    let signature = signer
+        .sign(&prepared.signed_attributes, prepared.signature_algorithm)
+        .unwrap();
+    let pdf_file_data =
+        PDFSigningDocument::finish_signature(prepared, ExternalSignature::Raw(signature)).unwrap();
+    assert_eq!(common::verify_all(&pdf_file_data), 1);
+}
+
+#[test]
+fn finish_refuses_invalid_byte_range() {
+    let (prepared, _) = prepare();
+    let pdf_len = prepared.pdf.len();
+    let [_, first_end, second_start, second_len] = prepared.byte_range[..] else {
+        panic!("{:?}", prepared.byte_range);
+    };
+    for byte_range in [
+        vec![],
+        vec![0, 100],
+        // No gap for `<>`.
+        vec![0, 100, 101, 10],
+        vec![0, usize::MAX, 0, 10],
+        // `Contents` outside of the file.
+        vec![0, pdf_len, pdf_len + 100, 0],
+        // Not the whole file.
+        vec![1, first_end, second_start, second_len],
+        vec![0, first_end, second_start, second_len - 1],
+        // Not the `Contents` placeholder.
+        vec![0, first_end + 1, second_start + 1, second_len - 1],
+        vec![0, first_end - 1, second_start - 1, second_len + 1],
+        vec![0, first_end, second_start - 1, second_len + 1],
+    ] {
+        let prepared = PreparedSignature {
+            byte_range: byte_range.clone(),
+            ..prepared.clone()
+        };
+        let result =
+            PDFSigningDocument::finish_signature(prepared, ExternalSignature::Cms(vec![1, 2, 3]));
+        assert!(
+            matches!(result, Err(Error::Other(_))),
+            "{:?}: {:?}",
+            byte_range,
+            result.map(|pdf| pdf.len())
+        );
+    }
+}
+
+#[test]
+fn finish_refuses_changed_contents() {
+    let (mut prepared, _) = prepare();
+    // The hex string written over is not the empty placeholder.
+    prepared.pdf[prepared.byte_range[1] + 1] = b'1';
+    let result =
+        PDFSigningDocument::finish_signature(prepared, ExternalSignature::Cms(vec![1, 2, 3]));
+    assert!(matches!(result, Err(Error::Other(_))));
+}
+
+#[test]
+fn prepare_does_not_change_the_document() {
+    let (certificate, _) = common::certificate();
+    let certificate_only = Arc::new(SigningKeys::from_certificate(certificate));
+    let mut document = common::read_document();
+    let user_info = common::user_signature_info("272", certificate_only);
+    let first = document.prepare_signature(&user_info).unwrap();
+    let second = document.prepare_signature(&user_info).unwrap();
+    assert_eq!(first.byte_range, second.byte_range);
+    assert!(document.get_new_document_ref().objects.is_empty());
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use pdf_signing::{PDFSigningDocument, SigningKeys, ValidationData};
+use std::sync::Arc;
+
+#[test]
+fn read_back_document_security_store() {
+    let (certificate, key) = common::certificate();
+    let certificate_der = certificate.encode_der().unwrap();
+    let signer = Arc::new(SigningKeys::new(key, certificate));
+    let signed = common::read_document()
+        .sign_document(vec![common::user_signature_info("272", signer.clone())])
+        .unwrap();
+
+    // The values are not checked, any bytes will do.
+    let validation_data = ValidationData {
+        certs: vec![certificate_der.clone()],
+        ocsps: vec![],
+        crls: vec![b"CRL".to_vec()],
+    };
+    let with_dss = PDFSigningDocument::read_from(&*signed, "signed.pdf".to_owned())
+        .unwrap()
+        .add_document_security_store(&validation_data)
+        .unwrap();
+    assert!(with_dss.starts_with(&signed));
+    assert_eq!(common::verify_all(&with_dss), 1);
+
+    let document = PDFSigningDocument::read_from(&*with_dss, "dss.pdf".to_owned()).unwrap();
+    let dss = document.document_security_store().unwrap();
+    assert_eq!(dss.certs, validation_data.certs);
+    assert_eq!(dss.crls, validation_data.crls);
+    assert!(dss.ocsps.is_empty());
+    let vri = document.signature_validation_data().unwrap();
+    assert_eq!(vri.len(), 1);
+    let vri = vri[0].as_ref().unwrap();
+    assert_eq!(vri.certs, validation_data.certs);
+    assert_eq!(vri.crls, validation_data.crls);
+
+    // Existing data is reused, new data is added to the `DSS` and the `VRI` entry.
+    let more_data = ValidationData {
+        certs: vec![certificate_der.clone()],
+        ocsps: vec![b"OCSP".to_vec()],
+        crls: vec![],
+    };
+    let with_dss = PDFSigningDocument::read_from(&*with_dss, "dss.pdf".to_owned())
+        .unwrap()
+        .add_document_security_store(&more_data)
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*with_dss, "dss.pdf".to_owned()).unwrap();
+    let dss = document.document_security_store().unwrap();
+    assert_eq!(dss.certs.len(), 1);
+    assert_eq!(dss.ocsps, more_data.ocsps);
+    assert_eq!(dss.crls, validation_data.crls);
+    let vri = document
+        .signature_validation_data()
+        .unwrap()
+        .remove(0)
+        .unwrap();
+    assert_eq!(vri.certs.len(), 1);
+    assert_eq!(vri.ocsps, more_data.ocsps);
+    assert_eq!(vri.crls, validation_data.crls);
+
+    // A signature added after the `DSS` has no `VRI` entry.
+    let signed = PDFSigningDocument::read_from(&*with_dss, "dss.pdf".to_owned())
+        .unwrap()
+        .sign_document(vec![common::user_signature_info("292", signer)])
+        .unwrap();
+    assert_eq!(common::verify_all(&signed), 2);
+    let document = PDFSigningDocument::read_from(&*signed, "signed.pdf".to_owned()).unwrap();
+    assert_eq!(document.document_security_store().unwrap().certs.len(), 1);
+    let vri = document.signature_validation_data().unwrap();
+    assert_eq!(vri.iter().filter(|vri| vri.is_some()).count(), 1);
+    assert_eq!(vri.iter().filter(|vri| vri.is_none()).count(), 1);
+}
+
+#[test]
+fn no_document_security_store() {
+    let document = common::read_document();
+    let dss = document.document_security_store().unwrap();
+    assert!(dss.certs.is_empty() && dss.ocsps.is_empty() && dss.crls.is_empty());
+    assert!(document.signature_validation_data().unwrap().is_empty());
+}
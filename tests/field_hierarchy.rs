@@ -0,0 +1,64 @@
+mod common;
+
+use pdf_signing::{FieldType, FieldValue, PDFSigningDocument};
+
+#[test]
+fn inherited_field_attributes() {
+    let pdf_data = common::documents::nested_form();
+    let fields = PDFSigningDocument::read_from(&*pdf_data, "nested.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap();
+    // The non-terminal fields are not listed, the cycle is not followed.
+    assert_eq!(fields.len(), 3);
+
+    let address = &fields[0];
+    assert_eq!(address.name.as_deref(), Some("customer.address.street"));
+    assert_eq!(address.partial_name.as_deref(), Some("street"));
+    assert_eq!(address.field_type, FieldType::Text);
+    assert_eq!(
+        address.value,
+        Some(FieldValue::Text("Main Street".to_owned()))
+    );
+    assert_eq!(
+        address.default_value,
+        Some(FieldValue::Text("-".to_owned()))
+    );
+    assert_eq!(
+        address.default_appearance.as_deref(),
+        Some("/Helv 10 Tf 0 g")
+    );
+    assert_eq!(address.quadding, Some(1));
+
+    let signatures = &fields[1..];
+    assert!(signatures.iter().all(|field| {
+        field.field_type == FieldType::Signature
+            && field.flags == 2
+            && field.name.as_deref().unwrap().starts_with("approval.")
+            && field.page() == Some(1)
+    }));
+}
+
+#[test]
+fn sign_nested_field() {
+    let pdf_data = common::documents::nested_form();
+    let pdf_file_data = PDFSigningDocument::read_from(&*pdf_data, "nested.pdf".to_owned())
+        .unwrap()
+        .sign_document(vec![common::user_signature_info("272", common::signer())])
+        .unwrap();
+    assert_eq!(common::verify_all(&pdf_file_data), 1);
+
+    let signed: Vec<_> = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap()
+        .into_iter()
+        .filter(|field| field.signed)
+        .collect();
+    assert_eq!(signed.len(), 1);
+    assert_eq!(
+        signed[0].partial_name.as_deref(),
+        Some("eyJ1c2VySWQiOiIyNzIifQ==")
+    );
+    assert!(signed[0].name.as_deref().unwrap().starts_with("approval."));
+}
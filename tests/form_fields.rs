@@ -0,0 +1,55 @@
+mod common;
+
+use pdf_signing::{FieldType, PDFSigningDocument, Rectangle, UserFormSignatureInfo};
+
+#[test]
+fn list_signature_fields() {
+    let fields = common::read_document().form_fields().unwrap();
+    let signature_fields: Vec<_> = fields
+        .iter()
+        .filter(|field| field.field_type == FieldType::Signature)
+        .collect();
+    assert_eq!(signature_fields.len(), 3);
+    assert!(signature_fields.iter().all(|field| !field.signed
+        && field.value.is_none()
+        && field.page().is_some()
+        && field.widgets.iter().all(|widget| widget.rect.is_some())));
+}
+
+#[test]
+fn list_added_and_signed_field() {
+    let mut document = common::read_document();
+    let rect = Rectangle {
+        x1: 50.0,
+        y1: 50.0,
+        x2: 250.0,
+        y2: 120.0,
+    };
+    document
+        .add_signature_field(
+            1,
+            rect.clone(),
+            "Approval",
+            UserFormSignatureInfo {
+                user_id: "500".to_owned(),
+            },
+        )
+        .unwrap();
+    let new_field = document.form_fields().unwrap().pop().unwrap();
+    assert_eq!(new_field.alternate_name.as_deref(), Some("Approval"));
+    assert_eq!(new_field.field_type, FieldType::Signature);
+    assert_eq!(new_field.page(), Some(1));
+    assert_eq!(new_field.widgets[0].rect.as_ref(), Some(&rect));
+    assert!(!new_field.signed);
+
+    let pdf_file_data = document
+        .sign_document(vec![common::user_signature_info("500", common::signer())])
+        .unwrap();
+    let fields = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap();
+    let signed_fields: Vec<_> = fields.iter().filter(|field| field.signed).collect();
+    assert_eq!(signed_fields.len(), 1);
+    assert_eq!(signed_fields[0].name, new_field.name);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use pdf_signing::{
+    lopdf, Error, IncrementalDocument, LocalTimestampAuthority, PDFSigningDocument, Rectangle,
+    SigningKeys, TimestampProvider, UserFormSignatureInfo,
+};
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::Arc;
+use x509_certificate::DigestAlgorithm;
+
+/// Reserves too little space for its token, so the `Contents` have to be resized.
+struct SmallEstimate(LocalTimestampAuthority);
+
+impl TimestampProvider for SmallEstimate {
+    fn get_timestamp_token(
+        &self,
+        message_digest: &[u8],
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Vec<u8>, Error> {
+        self.0.get_timestamp_token(message_digest, digest_algorithm)
+    }
+
+    fn estimated_token_size(&self) -> usize {
+        16
+    }
+}
+
+/// Each signature is a new incremental update on top of the previous ones.
+#[test]
+fn sign_many_fields() {
+    let mut document = common::read_document();
+    let user_ids: Vec<String> = (0..24).map(|index| format!("{}", 1000 + index)).collect();
+    for (index, user_id) in user_ids.iter().enumerate() {
+        let y1 = 20.0 + 30.0 * index as f32;
+        let rect = Rectangle {
+            x1: 300.0,
+            y1,
+            x2: 500.0,
+            y2: y1 + 25.0,
+        };
+        document
+            .add_signature_field(
+                1,
+                rect,
+                user_id,
+                UserFormSignatureInfo {
+                    user_id: user_id.clone(),
+                },
+            )
+            .unwrap();
+    }
+
+    let signer = common::signer();
+    let users_signature_info = user_ids
+        .iter()
+        .map(|user_id| common::user_signature_info(user_id, signer.clone()))
+        .collect();
+    let signed = document.sign_document(users_signature_info).unwrap();
+    assert_eq!(common::verify_all(&signed), user_ids.len());
+    // The document is updated as well.
+    assert_eq!(
+        document.get_incr_document_ref().get_prev_documents_bytes(),
+        &signed[..]
+    );
+}
+
+/// The `ByteRange` offsets of a large file have 9 digits.
+#[test]
+fn sign_with_large_offsets() {
+    let pdf_data = std::fs::read(format!("./examples/assets/{}", common::PDF_FILE_NAME)).unwrap();
+    let document = lopdf::Document::load_mem(&pdf_data).unwrap();
+    let mut padded = pdf_data;
+    padded.extend_from_slice(b"\n%");
+    padded.resize(padded.len() + 100_000_000, b'-');
+    padded.push(b'\n');
+    let mut large = Vec::new();
+    IncrementalDocument::create_from(padded, document)
+        .save_to(&mut large)
+        .unwrap();
+
+    let signed = PDFSigningDocument::read_from(&*large, "large.pdf".to_owned())
+        .unwrap()
+        .sign_document(vec![common::user_signature_info("272", common::signer())])
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*signed, "signed.pdf".to_owned()).unwrap();
+    let signatures = document.signatures().unwrap();
+    assert!(signatures
+        .iter()
+        .any(|signature| signature.byte_range[1].0 >= 100_000_000));
+    assert_eq!(common::verify_all(&signed), signatures.len());
+}
+
+/// Both signatures are written to the file once, the `Contents` are filled in by seeking back.
+#[test]
+fn sign_to_file() {
+    let path = std::env::temp_dir().join(format!("pdf_signing_{}.pdf", std::process::id()));
+    let mut file = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    // Offsets are relative to the position of the file when signing starts.
+    file.write_all(b"not part of the PDF").unwrap();
+    let start = file.stream_position().unwrap();
+
+    let mut document = common::read_document();
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| common::user_signature_info(user_id, common::signer()))
+        .collect();
+    let mut file = document
+        .sign_document_to(users_signature_info, file)
+        .unwrap();
+    let end = file.seek(SeekFrom::End(0)).unwrap();
+    drop(file);
+    let signed = std::fs::read(&path).unwrap().split_off(start as usize);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(signed.len() as u64, end - start);
+    assert_eq!(common::verify_all(&signed), 2);
+    assert_eq!(
+        document.get_incr_document_ref().get_prev_documents_bytes(),
+        &signed[..]
+    );
+}
+
+/// The update is written again over the first attempt when the token does not fit.
+#[test]
+fn resize_contents() {
+    let (certificate, key) = common::certificate();
+    let tsa = SmallEstimate(LocalTimestampAuthority::new(Arc::new(SigningKeys::new(
+        key,
+        certificate,
+    ))));
+    let mut document = common::read_document();
+    let signed = document.add_document_timestamp(&tsa).unwrap();
+    assert_eq!(common::verify_all(&signed), 1);
+    assert_eq!(
+        document.get_incr_document_ref().get_prev_documents_bytes(),
+        &signed[..]
+    );
+}
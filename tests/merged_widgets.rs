@@ -0,0 +1,39 @@
+mod common;
+
+use pdf_signing::PDFSigningDocument;
+
+#[test]
+fn list_widgets() {
+    let pdf_data = common::documents::merged_widgets();
+    let fields = PDFSigningDocument::read_from(&*pdf_data, "form.pdf".to_owned())
+        .unwrap()
+        .form_fields()
+        .unwrap();
+    // The merged field is its own widget.
+    assert_eq!(fields[0].widgets.len(), 1);
+    assert_eq!(fields[0].page(), Some(1));
+    let widget_pages: Vec<_> = fields[1].widgets.iter().map(|widget| widget.page).collect();
+    assert_eq!(widget_pages, vec![Some(1), Some(2)]);
+}
+
+#[test]
+fn sign_all_widgets() {
+    let pdf_data = common::documents::merged_widgets();
+    let mut document = PDFSigningDocument::read_from(&*pdf_data, "form.pdf".to_owned()).unwrap();
+    let fields = document.form_fields().unwrap();
+    let users_signature_info = ["272", "292"]
+        .into_iter()
+        .map(|user_id| common::user_signature_info(user_id, common::signer()))
+        .collect();
+    let pdf_file_data = document.sign_document(users_signature_info).unwrap();
+    assert_eq!(common::verify_all(&pdf_file_data), 2);
+
+    // All widgets show the signature and are locked.
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let raw_doc = document.get_prev_document_ref();
+    for widget in fields.iter().flat_map(|field| &field.widgets) {
+        let widget_dict = raw_doc.get_dictionary(widget.object_id).unwrap();
+        assert!(widget_dict.get(b"AP").unwrap().as_dict().unwrap().has(b"N"));
+        assert_eq!(widget_dict.get(b"F").unwrap().as_i64().unwrap() & 132, 132);
+    }
+}
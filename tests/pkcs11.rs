@@ -0,0 +1,286 @@
+//! Sign with keys on a SoftHSM token, run with
+//! `cargo test --features pkcs11 --test pkcs11 -- --include-ignored` and `SOFTHSM2_CONF` set.
+//!
+//! A new token is initialized in the free slot, the keys are generated on the token and the
+//! certificates are issued by an in-memory CA. Set `PKCS11_MODULE` when SoftHSM is not
+//! installed at `/usr/lib/softhsm/libsofthsm2.so`.
+
+#![cfg(feature = "pkcs11")]
+
+mod common;
+
+use bcder::{
+    encode::{self, PrimitiveContent, Values},
+    BitString, Mode, Unsigned,
+};
+use cryptographic_message_syntax::Bytes;
+use cryptoki::{
+    context::{CInitializeArgs, CInitializeFlags, Pkcs11},
+    error::{Error as Pkcs11Error, RvError},
+    mechanism::Mechanism,
+    object::{Attribute, AttributeType, CertificateType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::AuthPin,
+};
+use pdf_signing::{PdfSigner, Pkcs11KeyId, Pkcs11Signer, SignatureAlgorithm};
+use std::sync::Arc;
+use x509_certificate::{
+    asn1time::Time, rfc3280::Name, rfc5280, CapturedX509Certificate, DigestAlgorithm, EcdsaCurve,
+    InMemorySigningKeyPair, KeyAlgorithm, Sign, Signer, X509Certificate,
+};
+
+const SO_PIN: &str = "5678";
+const PIN: &str = "1234";
+/// `prime256v1`
+const P256_PARAMS: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+#[test]
+#[ignore = "needs SoftHSM"]
+fn sign_with_softhsm() {
+    assert!(
+        std::env::var_os("SOFTHSM2_CONF").is_some(),
+        "`SOFTHSM2_CONF` is not set."
+    );
+    let module = std::env::var("PKCS11_MODULE")
+        .unwrap_or_else(|_| "/usr/lib/softhsm/libsofthsm2.so".to_owned());
+    let token_label = format!("pdf-signing-{}", rand::random::<u32>());
+    create_token(&module, &token_label);
+
+    for (key_label, algorithms) in [
+        (
+            "rsa",
+            vec![
+                SignatureAlgorithm::RsaPkcs1(DigestAlgorithm::Sha256),
+                SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha256),
+                SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha512),
+            ],
+        ),
+        (
+            "ecdsa",
+            vec![
+                SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha256),
+                SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha384),
+            ],
+        ),
+    ] {
+        // Only one signer at a time, the sessions of a token share the login state.
+        let signer = Pkcs11Signer::new(
+            &module,
+            &token_label,
+            PIN,
+            Pkcs11KeyId::Label(key_label.to_owned()),
+        )
+        .unwrap();
+        assert_eq!(signer.certificate_chain().len(), 1, "{}", key_label);
+        let signer: Arc<dyn PdfSigner> = Arc::new(signer);
+        for algorithm in algorithms {
+            assert!(signer.supported_algorithms().contains(&algorithm));
+            let user_info =
+                common::user_signature_info("272", signer.clone()).signature_algorithm(algorithm);
+            let pdf_file_data = common::read_document()
+                .sign_document(vec![user_info])
+                .unwrap_or_else(|err| panic!("{}: {:?}", algorithm, err));
+            assert_eq!(common::verify_all(&pdf_file_data), 1, "{}", algorithm);
+        }
+    }
+}
+
+/// Initialize a token with an RSA and an ECDSA key pair, their certificates and the CA.
+fn create_token(module: &str, token_label: &str) {
+    let pkcs11 = Pkcs11::new(module).unwrap();
+    match pkcs11.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK)) {
+        Err(Pkcs11Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {}
+        result => result.unwrap(),
+    }
+    let free_slot = pkcs11
+        .get_all_slots()
+        .unwrap()
+        .into_iter()
+        .find(|slot| {
+            pkcs11
+                .get_token_info(*slot)
+                .is_ok_and(|info| !info.token_initialized())
+        })
+        .expect("SoftHSM has no free slot.");
+    pkcs11
+        .init_token(free_slot, &AuthPin::from(SO_PIN), token_label)
+        .unwrap();
+    // SoftHSM moves the new token to another slot.
+    let slot = pkcs11
+        .get_slots_with_token()
+        .unwrap()
+        .into_iter()
+        .find(|slot| {
+            pkcs11
+                .get_token_info(*slot)
+                .is_ok_and(|info| info.label().trim_end() == token_label)
+        })
+        .unwrap();
+
+    let session = pkcs11.open_rw_session(slot).unwrap();
+    session
+        .login(UserType::So, Some(&AuthPin::from(SO_PIN)))
+        .unwrap();
+    session.init_pin(&AuthPin::from(PIN)).unwrap();
+    session.logout().unwrap();
+    session
+        .login(UserType::User, Some(&AuthPin::from(PIN)))
+        .unwrap();
+
+    let (ca_certificate, ca_key) = common::certificate();
+    import_certificate(&session, &ca_certificate, "ca", &[0xca]);
+
+    let rsa_public_key = generate_key_pair(
+        &session,
+        &Mechanism::RsaPkcsKeyPairGen,
+        vec![Attribute::ModulusBits(2048.into())],
+        "rsa",
+        &[1],
+    );
+    let attributes = session
+        .get_attributes(
+            rsa_public_key,
+            &[AttributeType::Modulus, AttributeType::PublicExponent],
+        )
+        .unwrap();
+    let (modulus, exponent) = match attributes.as_slice() {
+        [Attribute::Modulus(modulus), Attribute::PublicExponent(exponent)] => (modulus, exponent),
+        attributes => panic!("Unexpected RSA attributes {:?}", attributes),
+    };
+    // `RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }`
+    let modulus = Unsigned::from_slice(modulus).unwrap();
+    let exponent = Unsigned::from_slice(exponent).unwrap();
+    let mut rsa_public_key_der = Vec::new();
+    encode::sequence(((&modulus).encode(), (&exponent).encode()))
+        .write_encoded(Mode::Der, &mut rsa_public_key_der)
+        .unwrap();
+    let certificate = issue_certificate(
+        &ca_certificate,
+        &ca_key,
+        "RSA signer",
+        KeyAlgorithm::Rsa,
+        rsa_public_key_der,
+    );
+    import_certificate(&session, &certificate, "rsa", &[1]);
+
+    let ec_public_key = generate_key_pair(
+        &session,
+        &Mechanism::EccKeyPairGen,
+        vec![Attribute::EcParams(P256_PARAMS.to_vec())],
+        "ecdsa",
+        &[2],
+    );
+    let attributes = session
+        .get_attributes(ec_public_key, &[AttributeType::EcPoint])
+        .unwrap();
+    let ec_point = match attributes.as_slice() {
+        // DER encoded `OCTET STRING` with the uncompressed point.
+        [Attribute::EcPoint(ec_point)] => ec_point[2..].to_vec(),
+        attributes => panic!("Unexpected EC attributes {:?}", attributes),
+    };
+    let certificate = issue_certificate(
+        &ca_certificate,
+        &ca_key,
+        "ECDSA signer",
+        KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1),
+        ec_point,
+    );
+    import_certificate(&session, &certificate, "ecdsa", &[2]);
+}
+
+/// Generate a key pair on the token, returns the public key.
+fn generate_key_pair(
+    session: &Session,
+    mechanism: &Mechanism,
+    mut public_template: Vec<Attribute>,
+    label: &str,
+    id: &[u8],
+) -> ObjectHandle {
+    public_template.extend([
+        Attribute::Token(true),
+        Attribute::Verify(true),
+        Attribute::Label(label.as_bytes().to_vec()),
+        Attribute::Id(id.to_vec()),
+    ]);
+    let private_template = [
+        Attribute::Token(true),
+        Attribute::Private(true),
+        Attribute::Sensitive(true),
+        Attribute::Sign(true),
+        Attribute::Label(label.as_bytes().to_vec()),
+        Attribute::Id(id.to_vec()),
+    ];
+    session
+        .generate_key_pair(mechanism, &public_template, &private_template)
+        .unwrap()
+        .0
+}
+
+fn import_certificate(
+    session: &Session,
+    certificate: &CapturedX509Certificate,
+    label: &str,
+    id: &[u8],
+) {
+    let mut subject = Vec::new();
+    certificate
+        .subject_name()
+        .encode_ref()
+        .write_encoded(Mode::Der, &mut subject)
+        .unwrap();
+    session
+        .create_object(&[
+            Attribute::Class(ObjectClass::CERTIFICATE),
+            Attribute::CertificateType(CertificateType::X_509),
+            Attribute::Token(true),
+            Attribute::Label(label.as_bytes().to_vec()),
+            Attribute::Id(id.to_vec()),
+            Attribute::Subject(subject),
+            Attribute::Value(certificate.constructed_data().to_vec()),
+        ])
+        .unwrap();
+}
+
+/// A certificate for the `public_key` on the token, signed by the CA.
+fn issue_certificate(
+    ca_certificate: &CapturedX509Certificate,
+    ca_key: &InMemorySigningKeyPair,
+    common_name: &str,
+    key_algorithm: KeyAlgorithm,
+    public_key: Vec<u8>,
+) -> CapturedX509Certificate {
+    let mut subject = Name::default();
+    subject.append_common_name_utf8_string(common_name).unwrap();
+    let now = chrono::Utc::now();
+    let tbs_certificate = rfc5280::TbsCertificate {
+        version: Some(rfc5280::Version::V3),
+        serial_number: rand::random::<u32>().into(),
+        signature: ca_key.signature_algorithm().unwrap().into(),
+        issuer: ca_certificate.subject_name().clone(),
+        validity: rfc5280::Validity {
+            not_before: Time::from(now - chrono::Duration::minutes(5)),
+            not_after: Time::from(now + chrono::Duration::hours(1)),
+        },
+        subject,
+        subject_public_key_info: rfc5280::SubjectPublicKeyInfo {
+            algorithm: key_algorithm.into(),
+            subject_public_key: BitString::new(0, Bytes::from(public_key)),
+        },
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: None,
+        raw_data: None,
+    };
+    let mut tbs_der = Vec::new();
+    tbs_certificate
+        .encode_ref()
+        .write_encoded(Mode::Der, &mut tbs_der)
+        .unwrap();
+    let signature = ca_key.try_sign(&tbs_der).unwrap();
+    let certificate = X509Certificate::from(rfc5280::Certificate {
+        tbs_certificate,
+        signature_algorithm: ca_key.signature_algorithm().unwrap().into(),
+        signature: BitString::new(0, Bytes::copy_from_slice(signature.as_ref())),
+    });
+    CapturedX509Certificate::from_der(certificate.encode_der().unwrap()).unwrap()
+}
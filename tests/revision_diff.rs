@@ -0,0 +1,63 @@
+mod common;
+
+use pdf_signing::{ObjectChange, ObjectOwner, PDFSigningDocument, RevisionDiffReport};
+
+fn signed_document() -> PDFSigningDocument {
+    let pdf_file_data = common::sign(&["272"], common::signer());
+    PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap()
+}
+
+#[test]
+fn diff_of_signature() {
+    let document = signed_document();
+    let revisions = document.revisions().unwrap();
+    let signed_revision = revisions.last().unwrap();
+    let field_name = signed_revision.signatures[0].field_name.clone().unwrap();
+    let report = document
+        .revision_diff(signed_revision.index - 1, signed_revision.index)
+        .unwrap();
+
+    // The signature field got a value, the signature dictionary was added.
+    let field_owner = ObjectOwner::FormField(field_name);
+    assert!(report.changes.iter().any(|change| {
+        change.change == ObjectChange::Modified
+            && change.object_type == "Field"
+            && change.changed_keys.contains(&"V".to_owned())
+            && change.owners.contains(&field_owner)
+    }));
+    assert!(report.changes.iter().any(|change| {
+        change.change == ObjectChange::Added
+            && change.object_type == "Sig"
+            && change.owners.contains(&field_owner)
+    }));
+    // The signature image is drawn in the widget appearance, pages are not changed.
+    assert!(report
+        .changes
+        .iter()
+        .all(|change| change.object_type != "Page"));
+    assert!(report
+        .changes
+        .iter()
+        .all(|change| change.change != ObjectChange::Deleted));
+}
+
+#[test]
+fn diff_of_same_revision() {
+    let document = signed_document();
+    let index = document.revisions().unwrap().last().unwrap().index;
+    let report = document.revision_diff(index, index).unwrap();
+    assert!(report.changes.is_empty());
+}
+
+#[test]
+fn diff_json_round_trip() {
+    let document = signed_document();
+    let index = document.revisions().unwrap().last().unwrap().index;
+    let report = document.revision_diff(0, index).unwrap();
+    assert!(!report.changes.is_empty());
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    assert_eq!(
+        serde_json::from_str::<RevisionDiffReport>(&json).unwrap(),
+        report
+    );
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use pdf_signing::{PDFSigningDocument, SignatureStatus};
+
+fn read_asset() -> Vec<u8> {
+    std::fs::read(format!("./examples/assets/{}", common::PDF_FILE_NAME)).unwrap()
+}
+
+#[test]
+fn list_revisions() {
+    let pdf_data = read_asset();
+    let original_revisions = common::read_document().revisions().unwrap();
+    assert_eq!(
+        original_revisions.last().unwrap().length,
+        pdf_data.len() as u64
+    );
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+
+    // Each signature is in its own incremental update.
+    let revisions = document.revisions().unwrap();
+    assert_eq!(revisions.len(), original_revisions.len() + 2);
+    assert_eq!(revisions.last().unwrap().length, pdf_file_data.len() as u64);
+    assert!(revisions[original_revisions.len()..]
+        .iter()
+        .all(|revision| revision.signatures.len() == 1));
+    assert!(revisions[..original_revisions.len()]
+        .iter()
+        .all(|revision| revision.signatures.is_empty()));
+
+    // The original document ends with its last revision.
+    let original = document
+        .revision(&revisions[original_revisions.len() - 1])
+        .unwrap();
+    assert_eq!(
+        original.get_incr_document_ref().get_prev_documents_bytes(),
+        &pdf_data[..]
+    );
+    assert!(original.signatures().unwrap().is_empty());
+}
+
+#[test]
+fn extract_signed_revision() {
+    let original_revision_count = common::read_document().revisions().unwrap().len();
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let revisions = document.revisions().unwrap();
+    let signed_revisions = &revisions[original_revision_count..];
+
+    // The first signature covers the document before the second signature was added.
+    let first_signature = &signed_revisions[0].signatures[0];
+    let signed_revision = document.signed_revision(first_signature).unwrap();
+    let signed_data = signed_revision
+        .get_incr_document_ref()
+        .get_prev_documents_bytes();
+    assert_eq!(signed_data.len() as u64, signed_revisions[0].length);
+    assert!(pdf_file_data.starts_with(signed_data));
+    let verifications = signed_revision.verify_signatures().unwrap();
+    assert_eq!(verifications.len(), 1);
+    assert_eq!(verifications[0].status, SignatureStatus::Valid);
+    assert!(verifications[0].is_valid());
+
+    // The last signature covers the whole document.
+    let last_signature = &signed_revisions[1].signatures[0];
+    let signed_revision = document.signed_revision(last_signature).unwrap();
+    assert_eq!(
+        signed_revision
+            .get_incr_document_ref()
+            .get_prev_documents_bytes(),
+        &pdf_file_data[..]
+    );
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use bcder::{decode::Constructed, Mode};
+use chrono::{DateTime, Duration, Utc};
+use common::revocation::{create_crl, create_ocsp_response, der};
+use common::{TestCertificate, CA, EMAIL_PROTECTION, OCSP_SIGNING};
+use cryptographic_message_syntax::{SignedDataBuilder, SignerBuilder};
+use pdf_signing::{
+    CertificateRevocation, ChainValidation, ExternalSignature, PDFSigningDocument,
+    RevocationSource, RevocationStatus, SignatureVerification, SigningKeys, TrustStatus,
+    TrustStore, ValidationData,
+};
+use std::sync::Arc;
+use x509_certificate::{rfc5652::AttributeValue, CapturedX509Certificate, InMemorySigningKeyPair};
+
+/// A root CA, a signer and an OCSP responder, with a signature of the signer.
+struct Setup {
+    root: TestCertificate,
+    signer_cert: CapturedX509Certificate,
+    responder: TestCertificate,
+    not_responder: TestCertificate,
+    pdf_file_data: Vec<u8>,
+    verification: SignatureVerification,
+    chain: ChainValidation,
+    signing_time: DateTime<Utc>,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let root = TestCertificate::new("Test Root CA", &[CA], None);
+        let TestCertificate {
+            certificate: signer_cert,
+            key: signer_key,
+        } = TestCertificate::new("Charlie", &[EMAIL_PROTECTION], Some(&root));
+        let responder = TestCertificate::new("Test OCSP Responder", &[OCSP_SIGNING], Some(&root));
+        let not_responder = TestCertificate::new("Not a responder", &[], Some(&root));
+
+        let pdf_file_data = common::sign(
+            &["272"],
+            Arc::new(SigningKeys::new(signer_key, signer_cert.clone())),
+        );
+        let verification = verify(&pdf_file_data);
+        let chain = verification
+            .validate_chain(&trust_store(&root), None)
+            .unwrap();
+        assert_eq!(chain.status, TrustStatus::Trusted);
+        let signing_time = chain.validation_time;
+        Setup {
+            root,
+            signer_cert,
+            responder,
+            not_responder,
+            pdf_file_data,
+            verification,
+            chain,
+            signing_time,
+        }
+    }
+
+    fn check_crl(&self, crl: Vec<u8>) -> RevocationStatus {
+        let validation_data = ValidationData {
+            crls: vec![crl],
+            ..Default::default()
+        };
+        self.check(&validation_data).status
+    }
+
+    fn check_ocsp(&self, ocsp: Vec<u8>) -> RevocationStatus {
+        let validation_data = ValidationData {
+            ocsps: vec![ocsp],
+            ..Default::default()
+        };
+        let revocation = self.check(&validation_data);
+        if revocation.status != RevocationStatus::Unknown {
+            assert_eq!(revocation.source, Some(RevocationSource::Ocsp));
+        }
+        revocation.status
+    }
+
+    fn check(&self, validation_data: &ValidationData) -> CertificateRevocation {
+        self.verification
+            .check_revocation(&self.chain, validation_data)
+            .remove(0)
+    }
+}
+
+fn trust_store(root: &TestCertificate) -> TrustStore {
+    let mut trust_store = TrustStore::new();
+    trust_store.add_anchor(root.certificate.clone());
+    trust_store
+}
+
+fn verify(pdf_file_data: &[u8]) -> SignatureVerification {
+    let verification = PDFSigningDocument::read_from(pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+        .remove(0);
+    assert!(verification.is_valid(), "{:?}", verification.status);
+    verification
+}
+
+#[test]
+fn no_revocation_data() {
+    let setup = Setup::new();
+    // The trust anchor is not checked.
+    let revocations = setup
+        .verification
+        .check_revocation(&setup.chain, &ValidationData::default());
+    assert_eq!(revocations.len(), 1);
+    assert_eq!(revocations[0].status, RevocationStatus::Unknown);
+}
+
+#[test]
+fn crl_in_document_security_store() {
+    let setup = Setup::new();
+    let mut pdf_signing_document =
+        PDFSigningDocument::read_from(&*setup.pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let crl = create_crl(&setup.root, &[], setup.signing_time);
+    pdf_signing_document
+        .add_document_security_store(&ValidationData {
+            certs: vec![],
+            ocsps: vec![],
+            crls: vec![crl],
+        })
+        .unwrap();
+    let document_security_store = pdf_signing_document.document_security_store().unwrap();
+    assert_eq!(document_security_store.crls.len(), 1);
+    let revocation = setup.check(&document_security_store);
+    assert_eq!(revocation.status, RevocationStatus::Good);
+    assert_eq!(revocation.source, Some(RevocationSource::Crl));
+}
+
+#[test]
+fn crl_revoked() {
+    let setup = Setup::new();
+    let signing_time = setup.signing_time;
+    let revoked_before = signing_time - Duration::hours(1);
+    let status = setup.check_crl(create_crl(
+        &setup.root,
+        &[(&setup.signer_cert, revoked_before)],
+        signing_time,
+    ));
+    let RevocationStatus::Revoked {
+        revocation_time, ..
+    } = status
+    else {
+        panic!("Expected revoked: {:?}", status);
+    };
+    assert_eq!(revocation_time.timestamp(), revoked_before.timestamp());
+
+    // Revoked after signing.
+    let status = setup.check_crl(create_crl(
+        &setup.root,
+        &[(&setup.signer_cert, signing_time + Duration::days(1))],
+        signing_time + Duration::days(2),
+    ));
+    assert_eq!(status, RevocationStatus::Good);
+}
+
+#[test]
+fn crl_of_other_issuer_is_ignored() {
+    let setup = Setup::new();
+    let status = setup.check_crl(create_crl(&setup.not_responder, &[], setup.signing_time));
+    assert_eq!(status, RevocationStatus::Unknown);
+}
+
+#[test]
+fn ocsp_responses() {
+    let setup = Setup::new();
+    let signing_time = setup.signing_time;
+    let revoked_before = signing_time - Duration::hours(1);
+
+    // Signed by the issuer.
+    let status = setup.check_ocsp(create_ocsp_response(
+        &setup.root,
+        &setup.root,
+        &setup.signer_cert,
+        None,
+        signing_time,
+    ));
+    assert_eq!(status, RevocationStatus::Good);
+
+    // Signed by a responder of the issuer.
+    let status = setup.check_ocsp(create_ocsp_response(
+        &setup.responder,
+        &setup.root,
+        &setup.signer_cert,
+        Some(revoked_before),
+        signing_time,
+    ));
+    assert!(matches!(status, RevocationStatus::Revoked { .. }));
+
+    // A responder without `OCSPSigning` is not accepted.
+    let status = setup.check_ocsp(create_ocsp_response(
+        &setup.not_responder,
+        &setup.root,
+        &setup.signer_cert,
+        None,
+        signing_time,
+    ));
+    assert_eq!(status, RevocationStatus::Unknown);
+}
+
+/// The OCSP response in the `adbe-revocationInfoArchival` attribute of the signature.
+#[test]
+fn ocsp_in_signature() {
+    let root = TestCertificate::new("Test Root CA", &[CA], None);
+    let TestCertificate {
+        certificate: signer_cert,
+        key: signer_key,
+    } = TestCertificate::new("Dave", &[EMAIL_PROTECTION], Some(&root));
+    let ocsp = create_ocsp_response(&root, &root, &signer_cert, None, Utc::now());
+    let pdf_file_data = sign_with_revocation_info(&signer_cert, &signer_key, ocsp);
+    let verification = verify(&pdf_file_data);
+    assert_eq!(verification.revocation_info.ocsps.len(), 1);
+    let chain = verification
+        .validate_chain(&trust_store(&root), None)
+        .unwrap();
+    let revocations = verification.check_revocation(&chain, &ValidationData::default());
+    assert_eq!(revocations[0].status, RevocationStatus::Good);
+}
+
+/// Sign with a CMS that contains the `ocsp` response in `adbe-revocationInfoArchival`.
+fn sign_with_revocation_info(
+    signer_cert: &CapturedX509Certificate,
+    signer_key: &InMemorySigningKeyPair,
+    ocsp: Vec<u8>,
+) -> Vec<u8> {
+    let certificate_only = Arc::new(SigningKeys::from_certificate(signer_cert.clone()));
+    let prepared = common::read_document()
+        .prepare_signature(&common::user_signature_info("272", certificate_only))
+        .unwrap();
+    let content: Vec<u8> = prepared
+        .byte_range
+        .chunks(2)
+        .flat_map(|range| prepared.pdf[range[0]..range[0] + range[1]].to_vec())
+        .collect();
+
+    let revocation_info = der(0x30, &[&der(0xa1, &[&der(0x30, &[&ocsp])])]);
+    let revocation_info = Constructed::decode(revocation_info.as_slice(), Mode::Der, |cons| {
+        cons.capture_one()
+    })
+    .unwrap();
+    let cms = SignedDataBuilder::default()
+        .content_external(content)
+        .signer(
+            SignerBuilder::new(signer_key, signer_cert.clone()).signed_attribute(
+                common::oid("1.2.840.113583.1.1.8"),
+                vec![AttributeValue::new(revocation_info)],
+            ),
+        )
+        .build_der()
+        .unwrap();
+    PDFSigningDocument::finish_signature(prepared, ExternalSignature::Cms(cms)).unwrap()
+}
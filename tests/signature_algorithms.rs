@@ -0,0 +1,175 @@
+mod common;
+
+use bcder::OctetString;
+use common::TestCertificate;
+use cryptographic_message_syntax::asn1::rfc5652::{SignedData, OID_MESSAGE_DIGEST};
+use pdf_signing::{
+    Error, PDFSigningDocument, PdfSigner, SignatureAlgorithm, SignatureFormat, SigningKeys,
+};
+use std::ops::Deref;
+use std::sync::Arc;
+use x509_certificate::{CapturedX509Certificate, DigestAlgorithm, EcdsaCurve, KeyAlgorithm};
+
+const DIGEST_ALGORITHMS: [DigestAlgorithm; 3] = [
+    DigestAlgorithm::Sha256,
+    DigestAlgorithm::Sha384,
+    DigestAlgorithm::Sha512,
+];
+
+fn sign(
+    signer: Arc<dyn PdfSigner>,
+    signature_algorithm: SignatureAlgorithm,
+) -> Result<Vec<u8>, Error> {
+    let user_info = common::user_signature_info("272", signer)
+        .signature_format(SignatureFormat::EtsiCadesDetached)
+        .signature_algorithm(signature_algorithm);
+    common::read_document().sign_document(vec![user_info])
+}
+
+/// Sign and verify, the `message-digest` has to be the digest of the `ByteRange` content.
+fn check_signature(signer: Arc<dyn PdfSigner>, signature_algorithm: SignatureAlgorithm) {
+    let pdf_file_data = sign(signer, signature_algorithm)
+        .unwrap_or_else(|err| panic!("{}: {:?}", signature_algorithm, err));
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let verifications = document.verify_signatures().unwrap();
+    assert_eq!(verifications.len(), 1);
+    let verification = &verifications[0];
+    assert!(
+        verification.is_valid(),
+        "{}: {:?}",
+        signature_algorithm,
+        verification
+    );
+    assert_eq!(verification.signature_algorithm, Some(signature_algorithm));
+    assert_eq!(
+        verification.digest_algorithm,
+        Some(signature_algorithm.digest_algorithm())
+    );
+
+    let signature = &document.signatures().unwrap()[0];
+    let content: Vec<u8> = signature
+        .byte_range
+        .iter()
+        .flat_map(|(offset, length)| &pdf_file_data[*offset as usize..(offset + length) as usize])
+        .copied()
+        .collect();
+    assert_eq!(
+        message_digest(&signature.contents),
+        signature_algorithm.digest_algorithm().digest_data(&content),
+        "{}",
+        signature_algorithm
+    );
+}
+
+/// The `message-digest` signed attribute of the only signer.
+fn message_digest(cms: &[u8]) -> Vec<u8> {
+    let signed_data = SignedData::decode_ber(cms).unwrap();
+    let attribute = signed_data.signer_infos[0]
+        .signed_attributes
+        .iter()
+        .flat_map(|attributes| attributes.iter())
+        .find(|attribute| attribute.typ == OID_MESSAGE_DIGEST)
+        .unwrap();
+    attribute.values[0]
+        .deref()
+        .clone()
+        .decode(OctetString::take_from)
+        .unwrap()
+        .to_bytes()
+        .to_vec()
+}
+
+fn generated_signer(key_algorithm: KeyAlgorithm) -> Arc<dyn PdfSigner> {
+    TestCertificate::with_key_algorithm("PDF signing test", key_algorithm, &[], None).into_signer()
+}
+
+#[test]
+fn rsa_pkcs1() {
+    let signer = TestCertificate::rsa().into_signer();
+    for digest_algorithm in DIGEST_ALGORITHMS {
+        check_signature(
+            signer.clone(),
+            SignatureAlgorithm::RsaPkcs1(digest_algorithm),
+        );
+    }
+}
+
+#[test]
+fn rsa_pss() {
+    let signer = TestCertificate::rsa().into_signer();
+    for digest_algorithm in DIGEST_ALGORITHMS {
+        check_signature(signer.clone(), SignatureAlgorithm::RsaPss(digest_algorithm));
+    }
+}
+
+#[test]
+fn ecdsa() {
+    check_signature(
+        generated_signer(KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1)),
+        SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha256),
+    );
+    check_signature(
+        generated_signer(KeyAlgorithm::Ecdsa(EcdsaCurve::Secp384r1)),
+        SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha384),
+    );
+}
+
+#[test]
+fn ed25519() {
+    check_signature(
+        generated_signer(KeyAlgorithm::Ed25519),
+        SignatureAlgorithm::Ed25519,
+    );
+}
+
+#[test]
+fn refuse_algorithm_of_other_key() {
+    let signer = generated_signer(KeyAlgorithm::Ed25519);
+    let result = sign(signer, SignatureAlgorithm::RsaPss(DigestAlgorithm::Sha256));
+    assert!(matches!(result, Err(Error::Other(_))));
+}
+
+#[test]
+fn refuse_sha1() {
+    let signer = generated_signer(KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1));
+    let err = sign(signer, SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha1)).unwrap_err();
+    assert!(format!("{:?}", err).contains("not allowed"), "{:?}", err);
+}
+
+/// A signer that claims to support ECDSA with any digest.
+struct AnyDigestSigner(SigningKeys);
+
+impl PdfSigner for AnyDigestSigner {
+    fn signing_certificate(&self) -> &CapturedX509Certificate {
+        self.0.signing_certificate()
+    }
+
+    fn supported_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        DIGEST_ALGORITHMS.map(SignatureAlgorithm::Ecdsa).to_vec()
+    }
+
+    fn sign(&self, data: &[u8], algorithm: SignatureAlgorithm) -> Result<Vec<u8>, Error> {
+        self.0.sign(data, algorithm)
+    }
+}
+
+/// Only the curve and digest pairs that can be verified are used to sign.
+#[test]
+fn refuse_digest_of_other_curve() {
+    let p256 = KeyAlgorithm::Ecdsa(EcdsaCurve::Secp256r1);
+    let p384 = KeyAlgorithm::Ecdsa(EcdsaCurve::Secp384r1);
+    assert!(SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha256).is_supported_by_key(p256));
+    assert!(SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha384).is_supported_by_key(p384));
+    assert!(!SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha512).is_supported_by_key(p256));
+    assert!(!SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha512).is_supported_by_key(p384));
+
+    let TestCertificate { certificate, key } =
+        TestCertificate::with_key_algorithm("PDF signing test", p256, &[], None);
+    let signer = Arc::new(AnyDigestSigner(SigningKeys::new(key, certificate)));
+    let err = sign(signer, SignatureAlgorithm::Ecdsa(DigestAlgorithm::Sha512)).unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("can not be used"),
+        "{:?}",
+        err
+    );
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use pdf_signing::{PDFSigningDocument, Rectangle, UserFormSignatureInfo};
+
+fn rect() -> Rectangle {
+    Rectangle {
+        x1: 50.0,
+        y1: 50.0,
+        x2: 250.0,
+        y2: 120.0,
+    }
+}
+
+fn user_form_info(user_id: &str) -> UserFormSignatureInfo {
+    UserFormSignatureInfo {
+        user_id: user_id.to_owned(),
+    }
+}
+
+#[test]
+fn add_field_to_signed_document() {
+    let pdf_file_data = common::sign(&["272"], common::signer());
+    let mut document =
+        PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let with_field = document
+        .add_signature_field(1, rect(), "Approval", user_form_info("500"))
+        .unwrap();
+    // The field is added in an incremental update, the existing signature stays valid.
+    assert!(with_field.starts_with(&pdf_file_data));
+    assert_eq!(common::verify_all(&with_field), 1);
+
+    let pdf_file_data = document
+        .sign_document(vec![common::user_signature_info("500", common::signer())])
+        .unwrap();
+    assert_eq!(common::verify_all(&pdf_file_data), 2);
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let signature = document
+        .signatures()
+        .unwrap()
+        .into_iter()
+        .find(|signature| signature.rect.as_ref() == Some(&rect()))
+        .unwrap();
+    assert_eq!(signature.page, Some(1));
+}
+
+#[test]
+fn refuse_invalid_fields() {
+    let mut document = common::read_document();
+    let with_field = document
+        .add_signature_field(1, rect(), "Approval", user_form_info("500"))
+        .unwrap();
+    // Only one field per user.
+    assert!(document
+        .add_signature_field(1, rect(), "Approval", user_form_info("500"))
+        .is_err());
+    // Pages are numbered from 1.
+    for page in [0, 99] {
+        assert!(document
+            .add_signature_field(page, rect(), "Approval", user_form_info("501"))
+            .is_err());
+    }
+    // Failed calls do not change the document.
+    assert_eq!(
+        document.get_incr_document_ref().get_prev_documents_bytes(),
+        &with_field[..]
+    );
+}
+
+#[test]
+fn add_field_to_document_without_form() {
+    let pdf_data = common::documents::without_form();
+    let mut document = PDFSigningDocument::read_from(&*pdf_data, "no-form.pdf".to_owned()).unwrap();
+    document
+        .add_signature_field(2, rect(), "Second page", user_form_info("600"))
+        .unwrap();
+    let pdf_file_data = document
+        .sign_document(vec![common::user_signature_info("600", common::signer())])
+        .unwrap();
+    assert_eq!(common::verify_all(&pdf_file_data), 1);
+    let document = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    assert_eq!(document.signatures().unwrap()[0].page, Some(2));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use cryptographic_message_syntax::SignedData;
+use pdf_signing::{
+    lopdf::{self, Object},
+    IncrementalDocument, PDFSigningDocument,
+};
+
+#[test]
+fn list_signatures() {
+    let signing_start = chrono::Utc::now();
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+    let signatures = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .signatures()
+        .unwrap();
+    assert_eq!(signatures.len(), 2);
+    for signature in &signatures {
+        assert_eq!(signature.page, Some(1));
+        assert!(signature.rect.is_some());
+        assert_eq!(signature.filter, "Adobe.PPKLite");
+        assert_eq!(signature.sub_filter.as_deref(), Some("adbe.pkcs7.detached"));
+        assert_eq!(signature.name.as_deref(), Some("Charlie"));
+        // `M` is written without fractions of a second.
+        let signing_time = signature.signing_time.unwrap();
+        assert!(signing_time >= signing_start - chrono::Duration::seconds(1));
+        assert!(signing_time <= chrono::Utc::now());
+        let app = signature
+            .build_properties
+            .as_ref()
+            .and_then(|build_properties| build_properties.app.as_ref())
+            .unwrap();
+        assert_eq!(app.name.as_deref(), Some("Rust PDF Signing"));
+        assert_eq!(app.r_ex.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(signature.byte_range.len(), 2);
+        // `Contents` without the padding is the CMS.
+        SignedData::parse_ber(&signature.contents).unwrap();
+    }
+}
+
+/// `Reason`, `Location` and `M` as written by other applications.
+#[test]
+fn read_entries_of_other_applications() {
+    let pdf_file_data = common::sign(&["272"], common::signer());
+
+    // Add the entries in a new incremental update.
+    let document = lopdf::Document::load_mem(&pdf_file_data).unwrap();
+    let signature_id = signature_dictionary_id(&document);
+    let mut incremental_document = IncrementalDocument::create_from(pdf_file_data, document);
+    incremental_document
+        .opt_clone_object_to_new_document(signature_id)
+        .unwrap();
+    let signature_dict = incremental_document
+        .new_document
+        .get_object_mut(signature_id)
+        .and_then(Object::as_dict_mut)
+        .unwrap();
+    signature_dict.set("Reason", lopdf::text_string("Approved ✓"));
+    signature_dict.set("Location", lopdf::text_string("Brussels"));
+    signature_dict.set(
+        "M",
+        Object::string_literal("D:20240229133000-05'30'".as_bytes()),
+    );
+    let mut changed = vec![];
+    incremental_document.save_to(&mut changed).unwrap();
+
+    let signature = PDFSigningDocument::read_from(&*changed, "changed.pdf".to_owned())
+        .unwrap()
+        .signatures()
+        .unwrap()
+        .remove(0);
+    assert_eq!(signature.reason.as_deref(), Some("Approved ✓"));
+    assert_eq!(signature.location.as_deref(), Some("Brussels"));
+    assert_eq!(
+        signature.signing_time.unwrap().to_rfc3339(),
+        "2024-02-29T19:00:00+00:00"
+    );
+}
+
+/// The signature dictionary (`V`) of the first signed field.
+fn signature_dictionary_id(document: &lopdf::Document) -> lopdf::ObjectId {
+    let root = document.catalog().unwrap();
+    let acro_form = match root.get(b"AcroForm").unwrap() {
+        Object::Reference(id) => document.get_dictionary(*id).unwrap(),
+        object => object.as_dict().unwrap(),
+    };
+    acro_form
+        .get(b"Fields")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .find_map(|field| {
+            let field = document.get_dictionary(field.as_reference().ok()?).ok()?;
+            field.get(b"V").ok()?.as_reference().ok()
+        })
+        .unwrap()
+}
@@ -0,0 +1,18 @@
+mod common;
+
+use pdf_signing::PDFSigningDocument;
+
+/// `M` and the `signing-time` attribute of the CMS are the same time.
+#[test]
+fn signing_time_matches_cms() {
+    let signed = common::read_document()
+        .sign_document(vec![common::user_signature_info("272", common::signer())])
+        .unwrap();
+    let document = PDFSigningDocument::read_from(&*signed, "signed.pdf".to_owned()).unwrap();
+    let signatures = document.signatures().unwrap();
+    let verifications = document.verify_signatures().unwrap();
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(verifications.len(), 1);
+    assert!(signatures[0].signing_time.is_some());
+    assert_eq!(signatures[0].signing_time, verifications[0].signing_time);
+}
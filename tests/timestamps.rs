@@ -0,0 +1,160 @@
+mod common;
+
+use bcder::{decode::Constructed, Mode};
+use common::{TestCertificate, DIGITAL_SIGNATURE, TIME_STAMPING};
+use cryptographic_message_syntax::{asn1::rfc3161::TstInfo, SignedData};
+use pdf_signing::{
+    Error, LocalTimestampAuthority, PDFSigningDocument, SignatureFormat, SignatureStatus,
+    SignatureVerification, SigningKeys, TimestampKind, TimestampProvider, TrustStatus, TrustStore,
+};
+use std::sync::Arc;
+use x509_certificate::DigestAlgorithm;
+
+fn timestamp_authority() -> LocalTimestampAuthority {
+    let (certificate, key) = common::certificate();
+    LocalTimestampAuthority::new(Arc::new(SigningKeys::new(key, certificate)))
+}
+
+/// Time-stamps a different message, so the message imprint does not match.
+struct WrongImprint(LocalTimestampAuthority);
+
+impl TimestampProvider for WrongImprint {
+    fn get_timestamp_token(
+        &self,
+        message_digest: &[u8],
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Vec<u8>, Error> {
+        let other_digest = digest_algorithm.digest_data(message_digest);
+        self.0.get_timestamp_token(&other_digest, digest_algorithm)
+    }
+}
+
+fn timestamp_certificate() -> TestCertificate {
+    TestCertificate::new("Test TSA", &[DIGITAL_SIGNATURE, TIME_STAMPING], None)
+}
+
+fn sign_cades(timestamp_provider: Arc<dyn TimestampProvider>) -> Vec<u8> {
+    let user_info = common::user_signature_info("272", common::signer())
+        .signature_format(SignatureFormat::EtsiCadesDetached)
+        .timestamp_provider(timestamp_provider);
+    common::read_document()
+        .sign_document(vec![user_info])
+        .unwrap()
+}
+
+fn verify(pdf_file_data: &[u8]) -> Vec<SignatureVerification> {
+    PDFSigningDocument::read_from(pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+}
+
+#[test]
+fn local_timestamp_token() {
+    let message_digest = DigestAlgorithm::Sha256.digest_data(b"signature value");
+    let token = timestamp_authority()
+        .get_timestamp_token(&message_digest, DigestAlgorithm::Sha256)
+        .unwrap();
+
+    let signed_data = SignedData::parse_ber(&token).unwrap();
+    for signer in signed_data.signers() {
+        signer
+            .verify_signature_with_signed_data(&signed_data)
+            .unwrap();
+        signer
+            .verify_message_digest_with_signed_data(&signed_data)
+            .unwrap();
+    }
+    let tst_info = Constructed::decode(
+        signed_data.signed_content().unwrap(),
+        Mode::Der,
+        TstInfo::take_from,
+    )
+    .unwrap();
+    let imprint = &tst_info.message_imprint;
+    assert_eq!(
+        DigestAlgorithm::try_from(&imprint.hash_algorithm).unwrap(),
+        DigestAlgorithm::Sha256
+    );
+    assert_eq!(imprint.hashed_message.to_bytes(), message_digest);
+}
+
+#[test]
+fn sign_with_local_timestamp() {
+    let user_info = common::user_signature_info("272", common::signer())
+        .timestamp_provider(Arc::new(timestamp_authority()));
+    let pdf_file_data = common::read_document()
+        .sign_document(vec![user_info])
+        .unwrap();
+
+    let verification = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+        .remove(0);
+    assert!(verification.is_valid());
+    assert_eq!(verification.timestamps.len(), 1);
+    assert!(verification.timestamps[0].is_valid());
+}
+
+#[test]
+fn signature_timestamp_of_trusted_authority() {
+    let tsa = timestamp_certificate();
+    let tsa_cert = tsa.certificate.clone();
+    let mut trust_store = TrustStore::new();
+    trust_store.add_anchor(tsa_cert.clone());
+
+    let signing_start = chrono::Utc::now();
+    let pdf_file_data = sign_cades(Arc::new(LocalTimestampAuthority::new(tsa.into_signer())));
+    let verification = verify(&pdf_file_data).remove(0);
+    assert!(verification.is_valid());
+    assert_eq!(verification.timestamps.len(), 1);
+    let timestamp = &verification.timestamps[0];
+    assert_eq!(timestamp.kind, TimestampKind::Signature);
+    assert_eq!(timestamp.status, SignatureStatus::Valid);
+    assert_eq!(timestamp.tsa_certificate.as_ref(), Some(&tsa_cert));
+    // `genTime` is written without fractions of a second.
+    let gen_time = timestamp.gen_time.unwrap();
+    assert!(gen_time >= signing_start - chrono::Duration::seconds(1));
+    assert_eq!(verification.timestamp_time(), Some(gen_time));
+    let validation = timestamp.validate_chain(&trust_store, None).unwrap();
+    assert_eq!(validation.status, TrustStatus::Trusted, "{:?}", validation);
+}
+
+#[test]
+fn document_timestamp() {
+    let tsa = LocalTimestampAuthority::new(timestamp_certificate().into_signer());
+    let pdf_file_data = sign_cades(Arc::new(tsa.clone()));
+    let signature_gen_time = verify(&pdf_file_data)[0].timestamps[0].gen_time.unwrap();
+
+    let mut document =
+        PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned()).unwrap();
+    let pdf_file_data = document.add_document_timestamp(&tsa).unwrap();
+    let verifications = verify(&pdf_file_data);
+    assert_eq!(verifications.len(), 2);
+    assert!(verifications[0].is_valid());
+    let document_timestamp = verifications
+        .iter()
+        .find(|verification| verification.sub_filter.as_deref() == Some("ETSI.RFC3161"))
+        .unwrap();
+    assert!(document_timestamp.is_valid());
+    assert_eq!(document_timestamp.timestamps.len(), 1);
+    let timestamp = &document_timestamp.timestamps[0];
+    assert_eq!(timestamp.kind, TimestampKind::Document);
+    assert_eq!(timestamp.status, SignatureStatus::Valid);
+    assert!(timestamp.gen_time.unwrap() >= signature_gen_time);
+}
+
+#[test]
+fn detect_wrong_message_imprint() {
+    let tsa = LocalTimestampAuthority::new(timestamp_certificate().into_signer());
+    let pdf_file_data = sign_cades(Arc::new(WrongImprint(tsa)));
+    let verification = verify(&pdf_file_data).remove(0);
+    // The signature itself is still valid.
+    assert!(verification.is_valid());
+    assert_eq!(
+        verification.timestamps[0].status,
+        SignatureStatus::DigestMismatch
+    );
+    assert_eq!(verification.timestamp_time(), None);
+}
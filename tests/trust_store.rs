@@ -0,0 +1,163 @@
+mod common;
+
+use common::{
+    TestCertificate, CA, CA_NO_SUB_CA, DIGITAL_SIGNATURE, EMAIL_PROTECTION, KEY_CERT_SIGN,
+    KEY_ENCIPHERMENT, NOT_CA,
+};
+use pdf_signing::{
+    ChainProblem, KeyPurpose, PDFSigningDocument, PdfSigner, SignatureVerification, SigningKeys,
+    TrustStatus, TrustStore,
+};
+use std::sync::Arc;
+
+/// A root CA, an intermediate CA that can not have CAs below it and a signer.
+fn chain() -> (TestCertificate, TestCertificate, TestCertificate) {
+    let root = TestCertificate::new("Test Root CA", &[CA, KEY_CERT_SIGN], None);
+    let intermediate = TestCertificate::new(
+        "Test Intermediate CA",
+        &[CA_NO_SUB_CA, KEY_CERT_SIGN],
+        Some(&root),
+    );
+    let signer = TestCertificate::new(
+        "Charlie",
+        &[NOT_CA, DIGITAL_SIGNATURE, EMAIL_PROTECTION],
+        Some(&intermediate),
+    );
+    (root, intermediate, signer)
+}
+
+/// Sign with the intermediate included in the signature.
+fn sign_and_verify(
+    intermediate: &TestCertificate,
+    signer: TestCertificate,
+) -> SignatureVerification {
+    let signer: Arc<dyn PdfSigner> = Arc::new(
+        SigningKeys::new(signer.key, signer.certificate)
+            .certificate_chain([intermediate.certificate.clone()]),
+    );
+    let pdf_file_data = common::sign(&["272"], signer);
+    let verification = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+        .remove(0);
+    assert!(verification.is_valid());
+    assert!(verification.signing_time.is_some());
+    verification
+}
+
+#[test]
+fn validate_signature_chain() {
+    let (root, intermediate, signer) = chain();
+    let signer_cert = signer.certificate.clone();
+    let verification = sign_and_verify(&intermediate, signer);
+
+    let mut trust_store = TrustStore::new();
+    trust_store
+        .add_anchors_pem(root.certificate.encode_pem().as_bytes())
+        .unwrap();
+    let validation = verification.validate_chain(&trust_store, None).unwrap();
+    assert_eq!(validation.status, TrustStatus::Trusted, "{:?}", validation);
+    assert_eq!(
+        validation.chain,
+        [
+            signer_cert.clone(),
+            intermediate.certificate.clone(),
+            root.certificate.clone()
+        ]
+    );
+
+    let validation = verification
+        .validate_chain(&TrustStore::new(), None)
+        .unwrap();
+    assert_eq!(validation.status, TrustStatus::Untrusted);
+    assert_eq!(validation.problems, [ChainProblem::NoTrustAnchor]);
+
+    let validation_time = signer_cert.validity_not_after() + chrono::Duration::days(1);
+    let validation = verification
+        .validate_chain(&trust_store, Some(validation_time))
+        .unwrap();
+    assert_eq!(validation.status, TrustStatus::Expired);
+}
+
+/// A trust store with the root as anchor and the intermediate.
+fn trust_store(root: &TestCertificate, intermediate: &TestCertificate) -> TrustStore {
+    let mut trust_store = TrustStore::new();
+    trust_store
+        .add_anchor_der(root.certificate.encode_der().unwrap())
+        .unwrap();
+    trust_store.add_intermediate(intermediate.certificate.clone());
+    trust_store
+}
+
+#[test]
+fn intermediate_from_trust_store() {
+    let (root, intermediate, signer) = chain();
+    let trust_store = trust_store(&root, &intermediate);
+    let validation = trust_store.validate(
+        &signer.certificate,
+        &[],
+        chrono::Utc::now(),
+        KeyPurpose::DocumentSigning,
+    );
+    assert_eq!(validation.status, TrustStatus::Trusted);
+    // But is not a time-stamping authority.
+    let validation = trust_store.validate(
+        &signer.certificate,
+        &[],
+        chrono::Utc::now(),
+        KeyPurpose::TimeStamping,
+    );
+    assert_eq!(
+        validation.problems,
+        [ChainProblem::ExtendedKeyUsage("Charlie".to_owned())]
+    );
+}
+
+#[test]
+fn refuse_key_usage() {
+    let (root, intermediate, _) = chain();
+    let trust_store = trust_store(&root, &intermediate);
+    // A certificate that is not allowed to sign.
+    let encipherment_cert = TestCertificate::new(
+        "Encipherment only",
+        &[NOT_CA, KEY_ENCIPHERMENT],
+        Some(&intermediate),
+    )
+    .certificate;
+    let validation = trust_store.validate(
+        &encipherment_cert,
+        &[],
+        chrono::Utc::now(),
+        KeyPurpose::DocumentSigning,
+    );
+    assert_eq!(validation.status, TrustStatus::Untrusted);
+    assert_eq!(
+        validation.problems,
+        [ChainProblem::KeyUsage("Encipherment only".to_owned())]
+    );
+}
+
+#[test]
+fn refuse_path_length() {
+    let (root, intermediate, _) = chain();
+    let trust_store = trust_store(&root, &intermediate);
+    // The intermediate is not allowed to have another CA below it.
+    let sub_ca = TestCertificate::new("Test Sub CA", &[CA, KEY_CERT_SIGN], Some(&intermediate));
+    let sub_ca_signer =
+        TestCertificate::new("Sub CA signer", &[NOT_CA, DIGITAL_SIGNATURE], Some(&sub_ca))
+            .certificate;
+    let validation = trust_store.validate(
+        &sub_ca_signer,
+        &[sub_ca.certificate],
+        chrono::Utc::now(),
+        KeyPurpose::DocumentSigning,
+    );
+    assert_eq!(validation.status, TrustStatus::Untrusted);
+    assert_eq!(
+        validation.problems,
+        [ChainProblem::PathLengthExceeded(
+            "Test Intermediate CA".to_owned()
+        )]
+    );
+}
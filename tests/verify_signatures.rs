@@ -0,0 +1,114 @@
+mod common;
+
+use common::TestCertificate;
+use pdf_signing::{
+    lopdf::{self, Object, Stream},
+    IncrementalDocument, ModificationKind, PDFSigningDocument, SignatureStatus,
+    SignatureVerification,
+};
+
+fn verify(pdf_file_data: &[u8]) -> Vec<SignatureVerification> {
+    PDFSigningDocument::read_from(pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .verify_signatures()
+        .unwrap()
+}
+
+fn statuses(pdf_file_data: &[u8]) -> Vec<SignatureStatus> {
+    verify(pdf_file_data)
+        .into_iter()
+        .map(|verification| verification.status)
+        .collect()
+}
+
+#[test]
+fn verify_valid_signatures() {
+    let test_certificate = TestCertificate::new("PDF signing test", &[], None);
+    let certificate = test_certificate.certificate.clone();
+    let pdf_file_data = common::sign(&["272", "292"], test_certificate.into_signer());
+
+    let verifications = verify(&pdf_file_data);
+    assert_eq!(verifications.len(), 2);
+    for verification in verifications {
+        assert_eq!(verification.status, SignatureStatus::Valid);
+        assert!(verification.is_valid(), "{:?}", verification);
+        assert_eq!(verification.signer_certificate.as_ref(), Some(&certificate));
+    }
+}
+
+#[test]
+fn detect_changed_content() {
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+    // Change a byte covered by the second signature only.
+    let (offset, length) = verify(&pdf_file_data)[0].byte_range[1];
+    let mut changed = pdf_file_data;
+    changed[(offset + length) as usize + 10] ^= 0x01;
+    assert_eq!(
+        statuses(&changed),
+        [SignatureStatus::Valid, SignatureStatus::DigestMismatch]
+    );
+}
+
+#[test]
+fn detect_changed_signature() {
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+    let verification = verify(&pdf_file_data).remove(0);
+    let signature = PDFSigningDocument::read_from(&*pdf_file_data, "signed.pdf".to_owned())
+        .unwrap()
+        .signatures()
+        .unwrap()
+        .remove(0);
+
+    // Change the last hex digit of the signature value, it is at the end of the CMS.
+    let contents_start = verification.byte_range[0].1 as usize + 1;
+    let last_hex_digit = contents_start + 2 * signature.contents.len() - 1;
+    let mut changed = pdf_file_data;
+    changed[last_hex_digit] = if changed[last_hex_digit] == b'0' {
+        b'1'
+    } else {
+        b'0'
+    };
+    assert_eq!(
+        statuses(&changed),
+        [
+            SignatureStatus::InvalidSignature,
+            SignatureStatus::DigestMismatch
+        ]
+    );
+}
+
+#[test]
+fn detect_changes_after_signing() {
+    let pdf_file_data = common::sign(&["272", "292"], common::signer());
+
+    // Replace the content of the first page in a new incremental update.
+    let document = lopdf::Document::load_mem(&pdf_file_data).unwrap();
+    let page_id = *document.get_pages().values().next().unwrap();
+    let contents_id = match document.get_dictionary(page_id).unwrap().get(b"Contents") {
+        Ok(Object::Reference(contents_id)) => *contents_id,
+        Ok(Object::Array(contents)) => contents[0].as_reference().unwrap(),
+        _ => panic!("Page has no content stream."),
+    };
+    let mut incremental_document = IncrementalDocument::create_from(pdf_file_data, document);
+    incremental_document.new_document.objects.insert(
+        contents_id,
+        Object::Stream(Stream::new(
+            lopdf::Dictionary::new(),
+            b"BT /F1 24 Tf 72 720 Td (Changed) Tj ET".to_vec(),
+        )),
+    );
+    let mut changed = vec![];
+    incremental_document.save_to(&mut changed).unwrap();
+
+    let verifications = verify(&changed);
+    assert_eq!(verifications.len(), 2);
+    for verification in &verifications {
+        // The signed revisions are not changed, but the change after them is not allowed.
+        assert_eq!(verification.status, SignatureStatus::Valid);
+        assert!(!verification.is_valid());
+        let modifications = verification.modifications.as_ref().unwrap();
+        assert!(modifications.modifications.iter().any(|modification| {
+            modification.kind == ModificationKind::ContentStream && !modification.allowed
+        }));
+    }
+}